@@ -1,12 +1,36 @@
+use crate::cli::Context;
+use crate::config::{config_path, Config};
 use crate::error::{CredentialError, CredentialResult};
-use crate::model::CredentialEntry;
+use crate::model::{
+    favorites_first, is_sensitive_field, parse_tags, same_service, sort_entries, ChangeSummary,
+    ConflictStrategy, CredentialDatabase, CredentialEntry, SearchField, SortKey,
+};
+use crate::storage::export::env_var_prefix;
 use crate::storage::{
-    backup_database, database_exists, delete_database, get_database_info, load_database,
-    save_database,
+    backup_database, database_exists, delete_database, export_database, export_entry,
+    get_database_info, init_database, load_database, save_database, EntryFormat, ExportFormat,
+    ImportFormat, MergeStrategy,
+};
+use crate::util::clipboard;
+use crate::util::link::normalize_url;
+use crate::util::passphrase::{self, DEFAULT_SEPARATOR};
+use crate::util::policy::check_policy;
+use crate::util::progress;
+use crate::util::screen;
+use crate::util::strength::{Strength, MIN_ACCEPTABLE_STRENGTH};
+use crate::util::style::{self, errln, outln};
+use crate::util::table::render_table;
+use crate::util::time::{now_unix, parse_date, parse_duration, unix_secs};
+use crate::util::totp::{self, OtpAuth, TotpAlgorithm, TotpParams};
+use crate::util::{
+    estimate_strength, format_timestamp_local, generate_password, ClipboardBackend,
+    PasswordOptions, SystemClipboard, TimeFormat,
 };
-use crate::util::format_timestamp_local;
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
 use dialoguer::{Confirm, Input, Password};
+use std::io::{IsTerminal, Read};
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(
@@ -15,260 +39,3655 @@ use dialoguer::{Confirm, Input, Password};
     version
 )]
 pub struct Cli {
+    /// Emit machine-readable JSON instead of decorated text
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// How to display timestamps
+    #[arg(long, global = true, value_enum)]
+    pub time_format: Option<TimeFormat>,
+    /// Disable emoji and colour in output (also honours NO_COLOR)
+    #[arg(long, global = true)]
+    pub no_color: bool,
+    /// Show what a command would change without saving anything
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+    /// Refuse any command that would modify the vault (also CRAB_READONLY=1)
+    #[arg(long, global = true)]
+    pub read_only: bool,
     #[command(subcommand)]
     pub command: Commands,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GetField {
+    Service,
+    Account,
+    Secret,
+    Created,
+    Updated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CopyField {
+    Secret,
+    Account,
+    Url,
+    Notes,
+}
+
+#[derive(Args)]
+pub struct ListArgs {
+    /// Only show entries with this tag
+    #[arg(short, long)]
+    tag: Option<String>,
+    /// Only show entries updated within this span (e.g. 24h, 7d, 2w)
+    #[arg(long, value_parser = parse_since)]
+    since: Option<Duration>,
+    /// Only show entries last updated before this date (YYYY-MM-DD or RFC 3339)
+    #[arg(long, value_parser = parse_before)]
+    before: Option<u64>,
+    /// Order entries by this key
+    #[arg(long, value_enum, default_value_t = SortKey::Name)]
+    sort: SortKey,
+    /// Reverse the sort order
+    #[arg(short, long)]
+    reverse: bool,
+    /// Show a table with account and last-updated time
+    #[arg(short, long)]
+    long: bool,
+    /// Only show favorites
+    #[arg(long)]
+    favorites: bool,
+    /// Print only the number of matching entries
+    #[arg(short, long, conflicts_with = "long")]
+    count: bool,
+}
+
+impl ListArgs {
+    fn has_filters(&self) -> bool {
+        self.tag.is_some() || self.since.is_some() || self.before.is_some() || self.favorites
+    }
+}
+
+#[derive(Args)]
+pub struct EditArgs {
+    /// Service to edit (pick interactively when omitted)
+    service: Option<String>,
+    /// Account to edit when the service holds several
+    #[arg(long, value_name = "ACCOUNT")]
+    which: Option<String>,
+    /// Rename the service (skips the interactive prompts)
+    #[arg(long = "service", value_name = "NAME")]
+    new_service: Option<String>,
+    /// Set the account (skips the interactive prompts)
+    #[arg(long)]
+    account: Option<String>,
+    /// Set the URL, or clear it with an empty value (skips the interactive prompts)
+    #[arg(long)]
+    url: Option<String>,
+    /// Set the notes, or remove them with an empty value (skips the interactive prompts)
+    #[arg(long)]
+    notes: Option<String>,
+    /// Read a new secret from stdin (skips the interactive prompts)
+    #[arg(long)]
+    secret_stdin: bool,
+    /// Remind to rotate the secret after this many days, 0 to stop (skips the interactive prompts)
+    #[arg(long, value_name = "DAYS")]
+    rotate_after: Option<u32>,
+    /// Hash used for the entry's TOTP codes (skips the interactive prompts)
+    #[arg(long, value_enum, value_name = "ALGORITHM")]
+    totp_algorithm: Option<TotpAlgorithm>,
+    /// Number of digits in the entry's TOTP codes, 6 to 8 (skips the interactive prompts)
+    #[arg(long, value_name = "DIGITS")]
+    totp_digits: Option<usize>,
+    /// Seconds each TOTP code is valid for (skips the interactive prompts)
+    #[arg(long, value_name = "SECS")]
+    totp_period: Option<u64>,
+    /// Save a new secret even if it breaks the configured password policy
+    #[arg(long)]
+    force: bool,
+}
+
+impl EditArgs {
+    fn has_changes(&self) -> bool {
+        self.new_service.is_some()
+            || self.account.is_some()
+            || self.url.is_some()
+            || self.notes.is_some()
+            || self.secret_stdin
+            || self.rotate_after.is_some()
+            || self.has_totp_changes()
+    }
+
+    fn has_totp_changes(&self) -> bool {
+        self.totp_algorithm.is_some() || self.totp_digits.is_some() || self.totp_period.is_some()
+    }
+}
+
+#[derive(Args)]
+pub struct GenArgs {
+    /// Password length
+    #[arg(short, long)]
+    length: Option<usize>,
+    /// Use only letters and digits
+    #[arg(long)]
+    no_symbols: bool,
+    /// Leave out uppercase letters
+    #[arg(long)]
+    no_uppercase: bool,
+    /// Leave out lowercase letters
+    #[arg(long)]
+    no_lowercase: bool,
+    /// Leave out digits
+    #[arg(long)]
+    no_digits: bool,
+    /// Leave out characters that are easy to misread (I, l, 1, O, 0)
+    #[arg(long)]
+    no_ambiguous: bool,
+    /// Generate a passphrase of this many words instead of a password
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = ["length", "no_symbols", "no_uppercase", "no_lowercase", "no_digits", "no_ambiguous"]
+    )]
+    words: Option<usize>,
+    /// Text placed between passphrase words
+    #[arg(long, default_value = DEFAULT_SEPARATOR, requires = "words")]
+    separator: String,
+    /// Word list for --words: one word per line, or the EFF diceware format
+    #[arg(long, value_name = "PATH", requires = "words")]
+    wordlist: Option<PathBuf>,
+    /// Number of passwords to print
+    #[arg(short = 'n', long, default_value_t = 1, conflicts_with_all = ["copy", "save"])]
+    count: usize,
+    /// Copy the password to the clipboard instead of printing it
+    #[arg(short, long)]
+    copy: bool,
+    /// Store the password as a new entry for this service instead of printing it
+    #[arg(long, value_name = "SERVICE")]
+    save: Option<String>,
+    /// Account for the saved entry (prompted for when omitted)
+    #[arg(short, long, requires = "save")]
+    account: Option<String>,
+}
+
+impl GenArgs {
+    fn password_options(&self) -> PasswordOptions {
+        PasswordOptions {
+            lowercase: !self.no_lowercase,
+            uppercase: !self.no_uppercase,
+            digits: !self.no_digits,
+            symbols: !self.no_symbols,
+            exclude_ambiguous: self.no_ambiguous,
+        }
+    }
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
+    Init,
     Add {
         #[arg(short, long)]
         service: Option<String>,
         #[arg(short, long)]
         account: Option<String>,
+        /// Read the secret from stdin instead of prompting (requires --service and --account)
+        #[arg(long)]
+        secret_stdin: bool,
+        /// Start from an existing entry's tags, URL, and notes (never its secret)
+        #[arg(long, value_name = "SERVICE")]
+        from: Option<String>,
+        /// Save the secret even if it breaks the configured password policy
+        #[arg(long)]
+        force: bool,
+        /// Take the 2FA secret and settings from an otpauth:// URI; its issuer
+        /// and account fill in --service and --account when omitted
+        #[arg(long, value_name = "URI")]
+        otpauth: Option<String>,
+        /// Read the otpauth:// URI from a QR code image (PNG or JPEG)
+        #[arg(long, value_name = "PATH", conflicts_with = "otpauth")]
+        otpauth_qr: Option<PathBuf>,
     },
     Get {
+        /// Services to show (pick one interactively when omitted)
+        services: Vec<String>,
+        /// Account to use when the service holds several
+        #[arg(short, long)]
+        account: Option<String>,
+        /// Print the secret instead of masking it
+        #[arg(long)]
+        show: bool,
+        /// Print only this field, undecorated
+        #[arg(long, value_enum)]
+        field: Option<GetField>,
+        /// Show the secret as a QR code (requires --show)
+        #[arg(long, conflicts_with = "field")]
+        qr: bool,
+        /// With --qr, encode the TOTP provisioning URI for authenticator apps
+        #[arg(long, requires = "qr")]
+        totp: bool,
+        /// With --qr, encode a Wi-Fi join code using the account as the network name
+        #[arg(long, requires = "qr", conflicts_with = "totp")]
+        wifi: bool,
+        /// Clear the terminal this many seconds after revealing (0 leaves it)
+        #[arg(long, value_name = "SECS")]
+        reveal_timeout: Option<u64>,
+        /// Write the entry to this file (mode 0600) instead of the terminal
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["field", "qr"])]
+        out: Option<PathBuf>,
+        /// Format for --out: the entry as JSON, or shell export lines
+        #[arg(long, value_enum, default_value_t = EntryFormat::Json, requires = "out")]
+        format: EntryFormat,
+        /// Overwrite the --out file if it already exists
+        #[arg(long, requires = "out")]
+        force: bool,
+        /// Copy the secret to the clipboard instead of printing it
+        #[arg(short, long, conflicts_with_all = ["show", "field", "qr", "out"])]
+        copy: bool,
+    },
+    Copy {
+        /// Service to copy from (pick interactively when omitted)
+        service: Option<String>,
+        /// Account to use when the service holds several
+        #[arg(short, long)]
+        account: Option<String>,
+        /// Field to copy (notes reads the custom field named "notes")
+        #[arg(long, value_enum, default_value = "secret")]
+        field: CopyField,
+        /// Seconds before the clipboard is cleared (0 keeps the secret on the clipboard)
+        #[arg(short, long)]
+        timeout: Option<u64>,
+    },
+    List(ListArgs),
+    Search {
+        query: String,
+        /// Treat the query as a regular expression
+        #[arg(long)]
+        regex: bool,
+        /// Field the regular expression is matched against
+        #[arg(long = "in", value_enum, default_value_t = SearchField::All, requires = "regex")]
+        field: SearchField,
+    },
+    Accounts {
+        /// Part of the service name, e.g. "aws"
+        pattern: String,
+    },
+    Env {
+        /// Services whose secrets are passed as CRAB_<SERVICE>
+        #[arg(required = true)]
+        services: Vec<String>,
+        /// Command to run, after `--`
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
+    Frequent {
+        /// Number of entries to show
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+        /// Order by last use instead of use count
+        #[arg(short, long)]
+        recent: bool,
+    },
+    Open {
         service: String,
+        /// Account to use when the service holds several
+        #[arg(short, long)]
+        account: Option<String>,
     },
-    List,
-    Edit {
+    Totp {
         service: String,
+        /// Account to use when the service holds several
+        #[arg(short, long)]
+        account: Option<String>,
+        /// Copy the code to the clipboard instead of printing it
+        #[arg(short, long)]
+        copy: bool,
+    },
+    Audit,
+    CheckPwned {
+        /// Skip the confirmation before contacting the breach database
+        #[arg(short, long)]
+        yes: bool,
+        /// Refuse to contact the breach database, like `offline = true` in the config
+        #[arg(long)]
+        offline: bool,
+    },
+    Edit(EditArgs),
+    History {
+        service: String,
+        /// Account to use when the service holds several
+        #[arg(short, long)]
+        account: Option<String>,
+        /// Print previous secrets instead of masking them
+        #[arg(long)]
+        show: bool,
     },
     Remove {
+        /// Services to remove (pick one interactively when omitted)
+        services: Vec<String>,
+        /// Account to use when the service holds several
+        #[arg(short, long)]
+        account: Option<String>,
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+        /// Delete for good instead of moving to the trash
+        #[arg(long)]
+        permanent: bool,
+    },
+    Trash {
+        #[command(subcommand)]
+        action: TrashAction,
+    },
+    Tag {
+        #[command(subcommand)]
+        action: TagAction,
+    },
+    Pin {
+        service: String,
+    },
+    Unpin {
+        service: String,
+    },
+    Rename {
+        old: String,
+        new: String,
+        /// Replace an existing entry named <NEW>
+        #[arg(long)]
+        force: bool,
+    },
+    Rotate {
         service: String,
+        /// Account to use when the service holds several
+        #[arg(short, long)]
+        account: Option<String>,
+        /// Password length
+        #[arg(short, long)]
+        length: Option<usize>,
+        /// Use only letters and digits
+        #[arg(long)]
+        no_symbols: bool,
+        /// Also print the new secret once
+        #[arg(long)]
+        show: bool,
+        /// Save the new secret even if it breaks the configured password policy
+        #[arg(long)]
+        force: bool,
+    },
+    #[command(alias = "generate")]
+    Gen(GenArgs),
+    Export {
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Skip the plaintext confirmation prompt
+        #[arg(long)]
+        force: bool,
+    },
+    Import {
+        path: PathBuf,
+        /// Input format (detected from the file extension and contents when omitted)
+        #[arg(short, long, value_enum)]
+        format: Option<ImportFormat>,
+        /// What to do when a service already exists
+        #[arg(short, long, visible_alias = "on-conflict", value_enum, default_value_t = MergeStrategy::Skip)]
+        merge: MergeStrategy,
+        /// Account for imported entries that have none, such as .env variables
+        #[arg(long, default_value = "env")]
+        account: String,
+        /// Key file that unlocks a KeePass database, alone or with its password
+        #[arg(long, value_name = "PATH")]
+        keyfile: Option<PathBuf>,
+    },
+    Merge {
+        /// Database file to merge into the current one
+        other: PathBuf,
+        /// Which entry wins when both databases have the same service
+        #[arg(short, long, value_enum, default_value_t = ConflictStrategy::Newer)]
+        strategy: ConflictStrategy,
+    },
+    Diff {
+        /// Database file to compare against
+        other: PathBuf,
     },
     Info,
-    Backup,
-    Delete,
+    Backup {
+        /// Number of backups to keep (0 keeps all)
+        #[arg(long)]
+        keep: Option<usize>,
+        /// Folder to write the backup to (created if needed)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Encrypt the backup with its own passphrase so it can be stored anywhere
+        #[arg(long)]
+        encrypt: bool,
+    },
+    Restore {
+        /// Backup file to restore, or a folder to pick one from (the backup folder when omitted)
+        path: Option<PathBuf>,
+        /// Skip the typed confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    #[command(group = ArgGroup::new("rule").required(true).multiple(true))]
+    PurgeBackups {
+        /// Keep only this many of the newest backups
+        #[arg(long, group = "rule")]
+        keep: Option<usize>,
+        /// Delete backups older than a span like 30d or 12w
+        #[arg(long, value_parser = parse_since, group = "rule")]
+        older_than: Option<Duration>,
+        /// Skip the confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    Dedup {
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+    Expiring {
+        /// Also include entries due within this many days
+        #[arg(long, value_name = "DAYS", default_value_t = 0)]
+        within: u32,
+    },
+    Stale {
+        /// List entries not updated for longer than a span like 180d or 52w
+        #[arg(long, value_parser = parse_since)]
+        older_than: Duration,
+        /// Move the stale entries to the trash after a confirmation
+        #[arg(long)]
+        remove: bool,
+        /// Skip the confirmation prompt
+        #[arg(short, long, requires = "remove")]
+        yes: bool,
+    },
+    Verify,
+    Doctor,
+    #[command(alias = "change-password")]
+    ChangeMaster,
+    #[command(name = clipboard::HELPER_COMMAND, hide = true)]
+    ClipboardHelper {
+        #[arg(long)]
+        timeout: u64,
+    },
+    Rekey,
+    Lock,
+    Delete {
+        /// Skip the typed confirmation (a backup is still made first)
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TrashAction {
+    List,
+    Restore {
+        service: String,
+    },
+    Empty {
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TagAction {
+    Add {
+        tag: String,
+        #[arg(required = true)]
+        services: Vec<String>,
+    },
+    Remove {
+        tag: String,
+        #[arg(required = true)]
+        services: Vec<String>,
+    },
+    Rename {
+        old: String,
+        new: String,
+    },
 }
 
 impl Commands {
-    pub fn execute(self) -> CredentialResult<()> {
+    // Commands that touch files other than through `save_database`, which a
+    // dry run cannot preview.
+    fn writes_outside_database(&self) -> bool {
+        matches!(
+            self,
+            Commands::Init
+                | Commands::Backup { .. }
+                | Commands::Restore { .. }
+                | Commands::PurgeBackups { .. }
+                | Commands::ChangeMaster
+                | Commands::Rekey
+                | Commands::Lock
+                | Commands::Delete { .. }
+        )
+    }
+
+    // Commands allowed under --read-only. Anything not listed is assumed to
+    // modify the vault, so new commands are refused until added here.
+    fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Commands::Get { .. }
+                | Commands::Copy { .. }
+                | Commands::List(_)
+                | Commands::Search { .. }
+                | Commands::Accounts { .. }
+                | Commands::Env { .. }
+                | Commands::Frequent { .. }
+                | Commands::Open { .. }
+                | Commands::Totp { .. }
+                | Commands::Audit
+                | Commands::CheckPwned { .. }
+                | Commands::History { .. }
+                | Commands::Trash {
+                    action: TrashAction::List
+                }
+                | Commands::Gen(GenArgs { save: None, .. })
+                | Commands::Export { .. }
+                | Commands::Diff { .. }
+                | Commands::Info
+                | Commands::Expiring { .. }
+                | Commands::Stale { remove: false, .. }
+                | Commands::Verify
+                | Commands::Doctor
+                | Commands::Lock
+                | Commands::ClipboardHelper { .. }
+        )
+    }
+
+    pub fn execute(self, ctx: &Context) -> CredentialResult<()> {
+        let json = ctx.json;
+        if crate::storage::file::is_read_only() && !self.is_read_only() {
+            return Err(CredentialError::ReadOnly);
+        }
+        crate::storage::file::set_hold_lock(!self.is_read_only());
+        if ctx.dry_run && self.writes_outside_database() {
+            return Err(CredentialError::invalid_input(
+                "--dry-run is not supported by this command",
+            ));
+        }
         match self {
-            Commands::Add { service, account } => add_credential(service, account),
-            Commands::Get { service } => get_credential(&service),
-            Commands::List => list_credentials(),
-            Commands::Edit { service } => edit_credential(&service),
-            Commands::Remove { service } => remove_credential(&service),
-            Commands::Info => show_credential(),
-            Commands::Backup => backup_database(),
-            Commands::Delete => delete_credential(),
+            Commands::Init => init_vault(&ctx.config),
+            Commands::Add {
+                service,
+                account,
+                secret_stdin,
+                from,
+                force,
+                otpauth,
+                otpauth_qr,
+            } => {
+                let otpauth = read_otpauth(otpauth, otpauth_qr)?;
+                add_credential(ctx, service, account, secret_stdin, from, force, otpauth)
+            }
+            Commands::Get {
+                services,
+                account,
+                show,
+                field,
+                qr,
+                totp,
+                wifi,
+                reveal_timeout,
+                out,
+                format,
+                force,
+                copy,
+            } => {
+                let reveal_timeout = reveal_timeout.unwrap_or(ctx.config.reveal_timeout);
+                if services.len() > 1 {
+                    if qr || out.is_some() || account.is_some() || copy {
+                        return Err(CredentialError::invalid_input(
+                            "--qr, --out, --copy, and --account take a single service",
+                        ));
+                    }
+                    return get_credentials(&services, show, field, reveal_timeout, ctx);
+                }
+                let service = resolve_service(services.into_iter().next())?;
+                let account = account.as_deref();
+                if copy {
+                    return copy_credential(
+                        &service,
+                        account,
+                        CopyField::Secret,
+                        ctx.config.clipboard_timeout,
+                        ctx,
+                    );
+                }
+                if let Some(out) = out {
+                    return write_credential(&service, account, &out, format, force, ctx);
+                }
+                match field {
+                    Some(field) => get_credential_field(&service, account, field, ctx),
+                    None if qr => {
+                        let content = if totp {
+                            QrContent::TotpUri
+                        } else if wifi {
+                            QrContent::Wifi
+                        } else {
+                            QrContent::Secret
+                        };
+                        get_credential_qr(&service, account, show, content, reveal_timeout, ctx)
+                    }
+                    None => get_credential(&service, account, show, reveal_timeout, ctx),
+                }
+            }
+            Commands::Copy {
+                service,
+                account,
+                field,
+                timeout,
+            } => copy_credential(
+                &resolve_service(service)?,
+                account.as_deref(),
+                field,
+                timeout.unwrap_or(ctx.config.clipboard_timeout),
+                ctx,
+            ),
+            Commands::List(args) => list_credentials(ctx, &args),
+            Commands::Search {
+                query,
+                regex,
+                field,
+            } => search_credentials(&query, regex.then_some(field)),
+            Commands::Accounts { pattern } => list_accounts(&pattern, json),
+            Commands::Env { services, command } => run_with_secrets(&services, &command, ctx),
+            Commands::Frequent { limit, recent } => list_frequent(limit, recent, ctx),
+            Commands::Open { service, account } => {
+                open_credential_url(&service, account.as_deref())
+            }
+            Commands::Totp {
+                service,
+                account,
+                copy,
+            } => totp_credential(&service, account.as_deref(), copy, ctx),
+            Commands::Audit => audit_credentials(json),
+            Commands::CheckPwned { yes, offline } => check_pwned(yes, offline, ctx),
+            Commands::Edit(mut args) => {
+                let service = resolve_service(args.service.take())?;
+                if args.has_changes() {
+                    edit_credential_from_flags(ctx, &service, args)
+                } else {
+                    edit_credential(ctx, &service, args.which.as_deref(), args.force)
+                }
+            }
+            Commands::History {
+                service,
+                account,
+                show,
+            } => show_history(&service, account.as_deref(), show, ctx),
+            Commands::Remove {
+                services,
+                account,
+                yes,
+                permanent,
+            } => {
+                let services = if services.is_empty() {
+                    vec![resolve_service(None)?]
+                } else {
+                    services
+                };
+                if services.len() > 1 && account.is_some() {
+                    return Err(CredentialError::invalid_input(
+                        "--account takes a single service",
+                    ));
+                }
+                remove_credentials(&services, account.as_deref(), yes, permanent, ctx)
+            }
+            Commands::Trash { action } => match action {
+                TrashAction::List => list_trash(ctx),
+                TrashAction::Restore { service } => restore_from_trash(&service, ctx),
+                TrashAction::Empty { yes } => empty_trash(yes, ctx),
+            },
+            Commands::Tag { action } => match action {
+                TagAction::Add { tag, services } => tag_credentials(&tag, &services, true, ctx),
+                TagAction::Remove { tag, services } => tag_credentials(&tag, &services, false, ctx),
+                TagAction::Rename { old, new } => rename_tag(&old, &new, ctx),
+            },
+            Commands::Pin { service } => set_favorite(&service, true, ctx),
+            Commands::Unpin { service } => set_favorite(&service, false, ctx),
+            Commands::Rename { old, new, force } => rename_credential(&old, &new, force, ctx),
+            Commands::Rotate {
+                service,
+                account,
+                length,
+                no_symbols,
+                show,
+                force,
+            } => rotate_credential(
+                &service,
+                account.as_deref(),
+                length,
+                no_symbols,
+                show,
+                force,
+                ctx,
+            ),
+            Commands::Gen(args) => generate_credential(ctx, args),
+            Commands::Export {
+                format,
+                output,
+                force,
+            } => export_credentials(format, output, force),
+            Commands::Import {
+                path,
+                format,
+                merge,
+                account,
+                keyfile,
+            } => import_credentials(&path, format, merge, &account, keyfile.as_deref(), ctx),
+            Commands::Merge { other, strategy } => merge_database(&other, strategy, ctx),
+            Commands::Diff { other } => diff_database(&other, json),
+            Commands::Info => show_credential(ctx),
+            Commands::Backup { keep, dir, encrypt } => {
+                let keep = keep.unwrap_or(ctx.config.backup_keep);
+                match dir {
+                    Some(dir) => crate::storage::file::backup_database_to(&dir, keep, encrypt),
+                    None => backup_database(keep, encrypt),
+                }
+            }
+            Commands::Restore { path, yes } => restore_credential(path, yes, ctx),
+            Commands::PurgeBackups {
+                keep,
+                older_than,
+                yes,
+            } => purge_backups(keep, older_than, yes),
+            Commands::Dedup { yes } => dedup_credentials(yes, ctx),
+            Commands::Expiring { within } => list_expiring(within, ctx),
+            Commands::Stale {
+                older_than,
+                remove,
+                yes,
+            } => list_stale(older_than, remove, yes, ctx),
+            Commands::Verify => verify_credentials(json),
+            Commands::Doctor => run_doctor(json),
+            Commands::ChangeMaster => change_master(&ctx.config),
+            Commands::ClipboardHelper { timeout } => {
+                clipboard::run_clearer(Duration::from_secs(timeout))
+            }
+            Commands::Rekey => rekey_vault(&ctx.config),
+            Commands::Lock => lock_session(),
+            Commands::Delete { yes } => delete_credential(yes, &ctx.config),
+        }
+    }
+}
+
+fn init_vault(config: &Config) -> CredentialResult<()> {
+    use crate::storage::file::{get_database_path, set_configured_database_path, DB_PATH_ENV};
+
+    let path = get_database_path()?;
+
+    if database_exists() {
+        outln!("ℹ️  Crab is already set up.");
+        outln!("  Location: {}", path.display());
+        return Ok(());
+    }
+
+    outln!("🦀 Welcome to Crab!");
+
+    let mut config = config.clone();
+    let path = if std::env::var_os(DB_PATH_ENV).is_some() {
+        outln!("Using the database location from {DB_PATH_ENV}:");
+        outln!("  {}", path.display());
+        path
+    } else {
+        let chosen: String = Input::new()
+            .with_prompt("Where should the credential database live?")
+            .default(path.display().to_string())
+            .interact_text()
+            .map_err(|_| CredentialError::user_cancelled())?;
+        let chosen = PathBuf::from(chosen.trim());
+        if chosen != path {
+            config.vault_path = Some(chosen.clone());
+            set_configured_database_path(chosen.clone());
         }
+        chosen
+    };
+
+    let proceed = Confirm::new()
+        .with_prompt(format!("Create the database at {}?", path.display()))
+        .default(true)
+        .interact()
+        .map_err(|_| CredentialError::user_cancelled())?;
+
+    if !proceed {
+        return Err(CredentialError::user_cancelled());
+    }
+
+    let path = init_database()?;
+    outln!("✅ Credential database created: {}", path.display());
+
+    if let Some(config_file) = config_path().filter(|config_file| !config_file.exists()) {
+        config.save_to(&config_file)?;
+        outln!("✅ Config written: {}", config_file.display());
+    }
+
+    outln!("💡 Next, run 'crab add' to store your first credential.");
+    Ok(())
+}
+
+fn read_secret_from_stdin() -> CredentialResult<String> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Err(CredentialError::invalid_input(
+            "--secret-stdin expects the secret to be piped in, but stdin is a terminal",
+        ));
+    }
+
+    let mut secret = String::new();
+    stdin.lock().read_to_string(&mut secret)?;
+    let secret = secret.trim_end_matches(['\n', '\r']).to_string();
+
+    if secret.is_empty() {
+        return Err(CredentialError::invalid_input(
+            "No secret received on stdin",
+        ));
     }
+    Ok(secret)
 }
 
-fn add_credential(service: Option<String>, account: Option<String>) -> CredentialResult<()> {
+fn add_credential(
+    ctx: &Context,
+    service: Option<String>,
+    account: Option<String>,
+    secret_stdin: bool,
+    from: Option<String>,
+    force: bool,
+    otpauth: Option<OtpAuth>,
+) -> CredentialResult<()> {
+    let service = service
+        .map(|service| required_flag(service, "--service"))
+        .transpose()?
+        .or_else(|| otpauth.as_ref().and_then(|otp| otp.issuer.clone()));
+    let account = account
+        .map(|account| required_flag(account, "--account"))
+        .transpose()?
+        .or_else(|| otpauth.as_ref().and_then(|otp| otp.account.clone()));
+
+    if secret_stdin {
+        return match (service, account) {
+            (Some(service), Some(account)) => {
+                add_credential_from_stdin(ctx, service, account, from.as_deref(), force, otpauth)
+            }
+            _ => Err(CredentialError::invalid_input(
+                "--secret-stdin requires both --service and --account",
+            )),
+        };
+    }
+
     let mut database = load_database()?;
+    let template = from
+        .map(|from| find_template(&database, &from))
+        .transpose()?;
 
     let service_name = match service {
         Some(s) => s,
-        None => Input::new()
+        None => Input::<String>::new()
             .with_prompt("Service name")
+            .validate_with(|input: &String| validate_required(input, "Service name"))
+            .interact_text()
+            .map_err(|_| CredentialError::user_cancelled())?,
+    };
+
+    let account_name = match account {
+        Some(a) => a,
+        None => Input::<String>::new()
+            .with_prompt("Please Enter Account Name")
+            .validate_with(|input: &String| validate_required(input, "Account name"))
             .interact_text()
             .map_err(|_| CredentialError::user_cancelled())?,
     };
 
-    if database.find_entry(&service_name).is_some() {
-        println!("⚠️ Service '{service_name}' already exists!");
+    if database
+        .find_entry(&service_name, Some(&account_name))
+        .is_some()
+    {
+        if let Some(otp) = otpauth {
+            return attach_otpauth(ctx, database, &service_name, &account_name, otp);
+        }
+        outln!("⚠️ Account '{account_name}' for '{service_name}' already exists!");
         let overwrite = Confirm::new()
             .with_prompt("Do you want to overwrite it?")
             .interact()
             .map_err(|_| CredentialError::user_cancelled())?;
 
         if !overwrite {
-            println!("Operation cancelled.");
+            outln!("Operation cancelled.");
             return Ok(());
         }
-        database.remove_entry(&service_name);
+        database.remove_entry(&service_name, Some(&account_name));
     }
 
-    let account_name = match account {
-        Some(a) => a,
-        None => Input::new()
-            .with_prompt("Please Enter Account Name")
-            .interact_text()
-            .map_err(|_| CredentialError::user_cancelled())?,
+    let generate = ctx.config.generate_on_add
+        || Confirm::new()
+            .with_prompt("Generate a strong password for you?")
+            .default(false)
+            .interact()
+            .map_err(|_| CredentialError::user_cancelled())?;
+
+    let secret = if generate {
+        generate_password(ctx.config.password_length, PasswordOptions::default())
+    } else {
+        prompt_new_secret("Please Enter Secret")?
     };
+    enforce_policy(&secret, ctx, force)?;
 
-    let secret = Password::new()
-        .with_prompt("Please Enter Secret")
-        .with_confirmation("Confirm Secret", "Secrets don't match")
-        .interact()
+    let url = prompt_url(template.as_ref().and_then(|t| t.url.as_deref()))?;
+    let totp = match otpauth {
+        Some(otp) => Some((otp.secret, otp.params)),
+        None => prompt_totp()?,
+    };
+
+    let mut tags_input = Input::<String>::new()
+        .with_prompt("Tags (comma-separated, optional)")
+        .allow_empty(true);
+    if let Some(template) = template.as_ref().filter(|t| !t.tags.is_empty()) {
+        tags_input = tags_input.default(template.tags.join(", "));
+    }
+    let tags = tags_input
+        .interact_text()
         .map_err(|_| CredentialError::user_cancelled())?;
 
-    let entry = CredentialEntry::new(service_name.clone(), account_name, secret);
+    let mut entry = match &template {
+        Some(template) => {
+            template.from_template(service_name.clone(), account_name, secret.clone())
+        }
+        None => CredentialEntry::new(service_name.clone(), account_name, secret.clone()),
+    };
+    entry.url = url;
+    if let Some((secret, params)) = totp {
+        entry.totp_secret = Some(secret);
+        entry.totp_params = params;
+    }
+    entry.tags = parse_tags(&tags);
     database.add_entry(entry);
 
-    save_database(&database)?;
+    if !persist(ctx, &database)? {
+        return Ok(());
+    }
 
-    println!("✅ Credential for '{service_name}' added successfully!");
+    outln!("✅ Credential for '{service_name}' added successfully!");
+    if generate {
+        offer_to_copy_generated(&secret, ctx)?;
+    }
     Ok(())
 }
 
-fn get_credential(service: &str) -> CredentialResult<()> {
-    let database = load_database()?;
-
-    match database.find_entry(service) {
-        Some(entry) => {
-            println!("📋 Credential found:");
-            println!("  Service: {}", entry.service);
-            println!("  Account: {}", entry.account);
-            println!("  Secret: {}", entry.secret);
-            println!("  Created: {}", format_timestamp_local(entry.created_at));
-            println!("  Updated: {}", format_timestamp_local(entry.updated_at));
-            Ok(())
-        }
-        None => Err(CredentialError::credential_not_found(service)),
+// A generated password has never been on screen, so offer the one place the
+// user is likely to need it next.
+fn offer_to_copy_generated(secret: &str, ctx: &Context) -> CredentialResult<()> {
+    let copy = Confirm::new()
+        .with_prompt("Copy the generated password to the clipboard?")
+        .default(true)
+        .interact()
+        .map_err(|_| CredentialError::user_cancelled())?;
+    if copy {
+        copy_with_timeout(secret, "Generated password", ctx.config.clipboard_timeout)?;
     }
+    Ok(())
 }
 
-fn list_credentials() -> CredentialResult<()> {
-    let database = load_database()?;
-
-    let entries = database.list_entries();
-
-    if entries.is_empty() {
-        Err(CredentialError::credentials_not_stored())?
-    } else {
-        println!("📋 Stored Credentials ({} entries):", entries.len());
-        for (i, entry) in entries.iter().enumerate() {
-            println!("  {}. {}", i + 1, entry.service);
-        }
+// Rejects a secret that breaks the configured policy, listing every rule it
+// misses. `--force` saves it anyway, with the violations printed as a warning.
+fn enforce_policy(secret: &str, ctx: &Context, force: bool) -> CredentialResult<()> {
+    let Err(violations) = check_policy(secret, &ctx.config.policy) else {
+        return Ok(());
+    };
+    let violations = violations.join("; ");
+    if !force {
+        return Err(CredentialError::PolicyViolation(violations));
     }
+    errln!("⚠️  Saving a secret that breaks the password policy: {violations}");
     Ok(())
 }
 
-fn edit_credential(service: &str) -> CredentialResult<()> {
-    let mut database = load_database()?;
-
-    match database.edit_entry(service) {
-        Some(entry) => {
-            println!("📝 Editing Credential for '{service}'");
-            println!("Current values:");
-            println!("  Service: {}", entry.service);
-            println!("  Account: {}", entry.account);
-
-            let new_service: String = Input::new()
-                .with_prompt("New Service Name")
-                .default(entry.service.clone())
-                .interact_text()
-                .map_err(|_| CredentialError::user_cancelled())?;
+// Asks for a secret and reports its strength, re-prompting if the user
+// decides a weak one isn't worth keeping.
+fn prompt_new_secret(prompt: &str) -> CredentialResult<String> {
+    loop {
+        let secret = Password::new()
+            .with_prompt(prompt)
+            .with_confirmation("Confirm Secret", "Secrets don't match")
+            .interact()
+            .map_err(|_| CredentialError::user_cancelled())?;
 
-            let new_account: String = Input::new()
-                .with_prompt("New Account")
-                .default(entry.account.clone())
-                .interact_text()
-                .map_err(|_| CredentialError::user_cancelled())?;
+        let strength = estimate_strength(&secret);
+        outln!(
+            "Strength: {strength} {}",
+            style::symbol(strength.indicator())
+        );
+        if strength >= MIN_ACCEPTABLE_STRENGTH {
+            return Ok(secret);
+        }
 
-            let change_secret = Confirm::new()
-                .with_prompt("Change Secret?")
-                .interact()
-                .map_err(|_| CredentialError::user_cancelled())?;
+        let keep = Confirm::new()
+            .with_prompt("This secret is weak. Use it anyway?")
+            .default(false)
+            .interact()
+            .map_err(|_| CredentialError::user_cancelled())?;
+        if keep {
+            return Ok(secret);
+        }
+    }
+}
 
-            if new_service != entry.service {
-                entry.update_service(new_service);
-            }
-            if new_account != entry.account {
-                entry.update_account(new_account);
-            }
+// The template is cloned so the new entry can be added while it's borrowed.
+fn find_template(database: &CredentialDatabase, from: &str) -> CredentialResult<CredentialEntry> {
+    database
+        .find_entry(from, None)
+        .cloned()
+        .ok_or_else(|| not_found(database, from))
+}
 
-            if change_secret {
-                let new_secret = Password::new()
-                    .with_prompt("New Secret")
-                    .with_confirmation("Confirm Secret", "Secrets don't match")
-                    .interact()
-                    .map_err(|_| CredentialError::user_cancelled())?;
-                entry.update_secret(new_secret);
-            }
+fn add_credential_from_stdin(
+    ctx: &Context,
+    service: String,
+    account: String,
+    from: Option<&str>,
+    force: bool,
+    otpauth: Option<OtpAuth>,
+) -> CredentialResult<()> {
+    let secret = read_secret_from_stdin()?;
+    enforce_policy(&secret, ctx, force)?;
+    let mut database = load_database()?;
 
-            save_database(&database)?;
+    if database.find_entry(&service, Some(&account)).is_some() {
+        return Err(CredentialError::invalid_input(&format!(
+            "Account '{account}' for '{service}' already exists"
+        )));
+    }
 
-            println!("✅ Credential Updated Successfully!");
-            Ok(())
+    let mut entry = match from {
+        Some(from) => {
+            find_template(&database, from)?.from_template(service.clone(), account, secret)
         }
-        None => Err(CredentialError::credential_not_found(service)),
+        None => CredentialEntry::new(service.clone(), account, secret),
+    };
+    if let Some(otp) = otpauth {
+        entry.update_totp_secret(Some(otp.secret), otp.params);
+    }
+    database.add_entry(entry);
+    if !persist(ctx, &database)? {
+        return Ok(());
     }
-}
 
-fn remove_credential(service: &str) -> CredentialResult<()> {
-    let mut database = load_database()?;
+    outln!("✅ Credential for '{service}' added successfully!");
+    Ok(())
+}
 
-    if database.find_entry(service).is_none() {
-        return Err(CredentialError::credential_not_found(service));
-    }
+fn read_otpauth(
+    uri: Option<String>,
+    qr_image: Option<PathBuf>,
+) -> CredentialResult<Option<OtpAuth>> {
+    let uri = match (uri, qr_image) {
+        (Some(uri), _) => uri,
+        (None, Some(path)) => crate::util::qr::decode_qr_image(&path)?,
+        (None, None) => return Ok(None),
+    };
+    totp::parse_otpauth_uri(&uri)
+        .map(Some)
+        .map_err(|e| CredentialError::invalid_input(&e))
+}
 
-    let confirm = Confirm::new()
-        .with_prompt(format!("Are you sure you want to remove '{service}'?"))
+// Migrating a token for an account that is already stored only needs its 2FA
+// secret, not a second copy of the password.
+fn attach_otpauth(
+    ctx: &Context,
+    mut database: CredentialDatabase,
+    service: &str,
+    account: &str,
+    otp: OtpAuth,
+) -> CredentialResult<()> {
+    let entry = database
+        .edit_entry(service, Some(account))
+        .expect("entry was just found");
+    let prompt = if entry.totp_secret.is_some() {
+        format!("'{service}' ({account}) already has a TOTP secret. Replace it?")
+    } else {
+        format!("'{service}' ({account}) already exists. Add the 2FA secret to it?")
+    };
+    let confirmed = Confirm::new()
+        .with_prompt(prompt)
+        .default(true)
         .interact()
         .map_err(|_| CredentialError::user_cancelled())?;
+    if !confirmed {
+        outln!("Operation cancelled.");
+        return Ok(());
+    }
 
-    if confirm {
-        database.remove_entry(service);
-        save_database(&database)?;
-        println!("✅ Credential for '{service}' removed successfully!");
+    entry.update_totp_secret(Some(otp.secret), otp.params);
+    if !persist(ctx, &database)? {
+        return Ok(());
     }
+
+    outln!("✅ 2FA secret for '{service}' saved!");
     Ok(())
 }
 
-fn show_credential() -> CredentialResult<()> {
-    if !database_exists() {
-        return Err(CredentialError::database_not_found());
-    }
+const MASKED_SECRET: &str = "••••••••";
 
-    let database = load_database()?;
+fn not_found(database: &CredentialDatabase, service: &str) -> CredentialError {
+    let suggestions = database
+        .closest_services(service, 3)
+        .into_iter()
+        .map(String::from)
+        .collect();
+    CredentialError::credential_not_found_with_suggestions(service, suggestions)
+}
 
-    println!("📊 Database Information:");
-    println!("  Version: {}", database.version);
-    println!("  Entries: {}", database.len());
+fn print_json(value: &serde_json::Value) -> CredentialResult<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
 
-    match get_database_info() {
-        Ok(metadata) => {
-            println!("  File size: {} bytes", metadata.len());
-            if let Ok(modified) = metadata.modified() {
-                println!("  Last modified: {modified:?}");
-            }
-        }
-        Err(e) => {
-            println!("  Failed to get file info: {e}");
-        }
-    }
+fn get_credential(
+    service: &str,
+    account: Option<&str>,
+    show: bool,
+    reveal_timeout: u64,
+    ctx: &Context,
+) -> CredentialResult<()> {
+    let database = load_database()?;
+    let entry = find_credential(&database, service, account)?;
 
-    if let Ok(path) = crate::storage::file::get_database_path() {
-        println!("  Location: {}", path.display());
+    // JSON output always includes the secret.
+    if show || ctx.json {
+        confirm_reveal(ctx)?;
     }
 
+    if ctx.json {
+        print_json(&entry_json(entry))?;
+        record_access(&[entry], ctx);
+        return Ok(());
+    }
+    outln!("📋 Credential found:");
+    print_entry(entry, show, ctx);
+    if show {
+        record_access(&[entry], ctx);
+        scrub_screen(reveal_timeout)?;
+    }
+    Ok(())
+}
+
+// Prints every entry that exists, then fails naming the ones that don't, so a
+// provisioning script gets what it can and still sees the gap.
+fn get_credentials(
+    services: &[String],
+    show: bool,
+    field: Option<GetField>,
+    reveal_timeout: u64,
+    ctx: &Context,
+) -> CredentialResult<()> {
+    let database = load_database()?;
+    let (found, missing): (Vec<&String>, Vec<&String>) = services
+        .iter()
+        .partition(|service| database.find_entry(service, None).is_some());
+    let entries = found
+        .iter()
+        .map(|service| find_credential(&database, service, None))
+        .collect::<CredentialResult<Vec<&CredentialEntry>>>()?;
+
+    let reveals = match field {
+        Some(field) => field == GetField::Secret,
+        None => show || ctx.json,
+    };
+    if reveals && !entries.is_empty() {
+        confirm_reveal(ctx)?;
+    }
+
+    match field {
+        Some(field) => {
+            for entry in &entries {
+                println!("{}", field_value(entry, field));
+            }
+        }
+        None if ctx.json => {
+            let items: Vec<serde_json::Value> = entries.iter().map(|e| entry_json(e)).collect();
+            print_json(&serde_json::json!(items))?;
+        }
+        None => {
+            for (i, entry) in entries.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                outln!("📋 {}:", entry.service);
+                print_entry(entry, show, ctx);
+            }
+        }
+    }
+
+    let not_found = (!missing.is_empty()).then(|| {
+        let suggestions = missing
+            .iter()
+            .flat_map(|service| database.closest_services(service, 1))
+            .map(String::from)
+            .collect();
+        let missing: Vec<&str> = missing.iter().map(|service| service.as_str()).collect();
+        CredentialError::credential_not_found_with_suggestions(&missing.join("', '"), suggestions)
+    });
+    if reveals {
+        record_access(&entries, ctx);
+    }
+    if let Some(e) = not_found {
+        return Err(e);
+    }
+    if reveals && field.is_none() && !ctx.json {
+        scrub_screen(reveal_timeout)?;
+    }
+    Ok(())
+}
+
+fn entry_json(entry: &CredentialEntry) -> serde_json::Value {
+    serde_json::json!({
+        "service": entry.service,
+        "account": entry.account,
+        "secret": entry.secret,
+        "created_at": entry.created_at,
+        "updated_at": entry.updated_at,
+        "tags": entry.tags,
+        "url": entry.url,
+        "fields": entry.fields,
+    })
+}
+
+fn print_entry(entry: &CredentialEntry, show: bool, ctx: &Context) {
+    outln!("  Service: {}", entry.service);
+    outln!("  Account: {}", entry.account);
+    if show {
+        outln!("  Secret: {}", entry.secret);
+    } else {
+        outln!("  Secret: {MASKED_SECRET} (use --show to reveal)");
+    }
+    if let Some(url) = &entry.url {
+        outln!("  URL: {url}");
+    }
+    if !entry.tags.is_empty() {
+        outln!("  Tags: {}", entry.tags.join(", "));
+    }
+    for (name, value) in &entry.fields {
+        if show || !is_sensitive_field(name) {
+            outln!("  {name}: {value}");
+        } else {
+            outln!("  {name}: {MASKED_SECRET}");
+        }
+    }
+    outln!(
+        "  Created: {}",
+        format_timestamp_local(entry.created_at, ctx.time_format)
+    );
+    outln!(
+        "  Updated: {}",
+        format_timestamp_local(entry.updated_at, ctx.time_format)
+    );
+}
+
+#[derive(Clone, Copy)]
+enum QrContent {
+    Secret,
+    TotpUri,
+    Wifi,
+}
+
+// Both the secret and the TOTP seed are readable from across the room once on
+// screen, so the QR code is only drawn with an explicit --show.
+fn get_credential_qr(
+    service: &str,
+    account: Option<&str>,
+    show: bool,
+    content: QrContent,
+    reveal_timeout: u64,
+    ctx: &Context,
+) -> CredentialResult<()> {
+    if !show {
+        return Err(CredentialError::invalid_input(
+            "--qr displays sensitive data; add --show to confirm",
+        ));
+    }
+
+    let database = load_database()?;
+    let entry = find_credential(&database, service, account)?;
+    confirm_reveal(ctx)?;
+
+    let data = match content {
+        QrContent::TotpUri => {
+            let secret = entry.totp_secret.as_deref().ok_or_else(|| {
+                CredentialError::invalid_input(&format!(
+                    "'{}' has no TOTP secret; add one with 'crab edit {}'",
+                    entry.service, entry.service
+                ))
+            })?;
+            zeroize::Zeroizing::new(totp::provisioning_uri(
+                secret,
+                entry.totp_params,
+                &entry.service,
+                &entry.account,
+            ))
+        }
+        QrContent::Wifi => {
+            zeroize::Zeroizing::new(crate::util::qr::wifi_payload(&entry.account, &entry.secret))
+        }
+        QrContent::Secret => zeroize::Zeroizing::new(entry.secret.clone()),
+    };
+
+    println!("{}", crate::util::qr::render_qr(&data)?);
+    record_access(&[entry], ctx);
+    scrub_screen(reveal_timeout)
+}
+
+// The terminal's own logging or history can't be reached from here; this only
+// takes the secret off the screen and out of the scrollback buffer.
+fn scrub_screen(timeout: u64) -> CredentialResult<()> {
+    if screen::should_scrub(timeout, std::io::stdout().is_terminal()) {
+        errln!("⏳ Screen will clear in {timeout}s");
+    }
+    screen::scrub_after(timeout)?;
+    Ok(())
+}
+
+// Prints a single raw value so it can be used in command substitution.
+fn get_credential_field(
+    service: &str,
+    account: Option<&str>,
+    field: GetField,
+    ctx: &Context,
+) -> CredentialResult<()> {
+    let database = load_database()?;
+
+    let entry = find_credential(&database, service, account)?;
+    if field == GetField::Secret {
+        confirm_reveal(ctx)?;
+    }
+    println!("{}", field_value(entry, field));
+    if field == GetField::Secret {
+        record_access(&[entry], ctx);
+    }
+    Ok(())
+}
+
+fn field_value(entry: &CredentialEntry, field: GetField) -> String {
+    match field {
+        GetField::Service => entry.service.clone(),
+        GetField::Account => entry.account.clone(),
+        GetField::Secret => entry.secret.clone(),
+        GetField::Created => entry.created_at.to_string(),
+        GetField::Updated => entry.updated_at.to_string(),
+    }
+}
+
+// The file is created with owner-only permissions from the start, so the
+// secret is never readable by others even briefly.
+fn write_credential(
+    service: &str,
+    account: Option<&str>,
+    path: &std::path::Path,
+    format: EntryFormat,
+    force: bool,
+    ctx: &Context,
+) -> CredentialResult<()> {
+    let database = load_database()?;
+    let entry = find_credential(&database, service, account)?;
+    confirm_reveal(ctx)?;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true);
+    if force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options.open(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::AlreadyExists {
+            CredentialError::invalid_input(&format!(
+                "{} already exists (use --force to overwrite it)",
+                path.display()
+            ))
+        } else {
+            e.into()
+        }
+    })?;
+    // An existing file keeps its old mode when truncated.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    let mut writer = std::io::BufWriter::new(file);
+    export_entry(entry, format, &mut writer)?;
+    std::io::Write::flush(&mut writer)?;
+
+    errln!("✅ Wrote '{}' to {}", entry.service, path.display());
+    record_access(&[entry], ctx);
+    Ok(())
+}
+
+fn copy_credential(
+    service: &str,
+    account: Option<&str>,
+    field: CopyField,
+    timeout: u64,
+    ctx: &Context,
+) -> CredentialResult<()> {
+    let database = load_database()?;
+
+    let entry = find_credential(&database, service, account)?;
+    let (value, label) = match field {
+        CopyField::Secret => (Some(&entry.secret), "Secret"),
+        CopyField::Account => (Some(&entry.account), "Account"),
+        CopyField::Url => (entry.url.as_ref(), "URL"),
+        CopyField::Notes => (entry.fields.get("notes"), "Notes"),
+    };
+    let value = value.filter(|value| !value.is_empty()).ok_or_else(|| {
+        CredentialError::invalid_input(&format!(
+            "'{service}' has no {} to copy",
+            label.to_lowercase()
+        ))
+    })?;
+
+    if field == CopyField::Secret {
+        confirm_reveal(ctx)?;
+    }
+    let value = zeroize::Zeroizing::new(value.clone());
+    record_access(&[entry], ctx);
+    copy_with_timeout(&value, &format!("{label} for '{service}'"), timeout)
+}
+
+// Best-effort bookkeeping for `crab frequent`: a read never prompts for the
+// master password or fails just because the counter couldn't be saved.
+fn record_access(entries: &[&CredentialEntry], ctx: &Context) {
+    if !ctx.config.track_access || ctx.dry_run {
+        return;
+    }
+    let now = now_unix();
+    let _ = crate::storage::file::update_database_if_unlocked(|database| {
+        entries.iter().fold(false, |recorded, entry| {
+            database.record_access(&entry.service, Some(&entry.account), now) | recorded
+        })
+    });
+}
+
+// With `confirm_on_get`, browsing the vault uses the session but exposing a
+// secret needs the master password again.
+fn confirm_reveal(ctx: &Context) -> CredentialResult<()> {
+    if ctx.config.confirm_on_get {
+        crate::storage::file::confirm_master_password()?;
+    }
+    Ok(())
+}
+
+fn copy_with_timeout(text: &str, label: &str, timeout: u64) -> CredentialResult<()> {
+    let mut clipboard = SystemClipboard::new()?;
+    clipboard.set_text(text)?;
+    outln!("✅ {label} copied to clipboard");
+
+    if timeout == 0 {
+        return Ok(());
+    }
+    let timeout_duration = Duration::from_secs(timeout);
+    if clipboard::spawn_clearer(text, timeout_duration).is_ok() {
+        outln!("⏳ Clipboard will clear in {timeout}s");
+        return Ok(());
+    }
+    // Without a helper, this process has to stay around to clear it.
+    outln!("⏳ Clipboard will clear in {timeout}s (keep crab running until then)");
+    if clipboard::clear_after(&mut clipboard, text, timeout_duration)? {
+        outln!("🧹 Clipboard cleared");
+    }
+    Ok(())
+}
+
+fn parse_since(input: &str) -> Result<Duration, String> {
+    parse_duration(input).ok_or_else(|| format!("'{input}' is not a duration like 24h or 7d"))
+}
+
+fn parse_before(input: &str) -> Result<u64, String> {
+    parse_date(input).ok_or_else(|| format!("'{input}' is not a date like 2024-01-31"))
+}
+
+fn list_credentials(ctx: &Context, args: &ListArgs) -> CredentialResult<()> {
+    let database = load_database()?;
+
+    if args.count && !args.has_filters() {
+        return print_count(database.len(), ctx);
+    }
+
+    // An empty vault is a valid, empty listing rather than an error.
+    if database.is_empty() {
+        if ctx.json {
+            return print_json(&serde_json::json!([]));
+        }
+        outln!("ℹ️  No credentials stored.");
+        outln!("💡 Try 'crab add' to create your first credential.");
+        return Ok(());
+    }
+
+    let mut entries = match &args.tag {
+        Some(tag) => database.list_by_tag(tag),
+        None => database.list_entries(),
+    };
+    if args.favorites {
+        let favorites = database.favorites();
+        entries.retain(|entry| favorites.iter().any(|other| std::ptr::eq(*other, *entry)));
+    }
+    if args.since.is_some() || args.before.is_some() {
+        let now = now_unix();
+        let since = args.since.map(|span| now.saturating_sub(span.as_secs()));
+        let in_range = database.filter_by_updated(since, args.before);
+        entries.retain(|entry| in_range.iter().any(|other| std::ptr::eq(*other, *entry)));
+    }
+    sort_entries(&mut entries, args.sort);
+    if args.reverse {
+        entries.reverse();
+    }
+    favorites_first(&mut entries);
+
+    if args.count {
+        return print_count(entries.len(), ctx);
+    }
+    if ctx.json {
+        let services: Vec<&str> = entries.iter().map(|entry| entry.service.as_str()).collect();
+        print_json(&serde_json::json!(services))?;
+    } else if args.long {
+        let rows: Vec<Vec<String>> = entries
+            .iter()
+            .map(|entry| {
+                // An ASCII marker keeps the columns aligned.
+                let service = if entry.favorite {
+                    format!("* {}", entry.service)
+                } else {
+                    entry.service.clone()
+                };
+                vec![
+                    service,
+                    entry.account.clone(),
+                    format_timestamp_local(entry.updated_at, ctx.time_format),
+                ]
+            })
+            .collect();
+        println!(
+            "{}",
+            render_table(&["SERVICE", "ACCOUNT", "UPDATED"], &rows)
+        );
+    } else {
+        outln!("📋 Stored Credentials ({} entries):", entries.len());
+        for (i, entry) in entries.iter().enumerate() {
+            if entry.favorite {
+                outln!("  {}. {} {}", i + 1, entry.service, favorite_marker());
+            } else {
+                outln!("  {}. {}", i + 1, entry.service);
+            }
+        }
+    }
+
+    if !ctx.json {
+        let now = now_unix();
+        let overdue = database.expiring(now, 0).len();
+        if overdue > 0 {
+            errln!("⚠️  {overdue} credential(s) overdue for rotation (see 'crab expiring')");
+        }
+    }
+    Ok(())
+}
+
+fn print_count(count: usize, ctx: &Context) -> CredentialResult<()> {
+    if ctx.json {
+        return print_json(&serde_json::json!({ "count": count }));
+    }
+    println!("{count}");
+    Ok(())
+}
+
+// Runs `command` with each service's secret in its environment and exits with
+// the child's status. Nothing is printed or written to disk; our copies of the
+// secrets are wiped as soon as the child has been spawned.
+fn run_with_secrets(
+    services: &[String],
+    command: &[String],
+    ctx: &Context,
+) -> CredentialResult<()> {
+    let database = load_database()?;
+    let mut variables: Vec<(String, zeroize::Zeroizing<String>)> = Vec::new();
+    for service in services {
+        let entry = database
+            .find_entry(service, None)
+            .ok_or_else(|| not_found(&database, service))?;
+        variables.push((
+            format!("CRAB_{}", env_var_prefix(&entry.service)),
+            zeroize::Zeroizing::new(entry.secret.clone()),
+        ));
+    }
+    drop(database);
+    confirm_reveal(ctx)?;
+
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| CredentialError::invalid_input("No command given after --"))?;
+    let mut child = std::process::Command::new(program);
+    child.args(args);
+    for (name, secret) in &variables {
+        child.env(name, secret.as_str());
+    }
+    let spawned = child.spawn();
+    drop(child);
+    drop(variables);
+    let status = spawned
+        .map_err(|e| {
+            CredentialError::IoError(std::io::Error::new(
+                e.kind(),
+                format!("Could not run '{program}': {e}"),
+            ))
+        })?
+        .wait()?;
+
+    if status.success() {
+        return Ok(());
+    }
+    std::process::exit(exit_code_of(status));
+}
+
+// A child killed by a signal reports 128 + the signal number, as shells do.
+fn exit_code_of(status: std::process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
+
+fn list_frequent(limit: usize, recent: bool, ctx: &Context) -> CredentialResult<()> {
+    let database = load_database()?;
+    let entries = if recent {
+        database.recently_used(limit)
+    } else {
+        database.most_used(limit)
+    };
+
+    if ctx.json {
+        let items: Vec<serde_json::Value> = entries
+            .iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "service": entry.service,
+                    "access_count": entry.access_count,
+                    "last_accessed": entry.last_accessed,
+                })
+            })
+            .collect();
+        return print_json(&serde_json::json!(items));
+    }
+
+    if entries.is_empty() {
+        outln!("ℹ️  No usage recorded yet.");
+        if !ctx.config.track_access {
+            outln!("   Access tracking is off (track_access = false in the config).");
+        }
+        return Ok(());
+    }
+
+    let rows: Vec<Vec<String>> = entries
+        .iter()
+        .map(|entry| {
+            vec![
+                entry.service.clone(),
+                entry.access_count.to_string(),
+                entry
+                    .last_accessed
+                    .map(|at| format_timestamp_local(at, ctx.time_format))
+                    .unwrap_or_default(),
+            ]
+        })
+        .collect();
+    println!("{}", render_table(&["SERVICE", "USES", "LAST USED"], &rows));
+    Ok(())
+}
+
+fn list_expiring(within_days: u32, ctx: &Context) -> CredentialResult<()> {
+    let database = load_database()?;
+    let now = now_unix();
+
+    let expiring = database.expiring(now, u64::from(within_days) * 24 * 60 * 60);
+
+    if ctx.json {
+        let items: Vec<serde_json::Value> = expiring
+            .iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "service": entry.service,
+                    "due_at": entry.rotation_due_at(),
+                    "overdue": entry.rotation_due_at().is_some_and(|due_at| due_at <= now),
+                })
+            })
+            .collect();
+        return print_json(&serde_json::json!(items));
+    }
+
+    if expiring.is_empty() {
+        outln!("✅ No credentials due for rotation.");
+        return Ok(());
+    }
+
+    outln!("⏰ Credentials due for rotation:");
+    for entry in &expiring {
+        let due_at = entry.rotation_due_at().unwrap_or(now);
+        let state = if due_at <= now {
+            "overdue since"
+        } else {
+            "due"
+        };
+        outln!(
+            "  {} ({state} {})",
+            entry.service,
+            format_timestamp_local(due_at, ctx.time_format)
+        );
+    }
+    Ok(())
+}
+
+fn list_stale(
+    older_than: Duration,
+    remove: bool,
+    yes: bool,
+    ctx: &Context,
+) -> CredentialResult<()> {
+    let database = load_database()?;
+    let stale = database.stale(now_unix(), older_than.as_secs());
+
+    if stale.is_empty() {
+        if ctx.json {
+            return print_json(&serde_json::json!([]));
+        }
+        outln!("✅ No stale credentials.");
+        return Ok(());
+    }
+
+    if remove {
+        let targets: Vec<(String, String)> = stale
+            .iter()
+            .map(|entry| (entry.service.clone(), entry.account.clone()))
+            .collect();
+        return remove_entries(database, targets, yes, false, ctx);
+    }
+
+    if ctx.json {
+        let items: Vec<serde_json::Value> = stale
+            .iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "service": entry.service,
+                    "account": entry.account,
+                    "updated_at": entry.updated_at,
+                })
+            })
+            .collect();
+        return print_json(&serde_json::json!(items));
+    }
+
+    let rows: Vec<Vec<String>> = stale
+        .iter()
+        .map(|entry| {
+            vec![
+                entry.service.clone(),
+                entry.account.clone(),
+                format_timestamp_local(entry.updated_at, ctx.time_format),
+            ]
+        })
+        .collect();
+    println!(
+        "{}",
+        render_table(&["SERVICE", "ACCOUNT", "UPDATED"], &rows)
+    );
+    Ok(())
+}
+
+fn favorite_marker() -> &'static str {
+    if style::is_plain() {
+        "*"
+    } else {
+        "⭐"
+    }
+}
+
+fn validate_tag(tag: &str) -> CredentialResult<String> {
+    let tag = tag.trim();
+    if tag.is_empty() || tag.contains(',') {
+        return Err(CredentialError::invalid_input(&format!(
+            "'{tag}' is not a valid tag (tags can't be empty or contain commas)"
+        )));
+    }
+    Ok(tag.to_string())
+}
+
+fn tag_credentials(
+    tag: &str,
+    services: &[String],
+    add: bool,
+    ctx: &Context,
+) -> CredentialResult<()> {
+    let tag = validate_tag(tag)?;
+    let mut database = load_database()?;
+
+    let mut changed = 0;
+    let mut found = 0;
+    for service in services {
+        let Some(entry) = database.edit_entry(service, None) else {
+            outln!("⚠️  No credential found for '{service}', skipping");
+            continue;
+        };
+        found += 1;
+        let did_change = if add {
+            let had_tag = entry.has_tag(&tag);
+            entry.add_tag(tag.clone());
+            !had_tag
+        } else {
+            entry.remove_tag(&tag)
+        };
+        if did_change {
+            changed += 1;
+        }
+    }
+    if found == 0 {
+        return Err(not_found(&database, &services[0]));
+    }
+
+    if changed > 0 && !persist(ctx, &database)? {
+        return Ok(());
+    }
+
+    let action = if add { "Tagged" } else { "Untagged" };
+    outln!("🏷️  {action} {changed} credential(s) with '{tag}'");
+    Ok(())
+}
+
+fn rename_tag(old: &str, new: &str, ctx: &Context) -> CredentialResult<()> {
+    let new = validate_tag(new)?;
+    let mut database = load_database()?;
+
+    let renamed = database.rename_tag(old, &new);
+    if renamed == 0 {
+        outln!("ℹ️  No credentials are tagged '{old}'.");
+        return Ok(());
+    }
+    if !persist(ctx, &database)? {
+        return Ok(());
+    }
+
+    outln!("🏷️  Renamed '{old}' to '{new}' on {renamed} credential(s)");
+    Ok(())
+}
+
+fn set_favorite(service: &str, favorite: bool, ctx: &Context) -> CredentialResult<()> {
+    let mut database = load_database()?;
+
+    let account = find_credential(&database, service, None)?.account.clone();
+    let entry = database
+        .edit_entry(service, Some(&account))
+        .expect("entry found above");
+    let name = entry.service.clone();
+    if entry.favorite == favorite {
+        let state = if favorite { "already" } else { "not" };
+        outln!("ℹ️  '{name}' is {state} a favorite.");
+        return Ok(());
+    }
+    entry.favorite = favorite;
+    if !persist(ctx, &database)? {
+        return Ok(());
+    }
+
+    if favorite {
+        outln!("⭐ Pinned '{name}' to the top of the list");
+    } else {
+        outln!("✅ Unpinned '{name}'");
+    }
+    Ok(())
+}
+
+fn validate_required(input: &str, field: &str) -> Result<(), String> {
+    if input.trim().is_empty() {
+        return Err(format!("{field} cannot be empty"));
+    }
+    Ok(())
+}
+
+fn required_flag(value: String, flag: &str) -> CredentialResult<String> {
+    validate_required(&value, flag).map_err(|e| CredentialError::invalid_input(&e))?;
+    Ok(value.trim().to_string())
+}
+
+fn validate_url(input: &str) -> Result<(), String> {
+    if input.trim().is_empty() {
+        return Ok(());
+    }
+    normalize_url(input).map(|_| ()).map_err(|e| e.to_string())
+}
+
+fn prompt_url(current: Option<&str>) -> CredentialResult<Option<String>> {
+    let mut input = Input::<String>::new()
+        .with_prompt("URL (optional)")
+        .allow_empty(true)
+        .validate_with(|input: &String| validate_url(input));
+    if let Some(current) = current {
+        input = input.default(current.to_string());
+    }
+
+    let url = input
+        .interact_text()
+        .map_err(|_| CredentialError::user_cancelled())?;
+    if url.trim().is_empty() {
+        return Ok(None);
+    }
+    normalize_url(&url).map(Some)
+}
+
+// Empty input means no TOTP secret; anything else must be valid base32. The
+// code settings are only asked for when the standard ones don't apply.
+fn prompt_totp() -> CredentialResult<Option<(String, TotpParams)>> {
+    let Some(secret) = prompt_totp_secret()? else {
+        return Ok(None);
+    };
+    let standard = Confirm::new()
+        .with_prompt("Standard codes (SHA1, 6 digits, every 30s)?")
+        .default(true)
+        .interact()
+        .map_err(|_| CredentialError::user_cancelled())?;
+    if standard {
+        return Ok(Some((secret, TotpParams::default())));
+    }
+
+    let algorithms = [
+        TotpAlgorithm::Sha1,
+        TotpAlgorithm::Sha256,
+        TotpAlgorithm::Sha512,
+    ];
+    let algorithm = dialoguer::Select::new()
+        .with_prompt("Algorithm")
+        .items(&algorithms)
+        .default(0)
+        .interact()
+        .map_err(|_| CredentialError::user_cancelled())?;
+    let digits: usize = Input::new()
+        .with_prompt("Digits")
+        .default(6)
+        .validate_with(|digits: &usize| {
+            TotpParams {
+                digits: *digits,
+                ..TotpParams::default()
+            }
+            .validate()
+        })
+        .interact_text()
+        .map_err(|_| CredentialError::user_cancelled())?;
+    let period: u64 = Input::new()
+        .with_prompt("Seconds per code")
+        .default(30)
+        .validate_with(|period: &u64| {
+            TotpParams {
+                period: *period,
+                ..TotpParams::default()
+            }
+            .validate()
+        })
+        .interact_text()
+        .map_err(|_| CredentialError::user_cancelled())?;
+
+    let params = TotpParams {
+        algorithm: algorithms[algorithm],
+        digits,
+        period,
+    };
+    Ok(Some((secret, params)))
+}
+
+fn prompt_totp_secret() -> CredentialResult<Option<String>> {
+    let secret = Password::new()
+        .with_prompt("TOTP secret (base32, optional)")
+        .allow_empty_password(true)
+        .validate_with(|input: &String| {
+            if input.trim().is_empty() {
+                Ok(())
+            } else {
+                totp::validate_secret(input)
+            }
+        })
+        .interact()
+        .map_err(|_| CredentialError::user_cancelled())?;
+
+    let secret = totp::normalize_secret(&secret);
+    Ok((!secret.is_empty()).then_some(secret))
+}
+
+fn open_credential_url(service: &str, account: Option<&str>) -> CredentialResult<()> {
+    let database = load_database()?;
+
+    let entry = find_credential(&database, service, account)?;
+    let url = entry
+        .url
+        .as_deref()
+        .ok_or_else(|| CredentialError::invalid_input(&format!("No URL stored for '{service}'")))?;
+    // Entries saved before URLs were normalized may still hold a bare host.
+    let url = normalize_url(url)?;
+
+    open::that(&url)?;
+    outln!("🌐 Opened {url}");
+    Ok(())
+}
+
+fn totp_credential(
+    service: &str,
+    account: Option<&str>,
+    copy: bool,
+    ctx: &Context,
+) -> CredentialResult<()> {
+    let database = load_database()?;
+
+    let entry = find_credential(&database, service, account)?;
+    let secret = entry.totp_secret.as_deref().ok_or_else(|| {
+        CredentialError::invalid_input(&format!("No TOTP secret stored for '{service}'"))
+    })?;
+    let (code, remaining) = totp::current_code(secret, entry.totp_params)
+        .map_err(|e| CredentialError::invalid_input(&e))?;
+
+    if ctx.json {
+        return print_json(&serde_json::json!({
+            "service": entry.service,
+            "code": code,
+            "remaining": remaining,
+            "period": entry.totp_params.period,
+        }));
+    }
+    if copy {
+        outln!("⏱️  Valid for {remaining}s");
+        return copy_with_timeout(
+            &code,
+            &format!("TOTP code for '{service}'"),
+            ctx.config.clipboard_timeout.min(remaining),
+        );
+    }
+
+    outln!("🔐 {code} (expires in {remaining}s)");
+    Ok(())
+}
+
+// `regex_field` switches from fuzzy matching to a regular expression.
+fn search_credentials(query: &str, regex_field: Option<SearchField>) -> CredentialResult<()> {
+    let database = load_database()?;
+
+    let matches = match regex_field {
+        Some(field) => {
+            let pattern = regex::Regex::new(query).map_err(|e| {
+                CredentialError::invalid_input(&format!("Invalid regular expression: {e}"))
+            })?;
+            database.search_regex(&pattern, field)
+        }
+        None => database.search(query),
+    };
+
+    if matches.is_empty() {
+        return Err(CredentialError::credential_not_found(query));
+    }
+
+    outln!("🔍 Matches for '{query}' ({} entries):", matches.len());
+    for (i, entry) in matches.iter().enumerate() {
+        outln!("  {}. {} ({})", i + 1, entry.service, entry.account);
+    }
+    Ok(())
+}
+
+fn list_accounts(pattern: &str, json: bool) -> CredentialResult<()> {
+    let database = load_database()?;
+
+    let accounts = database.accounts_matching(pattern);
+    if json {
+        let accounts: Vec<_> = accounts
+            .iter()
+            .map(|(service, account)| serde_json::json!({ "service": service, "account": account }))
+            .collect();
+        return print_json(&serde_json::Value::from(accounts));
+    }
+    if accounts.is_empty() {
+        return Err(CredentialError::credential_not_found(pattern));
+    }
+
+    outln!("👤 Accounts for services matching '{pattern}':");
+    for (service, account) in &accounts {
+        outln!("  {service}: {account}");
+    }
+    Ok(())
+}
+
+fn audit_credentials(json: bool) -> CredentialResult<()> {
+    let database = load_database()?;
+
+    if database.is_empty() {
+        return Err(CredentialError::credentials_not_stored());
+    }
+
+    let reused: Vec<Vec<&str>> = database
+        .find_duplicate_secrets()
+        .iter()
+        .map(|group| group.iter().map(|entry| entry.service.as_str()).collect())
+        .collect();
+    progress::catch_interrupts();
+    let entries = database.list_entries();
+    let bar = progress::progress_bar(entries.len(), "Auditing");
+    let mut weak: Vec<(&str, Strength)> = Vec::new();
+    for entry in entries {
+        let strength = estimate_strength(&entry.secret);
+        if strength < MIN_ACCEPTABLE_STRENGTH {
+            weak.push((entry.service.as_str(), strength));
+        }
+        progress::tick(&bar)?;
+    }
+    bar.finish_and_clear();
+
+    if json {
+        let weak: Vec<serde_json::Value> = weak
+            .iter()
+            .map(|(service, strength)| {
+                serde_json::json!({ "service": service, "strength": strength.to_string() })
+            })
+            .collect();
+        return print_json(&serde_json::json!({ "reused": reused, "weak": weak }));
+    }
+
+    if reused.is_empty() && weak.is_empty() {
+        outln!("✅ No reused or weak passwords found.");
+        return Ok(());
+    }
+
+    if !reused.is_empty() {
+        outln!("🔁 Reused passwords:");
+        for group in &reused {
+            outln!(
+                "  {} services share one password: {}",
+                group.len(),
+                group.join(", ")
+            );
+        }
+    }
+    if !weak.is_empty() {
+        outln!("⚠️  Weak passwords:");
+        for (service, strength) in &weak {
+            outln!("  {service} ({strength})");
+        }
+    }
+    Ok(())
+}
+
+fn check_pwned(yes: bool, offline: bool, ctx: &Context) -> CredentialResult<()> {
+    use crate::util::pwned::{self, HibpClient};
+
+    let client = pwned::online_client(offline || ctx.config.offline, HibpClient::new)?;
+
+    let database = load_database()?;
+    let entries = database.list_entries();
+    if entries.is_empty() {
+        return Err(CredentialError::credentials_not_stored());
+    }
+
+    if !yes {
+        let confirm = Confirm::new()
+            .with_prompt(
+                "Send the first 5 characters of each secret's SHA-1 hash to api.pwnedpasswords.com?",
+            )
+            .default(true)
+            .interact()
+            .map_err(|_| CredentialError::user_cancelled())?;
+        if !confirm {
+            return Ok(());
+        }
+    }
+
+    let secrets: Vec<&str> = entries.iter().map(|entry| entry.secret.as_str()).collect();
+    progress::catch_interrupts();
+    let bar = progress::progress_bar(secrets.len(), "Checking");
+    let counts = pwned::breach_counts(&client, &secrets, || progress::tick(&bar))?;
+    bar.finish_and_clear();
+    let breached: Vec<(&str, u64)> = entries
+        .iter()
+        .zip(counts)
+        .filter(|(_, count)| *count > 0)
+        .map(|(entry, count)| (entry.service.as_str(), count))
+        .collect();
+
+    if ctx.json {
+        let breached: Vec<serde_json::Value> = breached
+            .iter()
+            .map(|(service, count)| serde_json::json!({ "service": service, "count": count }))
+            .collect();
+        return print_json(&serde_json::json!({
+            "checked": entries.len(),
+            "breached": breached,
+        }));
+    }
+
+    if breached.is_empty() {
+        outln!(
+            "✅ None of your {} secrets appear in known breaches.",
+            entries.len()
+        );
+        return Ok(());
+    }
+
+    outln!("🚨 Secrets found in known breaches:");
+    for (service, count) in &breached {
+        outln!("  {service}: seen {count} times");
+    }
+    outln!("💡 Change these with 'crab edit <service>'.");
+    Ok(())
+}
+
+fn edit_credential(
+    ctx: &Context,
+    service: &str,
+    which: Option<&str>,
+    force: bool,
+) -> CredentialResult<()> {
+    let mut database = load_database()?;
+    let account = resolve_account(&database, service, which, "--which")?;
+    let other_accounts = other_accounts(&database, service, account.as_deref());
+    // Names of every other entry, so a rename can't collide with one of them.
+    let taken: Vec<String> = database
+        .list_entries()
+        .into_iter()
+        .filter(|entry| !same_service(&entry.service, service))
+        .map(|entry| entry.service.clone())
+        .collect();
+
+    match database.edit_entry(service, account.as_deref()) {
+        Some(entry) => {
+            outln!("📝 Editing Credential for '{service}'");
+            outln!("Current values:");
+            outln!("  Service: {}", entry.service);
+            outln!("  Account: {}", entry.account);
+            if !entry.tags.is_empty() {
+                outln!("  Tags: {}", entry.tags.join(", "));
+            }
+
+            let new_service: String = Input::new()
+                .with_prompt("New Service Name")
+                .default(entry.service.clone())
+                .validate_with(|input: &String| {
+                    validate_required(input, "Service name")?;
+                    match taken.iter().find(|other| same_service(other, input)) {
+                        Some(other) => Err(format!("Service '{other}' already exists")),
+                        None => Ok(()),
+                    }
+                })
+                .interact_text()
+                .map_err(|_| CredentialError::user_cancelled())?;
+
+            let new_account: String = Input::new()
+                .with_prompt("New Account")
+                .default(entry.account.clone())
+                .validate_with(|input: &String| {
+                    validate_required(input, "Account name")?;
+                    match other_accounts.iter().find(|other| *other == input) {
+                        Some(other) => Err(format!("Account '{other}' already exists")),
+                        None => Ok(()),
+                    }
+                })
+                .interact_text()
+                .map_err(|_| CredentialError::user_cancelled())?;
+
+            let new_url = prompt_url(entry.url.as_deref())?;
+
+            let new_tags: String = Input::new()
+                .with_prompt("Tags (comma-separated)")
+                .default(entry.tags.join(", "))
+                .allow_empty(true)
+                .interact_text()
+                .map_err(|_| CredentialError::user_cancelled())?;
+            let new_tags = parse_tags(&new_tags);
+
+            let change_secret = Confirm::new()
+                .with_prompt("Change Secret?")
+                .interact()
+                .map_err(|_| CredentialError::user_cancelled())?;
+
+            let rename = (new_service != entry.service).then_some(new_service);
+            if new_account != entry.account {
+                entry.update_account(new_account);
+            }
+            if new_url != entry.url {
+                entry.update_url(new_url);
+            }
+            if new_tags != entry.tags {
+                entry.set_tags(new_tags);
+            }
+
+            let mut generated = None;
+            if change_secret {
+                let generate = Confirm::new()
+                    .with_prompt("Generate a strong password for you?")
+                    .default(false)
+                    .interact()
+                    .map_err(|_| CredentialError::user_cancelled())?;
+                let new_secret = if generate {
+                    generate_password(ctx.config.password_length, PasswordOptions::default())
+                } else {
+                    prompt_new_secret("New Secret")?
+                };
+                enforce_policy(&new_secret, ctx, force)?;
+                if generate {
+                    generated = Some(zeroize::Zeroizing::new(new_secret.clone()));
+                }
+                entry.update_secret(new_secret);
+                entry.prune_history(ctx.config.history_limit);
+            }
+
+            let totp_action = if entry.totp_secret.is_some() {
+                "Change or remove TOTP secret?"
+            } else {
+                "Add a TOTP secret?"
+            };
+            let change_totp = Confirm::new()
+                .with_prompt(totp_action)
+                .default(false)
+                .interact()
+                .map_err(|_| CredentialError::user_cancelled())?;
+            if change_totp {
+                match prompt_totp()? {
+                    Some((secret, params)) => entry.update_totp_secret(Some(secret), params),
+                    None => entry.update_totp_secret(None, TotpParams::default()),
+                }
+            }
+
+            let rotate_after: u32 = Input::new()
+                .with_prompt("Rotate every N days (0 = never)")
+                .default(entry.rotate_after_days.unwrap_or(0))
+                .interact_text()
+                .map_err(|_| CredentialError::user_cancelled())?;
+            entry.set_rotation(Some(rotate_after));
+
+            prompt_custom_fields(entry)?;
+
+            let account = entry.account.clone();
+            if let Some(name) = rename {
+                database.move_entry(service, Some(&account), name);
+            }
+            if !persist(ctx, &database)? {
+                return Ok(());
+            }
+
+            outln!("✅ Credential Updated Successfully!");
+            if let Some(secret) = generated {
+                offer_to_copy_generated(&secret, ctx)?;
+            }
+            Ok(())
+        }
+        None => Err(not_found(&database, service)),
+    }
+}
+
+// Adds, changes, or (with an empty value) removes extra fields until the user
+// enters an empty name.
+fn prompt_custom_fields(entry: &mut CredentialEntry) -> CredentialResult<()> {
+    if !entry.fields.is_empty() {
+        outln!(
+            "Custom fields: {}",
+            entry.fields.keys().cloned().collect::<Vec<_>>().join(", ")
+        );
+    }
+    let edit_fields = Confirm::new()
+        .with_prompt("Add or change custom fields?")
+        .default(false)
+        .interact()
+        .map_err(|_| CredentialError::user_cancelled())?;
+    if !edit_fields {
+        return Ok(());
+    }
+
+    loop {
+        let name: String = Input::new()
+            .with_prompt("Field name (empty to finish)")
+            .allow_empty(true)
+            .interact_text()
+            .map_err(|_| CredentialError::user_cancelled())?;
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Ok(());
+        }
+
+        let current = entry.fields.get(&name).cloned().unwrap_or_default();
+        let value: String = if is_sensitive_field(&name) {
+            Password::new()
+                .with_prompt(format!("{name} (empty to remove)"))
+                .allow_empty_password(true)
+                .interact()
+        } else {
+            Input::new()
+                .with_prompt(format!("{name} (empty to remove)"))
+                .default(current)
+                .allow_empty(true)
+                .interact_text()
+        }
+        .map_err(|_| CredentialError::user_cancelled())?;
+
+        if value.is_empty() {
+            entry.remove_field(&name);
+        } else if entry.fields.get(&name) != Some(&value) {
+            entry.set_field(name, value);
+        }
+    }
+}
+
+// Applies exactly the fields given on the command line, without prompting.
+fn edit_credential_from_flags(
+    ctx: &Context,
+    service: &str,
+    args: EditArgs,
+) -> CredentialResult<()> {
+    let change_totp = args.has_totp_changes();
+    let new_service = args
+        .new_service
+        .map(|name| required_flag(name, "--service"))
+        .transpose()?;
+    let new_account = args
+        .account
+        .map(|account| required_flag(account, "--account"))
+        .transpose()?;
+    let new_url = match args.url.as_deref().map(str::trim) {
+        Some("") => Some(None),
+        Some(url) => Some(Some(normalize_url(url)?)),
+        None => None,
+    };
+    let new_secret = if args.secret_stdin {
+        Some(read_secret_from_stdin()?)
+    } else {
+        None
+    };
+    if let Some(secret) = &new_secret {
+        enforce_policy(secret, ctx, args.force)?;
+    }
+
+    let mut database = load_database()?;
+    if database.find_entry(service, None).is_none() {
+        return Err(not_found(&database, service));
+    }
+    let account = resolve_account(&database, service, args.which.as_deref(), "--which")?;
+    if let Some(new_account) = &new_account {
+        if other_accounts(&database, service, account.as_deref()).contains(new_account) {
+            return Err(CredentialError::invalid_input(&format!(
+                "Account '{new_account}' already exists for '{service}'"
+            )));
+        }
+    }
+    if let Some(name) = &new_service {
+        if !same_service(name, service) && database.find_entry(name, None).is_some() {
+            return Err(CredentialError::invalid_input(&format!(
+                "Service '{name}' already exists"
+            )));
+        }
+    }
+
+    let entry = database
+        .edit_entry(service, account.as_deref())
+        .ok_or_else(|| {
+            CredentialError::invalid_input(&format!(
+                "'{service}' has no account '{}'",
+                account.as_deref().unwrap_or_default()
+            ))
+        })?;
+    if let Some(account) = new_account {
+        entry.update_account(account);
+    }
+    if let Some(url) = new_url {
+        entry.update_url(url);
+    }
+    if let Some(notes) = &args.notes {
+        entry.update_notes(notes);
+    }
+    if let Some(secret) = new_secret {
+        entry.update_secret(secret);
+        entry.prune_history(ctx.config.history_limit);
+    }
+    if let Some(days) = args.rotate_after {
+        entry.set_rotation(Some(days));
+    }
+    if change_totp {
+        let secret = entry.totp_secret.clone().ok_or_else(|| {
+            CredentialError::invalid_input(&format!(
+                "'{service}' has no TOTP secret; add one with 'crab edit {service}'"
+            ))
+        })?;
+        let current = entry.totp_params;
+        let params = TotpParams {
+            algorithm: args.totp_algorithm.unwrap_or(current.algorithm),
+            digits: args.totp_digits.unwrap_or(current.digits),
+            period: args.totp_period.unwrap_or(current.period),
+        };
+        params
+            .validate()
+            .map_err(|e| CredentialError::invalid_input(&e))?;
+        entry.update_totp_secret(Some(secret), params);
+    }
+    let account = entry.account.clone();
+    if let Some(name) = new_service {
+        database.move_entry(service, Some(&account), name);
+    }
+    if !persist(ctx, &database)? {
+        return Ok(());
+    }
+
+    outln!("✅ Credential Updated Successfully!");
+    Ok(())
+}
+
+fn show_history(
+    service: &str,
+    account: Option<&str>,
+    show: bool,
+    ctx: &Context,
+) -> CredentialResult<()> {
+    let database = load_database()?;
+
+    let entry = find_credential(&database, service, account)?;
+
+    if ctx.json {
+        let history: Vec<serde_json::Value> = entry
+            .history
+            .iter()
+            .rev()
+            .map(|past| {
+                serde_json::json!({
+                    "secret": show.then_some(&past.secret),
+                    "replaced_at": past.replaced_at,
+                })
+            })
+            .collect();
+        return print_json(&serde_json::json!(history));
+    }
+
+    if entry.history.is_empty() {
+        outln!("ℹ️  No previous secrets stored for '{service}'.");
+        return Ok(());
+    }
+
+    outln!(
+        "🕘 Previous secrets for '{service}' ({} entries, newest first):",
+        entry.history.len()
+    );
+    for (i, past) in entry.history.iter().rev().enumerate() {
+        let secret = if show {
+            past.secret.as_str()
+        } else {
+            MASKED_SECRET
+        };
+        outln!(
+            "  {}. {secret} (replaced {})",
+            i + 1,
+            format_timestamp_local(past.replaced_at, ctx.time_format)
+        );
+    }
+    Ok(())
+}
+
+fn remove_credentials(
+    services: &[String],
+    account: Option<&str>,
+    yes: bool,
+    permanent: bool,
+    ctx: &Context,
+) -> CredentialResult<()> {
+    let database = load_database()?;
+
+    let (found, missing): (Vec<&String>, Vec<&String>) = services
+        .iter()
+        .partition(|service| database.find_entry(service, None).is_some());
+
+    for service in &missing {
+        outln!("⚠️  No credential found for '{service}', skipping");
+    }
+    if found.is_empty() {
+        return Err(not_found(&database, &services[0]));
+    }
+    let targets = found
+        .into_iter()
+        .map(|service| {
+            let entry = find_credential(&database, service, account)?;
+            Ok((service.clone(), entry.account.clone()))
+        })
+        .collect::<CredentialResult<Vec<_>>>()?;
+    remove_entries(database, targets, yes, permanent, ctx)
+}
+
+// Removes exactly the given (service, account) pairs, after one confirmation.
+fn remove_entries(
+    mut database: CredentialDatabase,
+    targets: Vec<(String, String)>,
+    yes: bool,
+    permanent: bool,
+    ctx: &Context,
+) -> CredentialResult<()> {
+    // The account is only worth showing when the service has several.
+    let found: Vec<(String, String, Option<String>)> = targets
+        .into_iter()
+        .map(|(service, account)| {
+            let shown = (database.entries_for(&service).len() > 1).then(|| account.clone());
+            (service, account, shown)
+        })
+        .collect();
+    let label = |service: &str, account: &Option<String>| match account {
+        Some(account) => format!("{service} ({account})"),
+        None => service.to_string(),
+    };
+
+    if !yes && !ctx.dry_run {
+        outln!("The following credentials will be removed:");
+        for (service, _, shown) in &found {
+            outln!("  - {}", label(service, shown));
+        }
+        let confirm = Confirm::new()
+            .with_prompt(format!(
+                "Are you sure you want to remove {} credential(s)?",
+                found.len()
+            ))
+            .interact()
+            .map_err(|_| CredentialError::user_cancelled())?;
+        if !confirm {
+            return Ok(());
+        }
+    }
+
+    let now = now_unix();
+    for (service, account, _) in &found {
+        if permanent {
+            database.remove_entry(service, Some(account));
+        } else {
+            database.trash_entry(service, Some(account), now);
+        }
+    }
+    if !persist(ctx, &database)? {
+        return Ok(());
+    }
+
+    for (service, _, shown) in &found {
+        let name = label(service, shown);
+        if permanent {
+            outln!("✅ Credential for '{name}' removed successfully!");
+        } else {
+            outln!("🗑️  Moved '{name}' to the trash (undo with 'crab trash restore {service}')");
+        }
+    }
+    Ok(())
+}
+
+fn list_trash(ctx: &Context) -> CredentialResult<()> {
+    let database = load_database()?;
+
+    if ctx.json {
+        let items: Vec<serde_json::Value> = database
+            .trash
+            .iter()
+            .map(|trashed| {
+                serde_json::json!({
+                    "service": trashed.entry.service,
+                    "account": trashed.entry.account,
+                    "deleted_at": trashed.deleted_at,
+                })
+            })
+            .collect();
+        return print_json(&serde_json::json!(items));
+    }
+
+    if database.trash.is_empty() {
+        outln!("🗑️  The trash is empty.");
+        return Ok(());
+    }
+
+    let rows: Vec<Vec<String>> = database
+        .trash
+        .iter()
+        .rev()
+        .map(|trashed| {
+            vec![
+                trashed.entry.service.clone(),
+                trashed.entry.account.clone(),
+                format_timestamp_local(trashed.deleted_at, ctx.time_format),
+            ]
+        })
+        .collect();
+    println!(
+        "{}",
+        render_table(&["SERVICE", "ACCOUNT", "DELETED"], &rows)
+    );
+    Ok(())
+}
+
+fn restore_from_trash(service: &str, ctx: &Context) -> CredentialResult<()> {
+    let mut database = load_database()?;
+
+    // Only the same service and account blocks a restore.
+    let trashed_account = database
+        .trash
+        .iter()
+        .rev()
+        .find(|trashed| same_service(&trashed.entry.service, service))
+        .map(|trashed| trashed.entry.account.clone());
+    if let Some(existing) = trashed_account
+        .as_deref()
+        .and_then(|account| database.find_entry(service, Some(account)))
+    {
+        return Err(CredentialError::invalid_input(&format!(
+            "A credential named '{}' for '{}' already exists; rename it first",
+            existing.service, existing.account
+        )));
+    }
+    if !database.restore_from_trash(service) {
+        return Err(CredentialError::credential_not_found(service));
+    }
+    if !persist(ctx, &database)? {
+        return Ok(());
+    }
+
+    outln!("✅ Restored '{service}' from the trash");
+    Ok(())
+}
+
+fn empty_trash(yes: bool, ctx: &Context) -> CredentialResult<()> {
+    let mut database = load_database()?;
+
+    if database.trash.is_empty() {
+        outln!("🗑️  The trash is already empty.");
+        return Ok(());
+    }
+
+    if ctx.dry_run {
+        outln!(
+            "🧪 Dry run: {} credential(s) in the trash would be permanently deleted.",
+            database.trash.len()
+        );
+        return Ok(());
+    }
+    if !yes {
+        confirm_typed(&format!(
+            "Permanently delete {} credential(s) in the trash?",
+            database.trash.len()
+        ))?;
+    }
+
+    let removed = database.empty_trash();
+    if !persist(ctx, &database)? {
+        return Ok(());
+    }
+
+    outln!("✅ Permanently deleted {removed} credential(s)");
+    Ok(())
+}
+
+// Generates a fresh secret, keeps the old one in the history, and hands the
+// new one over on the clipboard so it can be pasted into the site's form.
+fn rotate_credential(
+    service: &str,
+    account: Option<&str>,
+    length: Option<usize>,
+    no_symbols: bool,
+    show: bool,
+    force: bool,
+    ctx: &Context,
+) -> CredentialResult<()> {
+    let secret = zeroize::Zeroizing::new(generate_password(
+        length.unwrap_or(ctx.config.password_length),
+        PasswordOptions {
+            symbols: !no_symbols,
+            ..PasswordOptions::default()
+        },
+    ));
+    enforce_policy(&secret, ctx, force)?;
+
+    let mut database = load_database()?;
+    let account = find_credential(&database, service, account)?
+        .account
+        .clone();
+    let entry = database
+        .edit_entry(service, Some(&account))
+        .expect("entry found above");
+    let name = entry.service.clone();
+    entry.update_secret(secret.to_string());
+    entry.prune_history(ctx.config.history_limit);
+
+    if !persist(ctx, &database)? {
+        return Ok(());
+    }
+    outln!("🔄 Rotated the secret for '{name}' (the old one is in 'crab history {name}')");
+
+    if show {
+        confirm_reveal(ctx)?;
+        outln!("  New secret: {}", secret.as_str());
+    }
+    copy_with_timeout(
+        &secret,
+        &format!("New secret for '{name}'"),
+        ctx.config.clipboard_timeout,
+    )
+}
+
+fn generate_credential(ctx: &Context, args: GenArgs) -> CredentialResult<()> {
+    let next: Box<dyn Fn() -> String> = match args.words {
+        Some(count) => {
+            let words = load_wordlist(args.wordlist.as_deref(), ctx)?;
+            errln!(
+                "🎲 {:.1} bits of entropy ({count} words from a list of {})",
+                passphrase::entropy_bits(words.len(), count),
+                words.len()
+            );
+            let separator = args.separator.clone();
+            Box::new(move || passphrase::generate_passphrase(&words, count, &separator))
+        }
+        None => {
+            let opts = args.password_options();
+            if !opts.has_classes() {
+                return Err(CredentialError::invalid_input(
+                    "At least one character class must be left enabled",
+                ));
+            }
+            let length = args.length.unwrap_or(ctx.config.password_length);
+            Box::new(move || generate_password(length, opts))
+        }
+    };
+
+    if let Some(service) = args.save {
+        let password = zeroize::Zeroizing::new(next());
+        if !save_generated(ctx, service, args.account, &password)? || !args.copy {
+            return Ok(());
+        }
+        return copy_with_timeout(
+            &password,
+            "Generated password",
+            ctx.config.clipboard_timeout,
+        );
+    }
+    if args.copy {
+        let password = zeroize::Zeroizing::new(next());
+        return copy_with_timeout(
+            &password,
+            "Generated password",
+            ctx.config.clipboard_timeout,
+        );
+    }
+    for _ in 0..args.count {
+        println!("{}", next());
+    }
+    Ok(())
+}
+
+// --wordlist wins over the `wordlist` config setting.
+fn load_wordlist(path: Option<&std::path::Path>, ctx: &Context) -> CredentialResult<Vec<String>> {
+    let path = path.or(ctx.config.wordlist.as_deref()).ok_or_else(|| {
+        CredentialError::invalid_input(
+            "No word list to draw from; pass --wordlist or set `wordlist` in the config",
+        )
+    })?;
+    let text = std::fs::read_to_string(path)?;
+    passphrase::parse_wordlist(&text).map_err(|e| {
+        CredentialError::invalid_input(&format!("Invalid word list {}: {e}", path.display()))
+    })
+}
+
+// Returns whether the entry was written (a dry run leaves it unsaved).
+fn save_generated(
+    ctx: &Context,
+    service: String,
+    account: Option<String>,
+    secret: &str,
+) -> CredentialResult<bool> {
+    let service = required_flag(service, "--save")?;
+    let account = match account {
+        Some(account) => required_flag(account, "--account")?,
+        None => Input::<String>::new()
+            .with_prompt("Account name")
+            .validate_with(|input: &String| validate_required(input, "Account name"))
+            .interact_text()
+            .map_err(|_| CredentialError::user_cancelled())?,
+    };
+    enforce_policy(secret, ctx, false)?;
+
+    let mut database = load_database()?;
+    if database.find_entry(&service, Some(&account)).is_some() {
+        return Err(CredentialError::invalid_input(&format!(
+            "Account '{account}' for '{service}' already exists"
+        )));
+    }
+    database.add_entry(CredentialEntry::new(
+        service.clone(),
+        account,
+        secret.to_string(),
+    ));
+    if !persist(ctx, &database)? {
+        return Ok(false);
+    }
+
+    outln!("✅ Saved a generated password for '{service}' (copy it with 'crab copy {service}')");
+    Ok(true)
+}
+
+fn export_credentials(
+    format: ExportFormat,
+    output: Option<PathBuf>,
+    force: bool,
+) -> CredentialResult<()> {
+    let database = load_database()?;
+
+    if !force {
+        errln!("⚠️  The export contains all of your secrets in plaintext.");
+        let confirm = Confirm::new()
+            .with_prompt("Export anyway?")
+            .default(false)
+            .interact()
+            .map_err(|_| CredentialError::user_cancelled())?;
+
+        if !confirm {
+            return Err(CredentialError::user_cancelled());
+        }
+    }
+
+    match output {
+        Some(path) => {
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create(true).truncate(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+            let file = options.open(&path)?;
+            // An existing file keeps its old mode when truncated.
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+            }
+            export_database(&database, format, std::io::BufWriter::new(file))?;
+            errln!(
+                "✅ Exported {} entries to {}",
+                database.len(),
+                path.display()
+            );
+        }
+        None => export_database(&database, format, std::io::stdout().lock())?,
+    }
+    Ok(())
+}
+
+fn import_credentials(
+    path: &std::path::Path,
+    format: Option<ImportFormat>,
+    merge: MergeStrategy,
+    account: &str,
+    keyfile: Option<&std::path::Path>,
+    ctx: &Context,
+) -> CredentialResult<()> {
+    use crate::storage::import;
+
+    let account = required_flag(account.to_owned(), "--account")?;
+    let data = std::fs::read(path)?;
+    let format = format
+        .or_else(|| ImportFormat::detect(path, &data))
+        .ok_or_else(|| import::unknown_format_error(path))?;
+    let mut parsed = match format {
+        ImportFormat::Kdbx => import::parse_kdbx(data.as_slice(), prompt_kdbx_key(keyfile)?)?,
+        _ if keyfile.is_some() => {
+            return Err(CredentialError::invalid_input(
+                "--keyfile only applies to KeePass (.kdbx) databases",
+            ))
+        }
+        _ => import::parse_import(format, data.as_slice())?,
+    };
+    for entry in &mut parsed.entries {
+        if entry.account.is_empty() {
+            entry.account.clone_from(&account);
+        }
+    }
+
+    let mut database = load_database()?;
+    // Entries are applied to the in-memory copy only; an interrupt before the
+    // single save below leaves the vault file as it was.
+    progress::catch_interrupts();
+    let bar = progress::progress_bar(parsed.entries.len(), "Importing");
+    let mut summary = import::ImportSummary::default();
+    for entry in parsed.entries {
+        import::apply_import_entry(&mut database, entry, merge, &mut summary);
+        progress::tick(&bar)?;
+    }
+    bar.finish_and_clear();
+
+    if !parsed.errors.is_empty() {
+        outln!(
+            "⚠️  {} records were skipped as malformed or without a login:",
+            parsed.errors.len()
+        );
+        for error in &parsed.errors {
+            outln!("  - {error}");
+        }
+    }
+
+    let changed = summary.imported + summary.overwritten + summary.renamed > 0;
+    if (changed || ctx.dry_run) && !persist(ctx, &database)? {
+        return Ok(());
+    }
+
+    outln!(
+        "✅ {} imported, {} skipped, {} overwritten, {} renamed",
+        summary.imported,
+        summary.skipped,
+        summary.overwritten,
+        summary.renamed
+    );
     Ok(())
 }
 
-fn delete_credential() -> CredentialResult<()> {
+// A KeePass database may be locked by a password, a key file, or both, so
+// the password may be left empty when a key file is given.
+fn prompt_kdbx_key(
+    keyfile: Option<&std::path::Path>,
+) -> CredentialResult<crate::storage::import::KdbxKey> {
+    let keyfile = keyfile
+        .map(std::fs::read)
+        .transpose()?
+        .map(zeroize::Zeroizing::new);
+    let password = Password::new()
+        .with_prompt("KeePass database password")
+        .allow_empty_password(keyfile.is_some())
+        .interact()
+        .map_err(|_| CredentialError::user_cancelled())?;
+    Ok(crate::storage::import::KdbxKey {
+        password: (!password.is_empty()).then(|| zeroize::Zeroizing::new(password)),
+        keyfile,
+    })
+}
+
+fn merge_database(
+    other: &std::path::Path,
+    strategy: ConflictStrategy,
+    ctx: &Context,
+) -> CredentialResult<()> {
+    let theirs = crate::storage::file::load_database_from(other)?;
+    let mut database = load_database()?;
+
+    let summary = database.merge(theirs, strategy);
+    let changed = summary.added + summary.overwritten > 0;
+    if (changed || ctx.dry_run) && !persist(ctx, &database)? {
+        return Ok(());
+    }
+
+    outln!(
+        "✅ {} added, {} overwritten, {} skipped",
+        summary.added,
+        summary.overwritten,
+        summary.skipped
+    );
+    Ok(())
+}
+
+fn diff_database(other: &std::path::Path, json: bool) -> CredentialResult<()> {
+    // Unlocking mine first lets a copy of the same vault reuse its key.
+    let database = load_database()?;
+    let theirs = crate::storage::file::load_database_from(other)?;
+    let diff = database.diff(&theirs);
+
+    if json {
+        return print_json(&serde_json::to_value(&diff)?);
+    }
+    if diff.is_empty() {
+        outln!("✅ Both vaults hold the same credentials.");
+        return Ok(());
+    }
+
+    outln!("🔍 Compared with {}:", other.display());
+    if !diff.only_mine.is_empty() {
+        outln!("Only in mine ({}):", diff.only_mine.len());
+        for service in &diff.only_mine {
+            outln!("  - {service}");
+        }
+    }
+    if !diff.only_theirs.is_empty() {
+        outln!("Only in theirs ({}):", diff.only_theirs.len());
+        for service in &diff.only_theirs {
+            outln!("  + {service}");
+        }
+    }
+    if !diff.differing.is_empty() {
+        outln!("Differs ({}):", diff.differing.len());
+        for entry in &diff.differing {
+            outln!("  ~ {}: {} differs", entry.service, entry.fields.join(", "));
+        }
+    }
+    Ok(())
+}
+
+fn rename_credential(old: &str, new: &str, force: bool, ctx: &Context) -> CredentialResult<()> {
+    validate_required(new, "New service name").map_err(|e| CredentialError::invalid_input(&e))?;
+    let mut database = load_database()?;
+
+    let accounts: Vec<String> = database
+        .entries_for(old)
+        .iter()
+        .map(|entry| entry.account.clone())
+        .collect();
+    if accounts.is_empty() {
+        return Err(not_found(&database, old));
+    }
+    // Accounts of `new` that nothing is being renamed onto are left alone.
+    let colliding: Vec<&String> = if same_service(old, new) {
+        Vec::new()
+    } else {
+        accounts
+            .iter()
+            .filter(|account| database.find_entry(new, Some(account)).is_some())
+            .collect()
+    };
+    if !colliding.is_empty() {
+        if !force {
+            let names: Vec<&str> = colliding.iter().map(|account| account.as_str()).collect();
+            return Err(CredentialError::invalid_input(&format!(
+                "Service '{new}' already has account(s) {} (use --force to overwrite them)",
+                names.join(", ")
+            )));
+        }
+        for account in colliding {
+            database.remove_entry(new, Some(account));
+        }
+    }
+
+    let renamed = database.rename_entry(old, new.to_string());
+    if !persist(ctx, &database)? {
+        return Ok(());
+    }
+
+    if renamed > 1 {
+        outln!("✅ Renamed '{old}' to '{new}' ({renamed} accounts)");
+    } else {
+        outln!("✅ Renamed '{old}' to '{new}'");
+    }
+    Ok(())
+}
+
+fn show_credential(ctx: &Context) -> CredentialResult<()> {
     if !database_exists() {
         return Err(CredentialError::database_not_found());
     }
 
     let database = load_database()?;
-    println!("⚠️  You are about to delete the entire database!");
-    println!("📊 Current database contains {} entries", database.len());
 
-    let confirm = Confirm::new()
-        .with_prompt("Are you sure you want to delete the ENTIRE database? This cannot be undone!")
+    if ctx.json {
+        let path = crate::storage::file::get_database_path()?;
+        let size = get_database_info()?.len();
+        return print_json(&serde_json::json!({
+            "version": database.version,
+            "entries": database.len(),
+            "path": path.display().to_string(),
+            "size": size,
+        }));
+    }
+
+    outln!("📊 Database Information:");
+    outln!("  Version: {}", database.version);
+    outln!("  Entries: {}", database.len());
+
+    match get_database_info() {
+        Ok(metadata) => {
+            outln!("  File size: {} bytes", metadata.len());
+            if let Ok(modified) = metadata.modified() {
+                let modified = unix_secs(modified);
+                outln!(
+                    "  Last modified: {}",
+                    format_timestamp_local(modified, ctx.time_format)
+                );
+            }
+        }
+        Err(e) => {
+            outln!("  Failed to get file info: {e}");
+        }
+    }
+
+    if let Ok(path) = crate::storage::file::get_database_path() {
+        outln!("  Location: {}", path.display());
+    }
+
+    Ok(())
+}
+
+// Offers a picker when the service was left out, but only where someone can
+// answer it; scripts get a usage error instead.
+fn resolve_service(service: Option<String>) -> CredentialResult<String> {
+    if let Some(service) = service {
+        return Ok(service);
+    }
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Err(CredentialError::invalid_input(
+            "A service name is required when not running interactively",
+        ));
+    }
+    pick_service(&load_database()?)
+}
+
+// Long lists are narrowed with the same fuzzy matching `crab search` uses
+// before showing the menu.
+const PICKER_FILTER_THRESHOLD: usize = 15;
+
+fn pick_service(database: &CredentialDatabase) -> CredentialResult<String> {
+    if database.is_empty() {
+        return Err(CredentialError::credentials_not_stored());
+    }
+
+    let mut entries = database.list_entries();
+    sort_entries(&mut entries, SortKey::Name);
+    if entries.len() > PICKER_FILTER_THRESHOLD {
+        let filter: String = Input::new()
+            .with_prompt("Filter services (empty for all)")
+            .allow_empty(true)
+            .interact_text()
+            .map_err(|_| CredentialError::user_cancelled())?;
+        if !filter.trim().is_empty() {
+            entries = database.search(filter.trim());
+            if entries.is_empty() {
+                return Err(not_found(database, filter.trim()));
+            }
+        }
+    }
+    let items: Vec<String> = entries
+        .iter()
+        .map(|entry| format!("{} ({})", entry.service, entry.account))
+        .collect();
+    let selection = dialoguer::Select::new()
+        .with_prompt("Choose a credential")
+        .items(&items)
+        .default(0)
         .interact()
         .map_err(|_| CredentialError::user_cancelled())?;
+    Ok(entries[selection].service.clone())
+}
 
-    if confirm {
-        let create_backup = Confirm::new()
-            .with_prompt("Create a backup before deletion?")
-            .default(true)
+// A bare service that holds several accounts is ambiguous: ask which one on
+// a terminal, otherwise insist on --account.
+fn resolve_account(
+    database: &CredentialDatabase,
+    service: &str,
+    account: Option<&str>,
+    flag: &str,
+) -> CredentialResult<Option<String>> {
+    if let Some(account) = account {
+        return Ok(Some(account.to_string()));
+    }
+    let entries = database.entries_for(service);
+    if entries.len() < 2 {
+        return Ok(None);
+    }
+    let accounts: Vec<&str> = entries.iter().map(|entry| entry.account.as_str()).collect();
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Err(CredentialError::invalid_input(&format!(
+            "'{service}' has several accounts ({}); choose one with {flag}",
+            accounts.join(", ")
+        )));
+    }
+    let selection = dialoguer::Select::new()
+        .with_prompt(format!("Choose a '{service}' account"))
+        .items(&accounts)
+        .default(0)
+        .interact()
+        .map_err(|_| CredentialError::user_cancelled())?;
+    Ok(Some(accounts[selection].to_string()))
+}
+
+// Accounts under `service` other than the one being edited.
+fn other_accounts(
+    database: &CredentialDatabase,
+    service: &str,
+    account: Option<&str>,
+) -> Vec<String> {
+    database
+        .entries_for(service)
+        .into_iter()
+        .filter(|entry| account.is_some_and(|account| account != entry.account))
+        .map(|entry| entry.account.clone())
+        .collect()
+}
+
+fn find_credential<'a>(
+    database: &'a CredentialDatabase,
+    service: &str,
+    account: Option<&str>,
+) -> CredentialResult<&'a CredentialEntry> {
+    let account = resolve_account(database, service, account, "--account")?;
+    database
+        .find_entry(service, account.as_deref())
+        .ok_or_else(|| match account {
+            Some(account) if database.find_entry(service, None).is_some() => {
+                CredentialError::invalid_input(&format!("'{service}' has no account '{account}'"))
+            }
+            _ => not_found(database, service),
+        })
+}
+
+fn select_backup(dir: &std::path::Path, time_format: TimeFormat) -> CredentialResult<PathBuf> {
+    let backups = crate::storage::file::list_backups(dir)?;
+    if backups.is_empty() {
+        return Err(CredentialError::invalid_input(&format!(
+            "No backups found in {}",
+            dir.display()
+        )));
+    }
+
+    let items: Vec<String> = backups
+        .iter()
+        .map(|backup| {
+            let name = backup
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy();
+            format!(
+                "{}  ({name})",
+                format_timestamp_local(backup.timestamp, time_format)
+            )
+        })
+        .collect();
+
+    let selection = dialoguer::Select::new()
+        .with_prompt("Choose a backup to restore")
+        .items(&items)
+        .default(0)
+        .interact()
+        .map_err(|_| CredentialError::user_cancelled())?;
+
+    Ok(backups[selection].path.clone())
+}
+
+fn restore_credential(path: Option<PathBuf>, yes: bool, ctx: &Context) -> CredentialResult<()> {
+    let path = match path {
+        Some(dir) if dir.is_dir() => select_backup(&dir, ctx.time_format)?,
+        Some(path) => path,
+        None => select_backup(&crate::storage::file::backup_dir()?, ctx.time_format)?,
+    };
+
+    if !yes {
+        confirm_typed(&format!(
+            "Replace the current database with {}?",
+            path.display()
+        ))?;
+    }
+
+    let database = crate::storage::file::restore_database(&path, ctx.config.backup_keep)?;
+
+    outln!(
+        "✅ Restored {} entries from {}",
+        database.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+fn purge_backups(
+    keep: Option<usize>,
+    older_than: Option<Duration>,
+    yes: bool,
+) -> CredentialResult<()> {
+    use crate::storage::file::{backup_dir, select_backups_to_purge};
+
+    let dir = backup_dir()?;
+    let listing: Vec<PathBuf> = if dir.exists() {
+        std::fs::read_dir(&dir)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let now = now_unix();
+
+    let selected = select_backups_to_purge(listing, keep, older_than, now);
+    if selected.is_empty() {
+        outln!("ℹ️  No backups to purge in {}", dir.display());
+        return Ok(());
+    }
+
+    if !yes {
+        outln!("The following backups will be deleted:");
+        for backup in &selected {
+            outln!("  - {}", backup.path.display());
+        }
+        let confirm = Confirm::new()
+            .with_prompt(format!("Delete {} backup(s)?", selected.len()))
+            .interact()
+            .map_err(|_| CredentialError::user_cancelled())?;
+        if !confirm {
+            return Ok(());
+        }
+    }
+
+    let mut reclaimed = 0;
+    for backup in &selected {
+        let size = std::fs::metadata(&backup.path)
+            .map(|m| m.len())
+            .unwrap_or(0);
+        std::fs::remove_file(&backup.path)?;
+        reclaimed += size;
+        outln!("🧹 Deleted {}", backup.path.display());
+    }
+    outln!(
+        "✅ Purged {} backup(s), reclaimed {reclaimed} bytes",
+        selected.len()
+    );
+    Ok(())
+}
+
+fn dedup_credentials(yes: bool, ctx: &Context) -> CredentialResult<()> {
+    let mut database = load_database()?;
+
+    let groups = database.find_exact_duplicates();
+    if groups.is_empty() {
+        outln!("✅ No duplicate entries found.");
+        return Ok(());
+    }
+
+    let redundant: usize = groups.iter().map(|group| group.len() - 1).sum();
+    outln!("🔁 Found {} group(s) of identical entries:", groups.len());
+    for group in &groups {
+        let keeper = group[0];
+        outln!(
+            "  - {} ({}): keeping the one created {}, removing {} copy(ies)",
+            keeper.service,
+            keeper.account,
+            format_timestamp_local(keeper.created_at, ctx.time_format),
+            group.len() - 1
+        );
+    }
+
+    if !yes && !ctx.dry_run {
+        let confirm = Confirm::new()
+            .with_prompt(format!("Remove {redundant} duplicate entries?"))
             .interact()
             .map_err(|_| CredentialError::user_cancelled())?;
+        if !confirm {
+            return Ok(());
+        }
+    }
 
-        if create_backup {
-            crate::storage::file::backup_database()?;
+    let removed = database.remove_exact_duplicates();
+    if !persist(ctx, &database)? {
+        return Ok(());
+    }
+
+    outln!("✅ Removed {removed} duplicate entries");
+    Ok(())
+}
+
+fn verify_credentials(json: bool) -> CredentialResult<()> {
+    let issues = crate::storage::file::verify_database()?;
+
+    if json {
+        print_json(&serde_json::json!({ "ok": issues.is_empty(), "issues": issues }))?;
+    } else if issues.is_empty() {
+        outln!("✅ Database is healthy.");
+    } else {
+        outln!("❌ Found {} problem(s):", issues.len());
+        for issue in &issues {
+            outln!("  - {issue}");
+        }
+    }
+
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(CredentialError::IntegrityCheckFailed(issues.len()))
+    }
+}
+
+fn run_doctor(json: bool) -> CredentialResult<()> {
+    use crate::storage::doctor::{run_checks, CheckStatus};
+
+    let checks = run_checks();
+    let failures = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Fail)
+        .count();
+
+    if json {
+        print_json(&serde_json::json!({ "ok": failures == 0, "checks": checks }))?;
+    } else {
+        for check in &checks {
+            let label = match check.status {
+                CheckStatus::Pass => "✅ PASS",
+                CheckStatus::Warn => "⚠️  WARN",
+                CheckStatus::Fail => "❌ FAIL",
+            };
+            outln!("{label} {}: {}", check.name, check.detail);
+            if let Some(hint) = &check.hint {
+                outln!("     {hint}");
+            }
         }
+    }
+
+    if failures == 0 {
+        Ok(())
+    } else {
+        Err(CredentialError::DiagnosticsFailed(failures))
+    }
+}
+
+fn rekey_vault(config: &Config) -> CredentialResult<()> {
+    let previous = crate::storage::file::rekey_vault(config.backup_keep)?;
+    let current = crate::crypto::kdf::KdfParams::default();
+    outln!(
+        "🔑 Vault re-encrypted with Argon2id at {} MiB, {} passes (was {} MiB, {} passes).",
+        current.memory_kib / 1024,
+        current.time_cost,
+        previous.memory_kib / 1024,
+        previous.time_cost
+    );
+    Ok(())
+}
+
+fn change_master(config: &Config) -> CredentialResult<()> {
+    crate::storage::file::change_master_password(config.backup_keep)?;
+    outln!("🔑 Master password changed. Older backups still use the previous password.");
+    Ok(())
+}
+
+fn lock_session() -> CredentialResult<()> {
+    crate::crypto::forget_master_key();
+    if crate::crypto::clear_session()? {
+        outln!("🔒 Locked. The master password will be required next time.");
+    } else {
+        outln!("ℹ️  No active session.");
+    }
+    Ok(())
+}
+
+fn delete_credential(yes: bool, config: &Config) -> CredentialResult<()> {
+    if !database_exists() {
+        return Err(CredentialError::database_not_found());
+    }
+
+    let database = load_database()?;
+    outln!("⚠️  You are about to delete the entire database!");
+    outln!("📊 Current database contains {} entries", database.len());
+
+    let create_backup = if yes {
+        true
+    } else {
+        confirm_typed("This deletes the ENTIRE database and cannot be undone.")?;
+        Confirm::new()
+            .with_prompt("Create a backup before deletion?")
+            .default(true)
+            .interact()
+            .map_err(|_| CredentialError::user_cancelled())?
+    };
+
+    if create_backup {
+        backup_database(config.backup_keep, false)?;
+    }
 
-        delete_database()?;
+    delete_database()?;
+    crate::crypto::clear_session()?;
 
-        println!("🗑️  Database deleted successfully!");
+    outln!("🗑️  Database deleted successfully!");
+    Ok(())
+}
+
+// Saves the database, or with --dry-run prints what saving would change and
+// leaves the file alone. Returns whether anything was written.
+fn persist(ctx: &Context, database: &CredentialDatabase) -> CredentialResult<bool> {
+    if !ctx.dry_run {
+        save_database(database)?;
+        return Ok(true);
+    }
+
+    let stored = if database_exists() {
+        load_database()?
+    } else {
+        CredentialDatabase::new()
+    };
+    let changes = ChangeSummary::between(&stored, database);
+    if ctx.json {
+        print_json(&serde_json::json!({ "dry_run": true, "changes": changes }))?;
+        return Ok(false);
+    }
+
+    outln!("🧪 Dry run: nothing was saved.");
+    if changes.is_empty() {
+        outln!("  No changes.");
+    }
+    for service in &changes.added {
+        outln!("  + {service} (added)");
+    }
+    for service in &changes.removed {
+        outln!("  - {service} (removed)");
+    }
+    for service in &changes.changed {
+        outln!("  ~ {service} (overwritten)");
     }
+    Ok(false)
+}
+
+// Irreversible actions want a typed phrase: a y/n prompt is too easy to
+// answer by reflex.
+fn confirm_typed(warning: &str) -> CredentialResult<()> {
+    const PHRASE: &str = "DELETE";
 
+    let typed: String = Input::new()
+        .with_prompt(format!("{warning} Type {PHRASE} to confirm"))
+        .allow_empty(true)
+        .interact_text()
+        .map_err(|_| CredentialError::user_cancelled())?;
+    if typed.trim() != PHRASE {
+        return Err(CredentialError::user_cancelled());
+    }
     Ok(())
 }
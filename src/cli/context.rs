@@ -0,0 +1,9 @@
+use crate::config::Config;
+use crate::util::TimeFormat;
+
+pub struct Context {
+    pub config: Config,
+    pub json: bool,
+    pub dry_run: bool,
+    pub time_format: TimeFormat,
+}
@@ -1 +1,4 @@
 pub mod commands;
+pub mod context;
+
+pub use context::Context;
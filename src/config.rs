@@ -0,0 +1,248 @@
+// Settings are resolved with the precedence:
+//   CLI flag > environment variable > ~/.crab/config.toml > built-in default
+// Commands apply their own flags on top of the values loaded here.
+use crate::crypto::session::DEFAULT_SESSION_TIMEOUT_SECS;
+use crate::error::{CredentialError, CredentialResult};
+use crate::model::entry::DEFAULT_HISTORY_LIMIT;
+use crate::storage::file::{DEFAULT_BACKUP_KEEP, DEFAULT_TRASH_RETENTION_DAYS};
+use crate::util::clipboard::DEFAULT_CLEAR_TIMEOUT_SECS;
+use crate::util::password::DEFAULT_PASSWORD_LENGTH;
+use crate::util::policy::Policy;
+use crate::util::style::errln;
+use crate::util::TimeFormat;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub password_length: usize,
+    pub clipboard_timeout: u64,
+    pub reveal_timeout: u64,
+    pub backup_keep: usize,
+    pub history_limit: usize,
+    pub session_timeout: u64,
+    pub keyring: bool,
+    pub trash_retention_days: u64,
+    pub confirm_on_get: bool,
+    pub generate_on_add: bool,
+    pub verify_saves: bool,
+    pub track_access: bool,
+    pub offline: bool,
+    pub time_format: TimeFormat,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vault_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_dir: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wordlist: Option<PathBuf>,
+    pub policy: Policy,
+    #[serde(flatten, skip_serializing)]
+    pub unknown: BTreeMap<String, toml::Value>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            password_length: DEFAULT_PASSWORD_LENGTH,
+            clipboard_timeout: DEFAULT_CLEAR_TIMEOUT_SECS,
+            reveal_timeout: 0,
+            backup_keep: DEFAULT_BACKUP_KEEP,
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            session_timeout: DEFAULT_SESSION_TIMEOUT_SECS,
+            keyring: false,
+            trash_retention_days: DEFAULT_TRASH_RETENTION_DAYS,
+            confirm_on_get: false,
+            generate_on_add: false,
+            verify_saves: false,
+            track_access: true,
+            offline: false,
+            time_format: TimeFormat::Default,
+            vault_path: None,
+            backup_dir: None,
+            wordlist: None,
+            policy: Policy::default(),
+            unknown: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> CredentialResult<Self> {
+        match config_path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> CredentialResult<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        let config = Self::parse(&contents)
+            .map_err(|e| CredentialError::config_error(&format!("{}: {e}", path.display())))?;
+
+        for key in config.unknown.keys() {
+            errln!(
+                "⚠️  Ignoring unknown config key '{key}' in {}",
+                path.display()
+            );
+        }
+        Ok(config)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    pub fn save_to(&self, path: &Path) -> CredentialResult<()> {
+        let contents = toml::to_string_pretty(self)
+            .map_err(|e| CredentialError::config_error(&e.to_string()))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".crab").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_file_yields_defaults() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+
+        let config =
+            Config::load_from(&temp_dir.path().join("config.toml")).expect("load should succeed");
+
+        assert_eq!(config.password_length, DEFAULT_PASSWORD_LENGTH);
+        assert_eq!(config.clipboard_timeout, DEFAULT_CLEAR_TIMEOUT_SECS);
+        assert_eq!(config.history_limit, DEFAULT_HISTORY_LIMIT);
+        assert!(config.vault_path.is_none());
+        assert!(config.wordlist.is_none());
+    }
+
+    #[test]
+    fn parse_overrides_only_given_keys() {
+        let config = Config::parse("password_length = 32\n").expect("parse should succeed");
+
+        assert_eq!(config.password_length, 32);
+        assert_eq!(config.clipboard_timeout, DEFAULT_CLEAR_TIMEOUT_SECS);
+    }
+
+    #[test]
+    fn parse_reads_wordlist_path() {
+        let config = Config::parse("wordlist = \"/usr/share/eff_large_wordlist.txt\"\n")
+            .expect("parse should succeed");
+
+        assert_eq!(
+            config.wordlist,
+            Some(PathBuf::from("/usr/share/eff_large_wordlist.txt"))
+        );
+    }
+
+    #[test]
+    fn confirm_on_get_defaults_off() {
+        assert!(!Config::default().confirm_on_get);
+        let config = Config::parse("confirm_on_get = true\n").expect("parse should succeed");
+
+        assert!(config.confirm_on_get);
+    }
+
+    #[test]
+    fn reveal_timeout_defaults_to_never() {
+        assert_eq!(Config::default().reveal_timeout, 0);
+        let config = Config::parse("reveal_timeout = 15\n").expect("parse should succeed");
+
+        assert_eq!(config.reveal_timeout, 15);
+    }
+
+    #[test]
+    fn track_access_defaults_on() {
+        assert!(Config::default().track_access);
+        let config = Config::parse("track_access = false\n").expect("parse should succeed");
+
+        assert!(!config.track_access);
+    }
+
+    #[test]
+    fn keyring_defaults_off() {
+        assert!(!Config::default().keyring);
+        let config = Config::parse("keyring = true\n").expect("parse should succeed");
+
+        assert!(config.keyring);
+    }
+
+    #[test]
+    fn generate_on_add_defaults_off() {
+        assert!(!Config::default().generate_on_add);
+        let config = Config::parse("generate_on_add = true\n").expect("parse should succeed");
+
+        assert!(config.generate_on_add);
+    }
+
+    #[test]
+    fn parse_reads_policy_table() {
+        assert!(Config::default().policy.is_empty());
+        let config = Config::parse("[policy]\nmin_length = 16\nrequire_symbol = true\n")
+            .expect("parse should succeed");
+
+        assert_eq!(config.policy.min_length, 16);
+        assert!(config.policy.require_symbol);
+        assert!(!config.policy.require_digit);
+        assert!(Config::parse("[policy]\nmin_lenght = 16\n").is_err());
+    }
+
+    #[test]
+    fn parse_reads_time_format() {
+        let config = Config::parse("time_format = \"relative\"\n").expect("parse should succeed");
+
+        assert_eq!(config.time_format, TimeFormat::Relative);
+    }
+
+    #[test]
+    fn parse_collects_unknown_keys() {
+        let config = Config::parse("clipboard_timeout = 5\ncolour = \"blue\"\n")
+            .expect("unknown keys should not fail");
+
+        assert_eq!(config.clipboard_timeout, 5);
+        assert!(config.unknown.contains_key("colour"));
+    }
+
+    #[test]
+    fn invalid_value_is_an_error() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "password_length = \"long\"\n").expect("write");
+
+        let result = Config::load_from(&path);
+
+        assert!(matches!(result, Err(CredentialError::ConfigError(_))));
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let path = temp_dir.path().join("config.toml");
+        let config = Config {
+            vault_path: Some(PathBuf::from("/tmp/vault.json")),
+            ..Config::default()
+        };
+
+        config.save_to(&path).expect("save should succeed");
+        let loaded = Config::load_from(&path).expect("load should succeed");
+
+        assert_eq!(loaded.vault_path, config.vault_path);
+        assert!(loaded.unknown.is_empty());
+    }
+}
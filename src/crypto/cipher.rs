@@ -0,0 +1,348 @@
+use crate::crypto::kdf::{KdfParams, KDF_ARGON2ID, SALT_LEN};
+use crate::crypto::master::MasterKey;
+use crate::error::{CredentialError, CredentialResult};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+// On-disk layout:
+//   MAGIC | FORMAT_VERSION | kdf id | memory KiB | time cost | parallelism
+//   | cipher id | salt | nonce | ciphertext
+// with the three costs as little-endian u32s. Everything before the nonce is
+// authenticated as associated data. Version 3 files share the layout but leave
+// the header unauthenticated. Version 2 files have only the salt between the
+// version and the nonce, and imply `LEGACY_KDF` with AES-256-GCM.
+pub const MAGIC: &[u8; 4] = b"CRAB";
+// Portable backups use the same layout under their own passphrase.
+pub const ARCHIVE_MAGIC: &[u8; 4] = b"CRBK";
+pub const FORMAT_VERSION: u8 = 4;
+const UNAUTHENTICATED_FORMAT_VERSION: u8 = 3;
+const LEGACY_FORMAT_VERSION: u8 = 2;
+pub const CIPHER_AES256GCM: u8 = 1;
+pub const NONCE_LEN: usize = 12;
+pub const KEY_LEN: usize = 32;
+
+// The fixed parameters every version 2 file was written with.
+const LEGACY_KDF: KdfParams = KdfParams {
+    algorithm: KDF_ARGON2ID,
+    memory_kib: 64 * 1024,
+    time_cost: 3,
+    parallelism: 1,
+};
+
+const LEGACY_HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 1 + 3 * 4 + 1 + SALT_LEN;
+
+// What a file says about how its key was derived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyHeader {
+    pub salt: [u8; SALT_LEN],
+    pub kdf: KdfParams,
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+pub fn is_archive(data: &[u8]) -> bool {
+    data.starts_with(ARCHIVE_MAGIC)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes.try_into().expect("four header bytes"))
+}
+
+// Returns the header, where the nonce starts, and whether the bytes before it
+// are authenticated.
+fn read_header_with(magic: &[u8; 4], data: &[u8]) -> CredentialResult<(KeyHeader, usize, bool)> {
+    if !data.starts_with(magic) || data.len() <= magic.len() {
+        return Err(CredentialError::DecryptionFailed);
+    }
+    let version = data[magic.len()];
+    let (kdf, cipher, salt_at, header_len) = match version {
+        LEGACY_FORMAT_VERSION => (
+            LEGACY_KDF,
+            CIPHER_AES256GCM,
+            magic.len() + 1,
+            LEGACY_HEADER_LEN,
+        ),
+        FORMAT_VERSION | UNAUTHENTICATED_FORMAT_VERSION if data.len() >= HEADER_LEN => {
+            let fields = &data[magic.len() + 1..HEADER_LEN];
+            let kdf = KdfParams {
+                algorithm: fields[0],
+                memory_kib: read_u32(&fields[1..5]),
+                time_cost: read_u32(&fields[5..9]),
+                parallelism: read_u32(&fields[9..13]),
+            };
+            (kdf, fields[13], HEADER_LEN - SALT_LEN, HEADER_LEN)
+        }
+        FORMAT_VERSION | UNAUTHENTICATED_FORMAT_VERSION => {
+            return Err(CredentialError::DecryptionFailed)
+        }
+        version => {
+            return Err(CredentialError::UnsupportedVersion(format!(
+                "encrypted file format {version}"
+            )))
+        }
+    };
+    if data.len() < header_len + NONCE_LEN {
+        return Err(CredentialError::DecryptionFailed);
+    }
+    if cipher != CIPHER_AES256GCM {
+        return Err(CredentialError::UnsupportedVersion(format!(
+            "cipher {cipher}"
+        )));
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    salt.copy_from_slice(&data[salt_at..salt_at + SALT_LEN]);
+    Ok((
+        KeyHeader { salt, kdf },
+        header_len,
+        version == FORMAT_VERSION,
+    ))
+}
+
+fn seal(magic: &[u8; 4], key: &MasterKey, plaintext: &[u8]) -> CredentialResult<Vec<u8>> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.bytes()));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let mut data = Vec::with_capacity(HEADER_LEN + NONCE_LEN + plaintext.len() + 16);
+    data.extend_from_slice(magic);
+    data.push(FORMAT_VERSION);
+    data.push(key.kdf.algorithm);
+    data.extend_from_slice(&key.kdf.memory_kib.to_le_bytes());
+    data.extend_from_slice(&key.kdf.time_cost.to_le_bytes());
+    data.extend_from_slice(&key.kdf.parallelism.to_le_bytes());
+    data.push(CIPHER_AES256GCM);
+    data.extend_from_slice(&key.salt);
+
+    let payload = Payload {
+        msg: plaintext,
+        aad: &data,
+    };
+    let ciphertext = cipher.encrypt(&nonce, payload).map_err(|_| {
+        CredentialError::IoError(std::io::Error::other("Failed to encrypt database"))
+    })?;
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+    Ok(data)
+}
+
+fn open(magic: &[u8; 4], key: &MasterKey, data: &[u8]) -> CredentialResult<Vec<u8>> {
+    let (header, header_len, authenticated) = read_header_with(magic, data)?;
+    if header.salt != key.salt || header.kdf != key.kdf {
+        return Err(CredentialError::DecryptionFailed);
+    }
+
+    let (nonce, ciphertext) = data[header_len..].split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.bytes()));
+    let payload = Payload {
+        msg: ciphertext,
+        aad: if authenticated {
+            &data[..header_len]
+        } else {
+            &[]
+        },
+    };
+
+    cipher
+        .decrypt(Nonce::from_slice(nonce), payload)
+        .map_err(|_| CredentialError::DecryptionFailed)
+}
+
+pub fn read_header(data: &[u8]) -> CredentialResult<KeyHeader> {
+    read_header_with(MAGIC, data).map(|(header, _, _)| header)
+}
+
+pub fn encrypt(key: &MasterKey, plaintext: &[u8]) -> CredentialResult<Vec<u8>> {
+    seal(MAGIC, key, plaintext)
+}
+
+pub fn decrypt(key: &MasterKey, data: &[u8]) -> CredentialResult<Vec<u8>> {
+    open(MAGIC, key, data)
+}
+
+pub fn read_archive_header(data: &[u8]) -> CredentialResult<KeyHeader> {
+    read_header_with(ARCHIVE_MAGIC, data).map(|(header, _, _)| header)
+}
+
+pub fn encrypt_archive(key: &MasterKey, plaintext: &[u8]) -> CredentialResult<Vec<u8>> {
+    seal(ARCHIVE_MAGIC, key, plaintext)
+}
+
+pub fn decrypt_archive(key: &MasterKey, data: &[u8]) -> CredentialResult<Vec<u8>> {
+    open(ARCHIVE_MAGIC, key, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key(byte: u8) -> MasterKey {
+        MasterKey::from_parts([byte; SALT_LEN], [byte; KEY_LEN])
+    }
+
+    #[test]
+    fn encrypt_and_decrypt_round_trip() {
+        let key = test_key(7);
+        let plaintext = b"{\"entries\":[]}";
+
+        let encrypted = encrypt(&key, plaintext).expect("encrypt should succeed");
+        let decrypted = decrypt(&key, &encrypted).expect("decrypt should succeed");
+
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn encrypted_data_has_header_and_hides_plaintext() {
+        let key = test_key(7);
+        let plaintext = b"super-secret-value";
+
+        let encrypted = encrypt(&key, plaintext).expect("encrypt should succeed");
+
+        assert!(is_encrypted(&encrypted));
+        assert_eq!(read_header(&encrypted).expect("header").salt, key.salt);
+        assert!(!encrypted
+            .windows(plaintext.len())
+            .any(|window| window == plaintext));
+    }
+
+    #[test]
+    fn decrypt_with_wrong_key_fails() {
+        let encrypted = encrypt(&test_key(1), b"secret").expect("encrypt should succeed");
+        let wrong_key = MasterKey::from_parts([1u8; SALT_LEN], [2u8; KEY_LEN]);
+
+        let result = decrypt(&wrong_key, &encrypted);
+
+        assert!(matches!(result, Err(CredentialError::DecryptionFailed)));
+    }
+
+    #[test]
+    fn plaintext_json_is_not_detected_as_encrypted() {
+        assert!(!is_encrypted(b"{\"entries\": [], \"version\": \"1.0\"}"));
+    }
+
+    #[test]
+    fn archive_round_trip_is_distinct_from_database_format() {
+        let key = test_key(5);
+
+        let archive = encrypt_archive(&key, b"backup").expect("encrypt should succeed");
+
+        assert!(is_archive(&archive));
+        assert!(!is_encrypted(&archive));
+        assert_eq!(
+            read_archive_header(&archive).expect("header").salt,
+            key.salt
+        );
+        assert!(matches!(
+            decrypt(&key, &archive),
+            Err(CredentialError::DecryptionFailed)
+        ));
+        assert_eq!(
+            decrypt_archive(&key, &archive).expect("decrypt should succeed"),
+            b"backup"
+        );
+    }
+
+    #[test]
+    fn header_records_non_default_kdf_parameters() {
+        let kdf = KdfParams {
+            memory_kib: 8 * 1024,
+            time_cost: 2,
+            parallelism: 2,
+            ..KdfParams::default()
+        };
+        let key = MasterKey::derive_with("password", [6u8; SALT_LEN], kdf).expect("derive");
+
+        let encrypted = encrypt(&key, b"payload").expect("encrypt should succeed");
+        let header = read_header(&encrypted).expect("header");
+
+        assert_eq!(header.kdf, kdf);
+        let reopened = MasterKey::derive_with("password", header.salt, header.kdf).expect("derive");
+        assert_eq!(decrypt(&reopened, &encrypted).expect("decrypt"), b"payload");
+        let default_key = MasterKey::derive("password", header.salt);
+        assert!(matches!(
+            decrypt(&default_key, &encrypted),
+            Err(CredentialError::DecryptionFailed)
+        ));
+    }
+
+    #[test]
+    fn version_2_files_still_decrypt() {
+        let key = MasterKey::from_parts([8u8; SALT_LEN], [8u8; KEY_LEN]).with_kdf(LEGACY_KDF);
+        let nonce = [0u8; NONCE_LEN];
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.bytes()));
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), b"old vault".as_slice())
+            .expect("encrypt");
+        let mut legacy = MAGIC.to_vec();
+        legacy.push(LEGACY_FORMAT_VERSION);
+        legacy.extend_from_slice(&key.salt);
+        legacy.extend_from_slice(&nonce);
+        legacy.extend_from_slice(&ciphertext);
+
+        let header = read_header(&legacy).expect("header");
+
+        assert_eq!(header.kdf, LEGACY_KDF);
+        assert_eq!(decrypt(&key, &legacy).expect("decrypt"), b"old vault");
+    }
+
+    #[test]
+    fn version_3_files_still_decrypt() {
+        let key = test_key(4);
+        let mut encrypted = encrypt(&key, b"x").expect("encrypt should succeed");
+        encrypted.truncate(HEADER_LEN);
+        encrypted[MAGIC.len()] = UNAUTHENTICATED_FORMAT_VERSION;
+        let nonce = [0u8; NONCE_LEN];
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.bytes()));
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), b"v3 vault".as_slice())
+            .expect("encrypt");
+        encrypted.extend_from_slice(&nonce);
+        encrypted.extend_from_slice(&ciphertext);
+
+        assert_eq!(decrypt(&key, &encrypted).expect("decrypt"), b"v3 vault");
+    }
+
+    #[test]
+    fn tampered_header_fails_cleanly() {
+        let key = test_key(2);
+        let encrypted = encrypt(&key, b"secret").expect("encrypt should succeed");
+        let mut downgraded = encrypted.clone();
+        downgraded[MAGIC.len()] = UNAUTHENTICATED_FORMAT_VERSION;
+        let mut inflated = encrypted.clone();
+        inflated[MAGIC.len() + 2..MAGIC.len() + 6].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert!(matches!(
+            decrypt(&key, &downgraded),
+            Err(CredentialError::DecryptionFailed)
+        ));
+        let header = read_header(&inflated).expect("header");
+        assert_eq!(header.kdf.memory_kib, u32::MAX);
+        assert!(matches!(
+            MasterKey::derive_with("password", header.salt, header.kdf),
+            Err(CredentialError::DecryptionFailed)
+        ));
+        assert!(matches!(
+            decrypt(&key, &inflated),
+            Err(CredentialError::DecryptionFailed)
+        ));
+    }
+
+    #[test]
+    fn unknown_cipher_or_format_is_unsupported() {
+        let mut encrypted = encrypt(&test_key(3), b"x").expect("encrypt should succeed");
+        let mut newer = encrypted.clone();
+        newer[MAGIC.len()] = FORMAT_VERSION + 1;
+        encrypted[HEADER_LEN - SALT_LEN - 1] = 9;
+
+        assert!(matches!(
+            read_header(&encrypted),
+            Err(CredentialError::UnsupportedVersion(_))
+        ));
+        assert!(matches!(
+            read_header(&newer),
+            Err(CredentialError::UnsupportedVersion(_))
+        ));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::crypto::cipher::KEY_LEN;
+use crate::error::{CredentialError, CredentialResult};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use argon2::{Algorithm, Argon2, Params, Version};
+
+pub const SALT_LEN: usize = 16;
+
+// Argon2id cost parameters (RFC 9106 second recommended option, single lane).
+pub const ARGON2_MEMORY_COST_KIB: u32 = 64 * 1024;
+pub const ARGON2_TIME_COST: u32 = 3;
+pub const ARGON2_PARALLELISM: u32 = 1;
+
+// Ceilings for parameters read from a file. Far above the defaults, but a
+// corrupted or tampered header can't ask for terabytes of memory or hours of
+// hashing before the unlock fails.
+pub const MAX_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+pub const MAX_TIME_COST: u32 = 64;
+pub const MAX_PARALLELISM: u32 = 16;
+
+// Identifies the key derivation function in the file header.
+pub const KDF_ARGON2ID: u8 = 1;
+
+// How the master key was derived from the password. Stored in each encrypted
+// file so the defaults can be raised without breaking older vaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    pub algorithm: u8,
+    pub memory_kib: u32,
+    pub time_cost: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            algorithm: KDF_ARGON2ID,
+            memory_kib: ARGON2_MEMORY_COST_KIB,
+            time_cost: ARGON2_TIME_COST,
+            parallelism: ARGON2_PARALLELISM,
+        }
+    }
+}
+
+impl KdfParams {
+    // True when either cost is below the current default, i.e. `crab rekey`
+    // would make the vault harder to brute-force.
+    pub fn is_weaker_than_default(&self) -> bool {
+        let current = Self::default();
+        self.algorithm != current.algorithm
+            || self.memory_kib < current.memory_kib
+            || self.time_cost < current.time_cost
+    }
+
+    fn within_limits(&self) -> bool {
+        self.memory_kib <= MAX_MEMORY_KIB
+            && self.time_cost <= MAX_TIME_COST
+            && self.parallelism <= MAX_PARALLELISM
+    }
+}
+
+pub fn generate_salt() -> [u8; SALT_LEN] {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+pub fn derive_key(password: &str, salt: &[u8]) -> [u8; KEY_LEN] {
+    derive_key_with(password, salt, &KdfParams::default()).expect("Argon2 cost constants are valid")
+}
+
+// Parameters read from a file may be anything, so they are checked here
+// rather than trusted.
+pub fn derive_key_with(
+    password: &str,
+    salt: &[u8],
+    params: &KdfParams,
+) -> CredentialResult<[u8; KEY_LEN]> {
+    if params.algorithm != KDF_ARGON2ID {
+        return Err(CredentialError::UnsupportedVersion(format!(
+            "key derivation {}",
+            params.algorithm
+        )));
+    }
+    if !params.within_limits() {
+        return Err(CredentialError::DecryptionFailed);
+    }
+    let argon2_params = Params::new(
+        params.memory_kib,
+        params.time_cost,
+        params.parallelism,
+        Some(KEY_LEN),
+    )
+    .map_err(|_| CredentialError::DecryptionFailed)?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, argon2_params);
+
+    let mut key = [0u8; KEY_LEN];
+    argon2
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|_| CredentialError::DecryptionFailed)?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_password_and_salt_yield_same_key() {
+        let salt = [1u8; SALT_LEN];
+
+        assert_eq!(derive_key("password", &salt), derive_key("password", &salt));
+    }
+
+    #[test]
+    fn different_salt_yields_different_key() {
+        let key_a = derive_key("password", &[1u8; SALT_LEN]);
+        let key_b = derive_key("password", &[2u8; SALT_LEN]);
+
+        assert_ne!(key_a, key_b);
+    }
+
+    #[test]
+    fn cost_parameters_change_the_key() {
+        let salt = [1u8; SALT_LEN];
+        let light = KdfParams {
+            memory_kib: 1024,
+            time_cost: 1,
+            ..KdfParams::default()
+        };
+
+        let key = derive_key_with("password", &salt, &light).expect("derive");
+
+        assert_ne!(key, derive_key("password", &salt));
+        assert!(light.is_weaker_than_default());
+        assert!(!KdfParams::default().is_weaker_than_default());
+    }
+
+    #[test]
+    fn unknown_or_invalid_parameters_are_rejected() {
+        let salt = [1u8; SALT_LEN];
+        let unknown = KdfParams {
+            algorithm: 9,
+            ..KdfParams::default()
+        };
+        let invalid = KdfParams {
+            time_cost: 0,
+            ..KdfParams::default()
+        };
+
+        assert!(matches!(
+            derive_key_with("password", &salt, &unknown),
+            Err(CredentialError::UnsupportedVersion(_))
+        ));
+        assert!(derive_key_with("password", &salt, &invalid).is_err());
+    }
+
+    #[test]
+    fn oversized_parameters_fail_before_deriving() {
+        let salt = [1u8; SALT_LEN];
+        let oversized = [
+            KdfParams {
+                memory_kib: u32::MAX,
+                ..KdfParams::default()
+            },
+            KdfParams {
+                time_cost: MAX_TIME_COST + 1,
+                ..KdfParams::default()
+            },
+            KdfParams {
+                parallelism: u32::MAX,
+                ..KdfParams::default()
+            },
+        ];
+
+        for params in oversized {
+            assert!(matches!(
+                derive_key_with("password", &salt, &params),
+                Err(CredentialError::DecryptionFailed)
+            ));
+        }
+    }
+
+    #[test]
+    fn generated_salts_are_random() {
+        assert_ne!(generate_salt(), generate_salt());
+    }
+}
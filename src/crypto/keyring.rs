@@ -0,0 +1,169 @@
+// Optional home for the unlock session. Built with the `keyring` feature on
+// Linux, the session token goes into the kernel's per-user keyring instead of
+// ~/.crab/session: it never touches the disk, and the kernel discards it when
+// the session times out even if crab never runs again.
+use std::io;
+use zeroize::Zeroizing;
+
+pub const AVAILABLE: bool = cfg!(all(feature = "keyring", target_os = "linux"));
+
+#[cfg(all(feature = "keyring", target_os = "linux"))]
+mod kernel {
+    use std::ffi::CStr;
+    use std::io;
+    use zeroize::Zeroizing;
+
+    const KEY_TYPE: &CStr = c"user";
+    pub const DESCRIPTION: &CStr = c"crab:session";
+    // Comfortably larger than a session token.
+    const MAX_PAYLOAD: usize = 256;
+
+    fn check(ret: libc::c_long) -> io::Result<libc::c_long> {
+        if ret < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(ret)
+        }
+    }
+
+    fn find(description: &CStr) -> io::Result<Option<libc::c_long>> {
+        // SAFETY: both strings are NUL-terminated and outlive the call.
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_keyctl,
+                libc::KEYCTL_SEARCH,
+                libc::KEY_SPEC_USER_KEYRING,
+                KEY_TYPE.as_ptr(),
+                description.as_ptr(),
+                0,
+            )
+        };
+        match check(ret) {
+            Ok(id) => Ok(Some(id)),
+            Err(e)
+                if matches!(
+                    e.raw_os_error(),
+                    Some(libc::ENOKEY | libc::EKEYEXPIRED | libc::EKEYREVOKED)
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // add_key replaces the payload of an existing key with the same description.
+    pub fn store(description: &CStr, payload: &[u8], timeout: u64) -> io::Result<()> {
+        // SAFETY: the strings are NUL-terminated and the payload pointer is
+        // valid for `payload.len()` bytes.
+        let id = check(unsafe {
+            libc::syscall(
+                libc::SYS_add_key,
+                KEY_TYPE.as_ptr(),
+                description.as_ptr(),
+                payload.as_ptr(),
+                payload.len(),
+                libc::KEY_SPEC_USER_KEYRING,
+            )
+        })?;
+        let timeout = libc::c_uint::try_from(timeout).unwrap_or(libc::c_uint::MAX);
+        // SAFETY: plain integer arguments.
+        check(unsafe { libc::syscall(libc::SYS_keyctl, libc::KEYCTL_SET_TIMEOUT, id, timeout) })?;
+        Ok(())
+    }
+
+    pub fn load(description: &CStr) -> Option<Zeroizing<Vec<u8>>> {
+        let id = find(description).ok()??;
+        let mut payload = Zeroizing::new(vec![0u8; MAX_PAYLOAD]);
+        // SAFETY: the buffer is valid for writes of its full length.
+        let len = check(unsafe {
+            libc::syscall(
+                libc::SYS_keyctl,
+                libc::KEYCTL_READ,
+                id,
+                payload.as_mut_ptr(),
+                payload.len(),
+            )
+        })
+        .ok()?;
+        // A longer key was not written by crab.
+        let len = usize::try_from(len)
+            .ok()
+            .filter(|len| *len <= MAX_PAYLOAD)?;
+        payload.truncate(len);
+        Some(payload)
+    }
+
+    pub fn clear(description: &CStr) -> io::Result<bool> {
+        let Some(id) = find(description)? else {
+            return Ok(false);
+        };
+        // SAFETY: plain integer arguments.
+        check(unsafe {
+            libc::syscall(
+                libc::SYS_keyctl,
+                libc::KEYCTL_UNLINK,
+                id,
+                libc::KEY_SPEC_USER_KEYRING,
+            )
+        })?;
+        Ok(true)
+    }
+}
+
+#[cfg(all(feature = "keyring", target_os = "linux"))]
+pub fn store(payload: &[u8], timeout: u64) -> io::Result<()> {
+    kernel::store(kernel::DESCRIPTION, payload, timeout)
+}
+
+#[cfg(all(feature = "keyring", target_os = "linux"))]
+pub fn load() -> Option<Zeroizing<Vec<u8>>> {
+    kernel::load(kernel::DESCRIPTION)
+}
+
+#[cfg(all(feature = "keyring", target_os = "linux"))]
+pub fn clear() -> io::Result<bool> {
+    kernel::clear(kernel::DESCRIPTION)
+}
+
+#[cfg(not(all(feature = "keyring", target_os = "linux")))]
+pub fn store(_payload: &[u8], _timeout: u64) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "this crab was built without keyring support",
+    ))
+}
+
+#[cfg(not(all(feature = "keyring", target_os = "linux")))]
+pub fn load() -> Option<Zeroizing<Vec<u8>>> {
+    None
+}
+
+#[cfg(not(all(feature = "keyring", target_os = "linux")))]
+pub fn clear() -> io::Result<bool> {
+    Ok(false)
+}
+
+#[cfg(all(test, feature = "keyring", target_os = "linux"))]
+mod tests {
+    use super::kernel;
+
+    // A separate description so the test never touches a real session.
+    const TEST_DESCRIPTION: &std::ffi::CStr = c"crab:test-session";
+
+    #[test]
+    fn stores_reads_and_clears_a_key() {
+        kernel::clear(TEST_DESCRIPTION).expect("clear");
+
+        kernel::store(TEST_DESCRIPTION, b"first", 60).expect("store");
+        kernel::store(TEST_DESCRIPTION, b"second", 60).expect("replace");
+
+        assert_eq!(
+            kernel::load(TEST_DESCRIPTION).as_deref().map(Vec::as_slice),
+            Some(&b"second"[..])
+        );
+        assert!(kernel::clear(TEST_DESCRIPTION).expect("clear"));
+        assert!(kernel::load(TEST_DESCRIPTION).is_none());
+        assert!(!kernel::clear(TEST_DESCRIPTION).expect("clear"));
+    }
+}
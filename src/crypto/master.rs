@@ -0,0 +1,216 @@
+use crate::crypto::cipher::{KeyHeader, KEY_LEN};
+use crate::crypto::kdf::{self, KdfParams, SALT_LEN};
+use crate::crypto::session;
+use crate::error::{CredentialError, CredentialResult};
+use crate::util::style::errln;
+use dialoguer::Password;
+use std::sync::{Mutex, Once, OnceLock};
+use zeroize::Zeroizing;
+
+pub const MASTER_PASSWORD_ENV: &str = "CRAB_MASTER_PASSWORD";
+
+#[derive(Clone)]
+pub struct MasterKey {
+    pub salt: [u8; SALT_LEN],
+    pub kdf: KdfParams,
+    key: Zeroizing<[u8; KEY_LEN]>,
+}
+
+impl MasterKey {
+    // Uses the current default cost parameters.
+    pub fn derive(password: &str, salt: [u8; SALT_LEN]) -> Self {
+        Self::from_parts(salt, kdf::derive_key(password, &salt))
+    }
+
+    pub fn derive_with(
+        password: &str,
+        salt: [u8; SALT_LEN],
+        params: KdfParams,
+    ) -> CredentialResult<Self> {
+        let key = kdf::derive_key_with(password, &salt, &params)?;
+        Ok(Self::from_parts(salt, key).with_kdf(params))
+    }
+
+    // Re-derives the key the way an existing file says it was made.
+    pub fn derive_for(password: &str, header: &KeyHeader) -> CredentialResult<Self> {
+        Self::derive_with(password, header.salt, header.kdf)
+    }
+
+    pub fn from_parts(salt: [u8; SALT_LEN], key: [u8; KEY_LEN]) -> Self {
+        Self {
+            salt,
+            kdf: KdfParams::default(),
+            key: Zeroizing::new(key),
+        }
+    }
+
+    pub fn with_kdf(mut self, kdf: KdfParams) -> Self {
+        self.kdf = kdf;
+        self
+    }
+
+    fn matches(&self, header: &KeyHeader) -> bool {
+        self.salt == header.salt && self.kdf == header.kdf
+    }
+
+    pub fn bytes(&self) -> &[u8; KEY_LEN] {
+        &self.key
+    }
+}
+
+// The key is kept for the lifetime of the process so a load followed by a save
+// only prompts once.
+fn key_cache() -> &'static Mutex<Option<MasterKey>> {
+    static CACHE: OnceLock<Mutex<Option<MasterKey>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(None))
+}
+
+// For pipelines that can't answer a prompt. Anything able to read this
+// process's environment can read the password, hence the warning.
+fn password_from_env() -> Option<Zeroizing<String>> {
+    let password = Zeroizing::new(std::env::var(MASTER_PASSWORD_ENV).ok()?);
+    if password.is_empty() {
+        return None;
+    }
+
+    static WARNED: Once = Once::new();
+    WARNED.call_once(|| {
+        errln!(
+            "⚠️  Using the master password from {MASTER_PASSWORD_ENV} (less secure than typing it)"
+        )
+    });
+    Some(password)
+}
+
+fn prompt_password(new_vault: bool) -> CredentialResult<Zeroizing<String>> {
+    if let Some(password) = password_from_env() {
+        return Ok(password);
+    }
+    if new_vault {
+        Password::new()
+            .with_prompt("Set Master Password")
+            .with_confirmation("Confirm Master Password", "Passwords don't match")
+            .interact()
+    } else {
+        Password::new().with_prompt("Master Password").interact()
+    }
+    .map(Zeroizing::new)
+    .map_err(|_| CredentialError::user_cancelled())
+}
+
+pub fn unlock_key(header: KeyHeader) -> CredentialResult<MasterKey> {
+    let mut cache = key_cache().lock().expect("key cache poisoned");
+    if let Some(key) = cache.as_ref().filter(|key| key.matches(&header)) {
+        return Ok(key.clone());
+    }
+    // Sessions hold only the derived bytes; the parameters come from the file.
+    if let Some(key) = session::load_session(header.salt) {
+        let key = key.with_kdf(header.kdf);
+        *cache = Some(key.clone());
+        return Ok(key);
+    }
+
+    let password = prompt_password(false)?;
+    let key = MasterKey::derive_for(&password, &header)?;
+    *cache = Some(key.clone());
+    Ok(key)
+}
+
+// Unlocks a database other than the live one (e.g. for a merge). A newly
+// entered password is not cached, so the live vault's key stays in place.
+pub fn unlock_other_key(header: KeyHeader, label: &str) -> CredentialResult<MasterKey> {
+    if let Some(key) = key_cache()
+        .lock()
+        .expect("key cache poisoned")
+        .as_ref()
+        .filter(|key| key.matches(&header))
+    {
+        return Ok(key.clone());
+    }
+
+    let password = Password::new()
+        .with_prompt(format!("Master Password for {label}"))
+        .interact()
+        .map(Zeroizing::new)
+        .map_err(|_| CredentialError::user_cancelled())?;
+    MasterKey::derive_for(&password, &header)
+}
+
+// Backup passphrases are independent of the master password and never cached.
+pub fn new_backup_key() -> CredentialResult<MasterKey> {
+    let password = Password::new()
+        .with_prompt("Backup passphrase")
+        .with_confirmation("Confirm backup passphrase", "Passphrases don't match")
+        .interact()
+        .map(Zeroizing::new)
+        .map_err(|_| CredentialError::user_cancelled())?;
+    Ok(MasterKey::derive(&password, kdf::generate_salt()))
+}
+
+pub fn unlock_backup_key(header: KeyHeader) -> CredentialResult<MasterKey> {
+    let password = Password::new()
+        .with_prompt("Backup passphrase")
+        .interact()
+        .map(Zeroizing::new)
+        .map_err(|_| CredentialError::user_cancelled())?;
+    MasterKey::derive_for(&password, &header)
+}
+
+// Always asks, even with a cached key, so the user proves they know it.
+pub fn prompt_key(header: KeyHeader, prompt: &str) -> CredentialResult<MasterKey> {
+    let password = Password::new()
+        .with_prompt(prompt)
+        .interact()
+        .map(Zeroizing::new)
+        .map_err(|_| CredentialError::user_cancelled())?;
+    MasterKey::derive_for(&password, &header)
+}
+
+// The same password under a fresh salt and today's cost parameters, alongside
+// the key for the current file so the caller can check it first.
+pub fn prompt_rekey(header: KeyHeader) -> CredentialResult<(MasterKey, MasterKey)> {
+    let password = prompt_password(false)?;
+    let current = MasterKey::derive_for(&password, &header)?;
+    let upgraded = MasterKey::derive(&password, kdf::generate_salt());
+    Ok((current, upgraded))
+}
+
+// A key for a new master password under a fresh salt. Not cached until the
+// caller has written the database with it.
+pub fn prompt_new_master_key() -> CredentialResult<MasterKey> {
+    let password = Password::new()
+        .with_prompt("New Master Password")
+        .with_confirmation("Confirm New Master Password", "Passwords don't match")
+        .interact()
+        .map(Zeroizing::new)
+        .map_err(|_| CredentialError::user_cancelled())?;
+    Ok(MasterKey::derive(&password, kdf::generate_salt()))
+}
+
+pub fn master_key() -> CredentialResult<MasterKey> {
+    let mut cache = key_cache().lock().expect("key cache poisoned");
+    if let Some(key) = cache.as_ref() {
+        return Ok(key.clone());
+    }
+
+    let password = prompt_password(true)?;
+    let key = MasterKey::derive(&password, kdf::generate_salt());
+    *cache = Some(key.clone());
+    Ok(key)
+}
+
+// True once the vault has been unlocked in this process, i.e. `master_key`
+// will not prompt.
+pub fn is_unlocked() -> bool {
+    key_cache().lock().is_ok_and(|cache| cache.is_some())
+}
+
+pub fn forget_master_key() {
+    if let Ok(mut cache) = key_cache().lock() {
+        *cache = None;
+    }
+}
+
+pub fn cache_master_key(key: MasterKey) {
+    *key_cache().lock().expect("key cache poisoned") = Some(key);
+}
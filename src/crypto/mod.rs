@@ -0,0 +1,9 @@
+pub mod cipher;
+pub mod kdf;
+pub mod keyring;
+pub mod master;
+pub mod session;
+
+pub use cipher::{decrypt, encrypt, is_encrypted, read_header, KeyHeader};
+pub use master::{forget_master_key, master_key, unlock_key};
+pub use session::{clear_session, store_session};
@@ -0,0 +1,302 @@
+use crate::crypto::cipher::{self, KEY_LEN};
+use crate::crypto::kdf::{self, SALT_LEN};
+use crate::crypto::keyring;
+use crate::crypto::master::MasterKey;
+use crate::error::CredentialResult;
+use crate::util::time::now_unix;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use zeroize::Zeroizing;
+
+pub const DEFAULT_SESSION_TIMEOUT_SECS: u64 = 5 * 60;
+
+// Session token plaintext: expires_at (u64 LE) | salt | key
+const EXPIRY_LEN: usize = 8;
+const TOKEN_LEN: usize = EXPIRY_LEN + SALT_LEN + KEY_LEN;
+
+// The derived key is stored encrypted under ~/.crab/session, or in the
+// keyring when that is enabled. Either way the wrapping key lives in the
+// per-user runtime directory where available, so it is gone after logout or
+// reboot even if the token is not, and neither half alone gives up the key.
+struct SessionPaths {
+    token: PathBuf,
+    wrapping_key: PathBuf,
+    token_in_keyring: bool,
+}
+
+impl SessionPaths {
+    fn locate() -> Option<Self> {
+        let crab_dir = dirs::home_dir()?.join(".crab");
+        let key_dir = dirs::runtime_dir().unwrap_or_else(|| crab_dir.clone());
+        Some(Self {
+            token: crab_dir.join("session"),
+            wrapping_key: key_dir.join("crab-session.key"),
+            token_in_keyring: use_keyring(),
+        })
+    }
+
+    fn read_token(&self) -> Option<Vec<u8>> {
+        if self.token_in_keyring {
+            keyring::load().map(|token| token.to_vec())
+        } else {
+            fs::read(&self.token).ok()
+        }
+    }
+
+    // The keyring expires its copy on its own after `timeout`; the expiry
+    // inside the token still guards against a stale key being read back.
+    fn write_token(&self, token: &[u8], timeout: u64) -> CredentialResult<()> {
+        if self.token_in_keyring {
+            keyring::store(token, timeout)?;
+            Ok(())
+        } else {
+            write_private(&self.token, token)
+        }
+    }
+}
+
+// Set from the config at startup; sessions stay disabled until then.
+fn timeout_setting() -> &'static Mutex<u64> {
+    static TIMEOUT: OnceLock<Mutex<u64>> = OnceLock::new();
+    TIMEOUT.get_or_init(|| Mutex::new(0))
+}
+
+pub fn set_session_timeout(secs: u64) {
+    *timeout_setting()
+        .lock()
+        .expect("session timeout lock poisoned") = secs;
+}
+
+fn session_timeout() -> u64 {
+    *timeout_setting()
+        .lock()
+        .expect("session timeout lock poisoned")
+}
+
+fn keyring_setting() -> &'static Mutex<bool> {
+    static USE_KEYRING: OnceLock<Mutex<bool>> = OnceLock::new();
+    USE_KEYRING.get_or_init(|| Mutex::new(false))
+}
+
+// Only takes effect when the keyring backend is compiled in.
+pub fn set_use_keyring(enabled: bool) {
+    *keyring_setting()
+        .lock()
+        .expect("keyring setting lock poisoned") = enabled && keyring::AVAILABLE;
+}
+
+fn use_keyring() -> bool {
+    *keyring_setting()
+        .lock()
+        .expect("keyring setting lock poisoned")
+}
+
+fn token_plaintext(key: &MasterKey, expires_at: u64) -> Zeroizing<Vec<u8>> {
+    let mut plaintext = Zeroizing::new(Vec::with_capacity(TOKEN_LEN));
+    plaintext.extend_from_slice(&expires_at.to_le_bytes());
+    plaintext.extend_from_slice(&key.salt);
+    plaintext.extend_from_slice(key.bytes());
+    plaintext
+}
+
+fn parse_token(
+    plaintext: &[u8],
+    salt: [u8; SALT_LEN],
+    now: u64,
+    timeout: u64,
+) -> Option<MasterKey> {
+    if plaintext.len() != TOKEN_LEN {
+        return None;
+    }
+
+    let (expiry, rest) = plaintext.split_at(EXPIRY_LEN);
+    let (token_salt, key) = rest.split_at(SALT_LEN);
+    let expires_at = u64::from_le_bytes(expiry.try_into().ok()?);
+    // An expiry further out than the timeout means the clock was moved back.
+    if now >= expires_at || expires_at > now.saturating_add(timeout) || token_salt != salt {
+        return None;
+    }
+
+    let mut key_bytes = Zeroizing::new([0u8; KEY_LEN]);
+    key_bytes.copy_from_slice(key);
+    Some(MasterKey::from_parts(salt, *key_bytes))
+}
+
+fn write_private(path: &Path, data: &[u8]) -> CredentialResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(data)?;
+    Ok(())
+}
+
+fn load_at(
+    paths: &SessionPaths,
+    salt: [u8; SALT_LEN],
+    now: u64,
+    timeout: u64,
+) -> Option<MasterKey> {
+    let wrapping_key = Zeroizing::new(fs::read(&paths.wrapping_key).ok()?);
+    let token = paths.read_token()?;
+
+    let mut key_bytes = Zeroizing::new([0u8; KEY_LEN]);
+    if wrapping_key.len() != KEY_LEN {
+        return None;
+    }
+    key_bytes.copy_from_slice(&wrapping_key);
+    let wrapping_key = MasterKey::from_parts(cipher::read_header(&token).ok()?.salt, *key_bytes);
+
+    let plaintext = Zeroizing::new(cipher::decrypt(&wrapping_key, &token).ok()?);
+    parse_token(&plaintext, salt, now, timeout)
+}
+
+fn store_at(paths: &SessionPaths, key: &MasterKey, now: u64, timeout: u64) -> CredentialResult<()> {
+    // An unexpired session for the same key keeps its original expiry.
+    if load_at(paths, key.salt, now, timeout).is_some_and(|cached| cached.bytes() == key.bytes()) {
+        return Ok(());
+    }
+
+    let mut wrapping_bytes = Zeroizing::new([0u8; KEY_LEN]);
+    OsRng.fill_bytes(wrapping_bytes.as_mut());
+    let wrapping_key = MasterKey::from_parts(kdf::generate_salt(), *wrapping_bytes);
+
+    let plaintext = token_plaintext(key, now.saturating_add(timeout));
+    let token = cipher::encrypt(&wrapping_key, &plaintext)?;
+
+    write_private(&paths.wrapping_key, wrapping_key.bytes())?;
+    paths.write_token(&token, timeout)
+}
+
+fn clear_at(paths: &SessionPaths) -> CredentialResult<bool> {
+    let mut cleared = false;
+    for path in [&paths.token, &paths.wrapping_key] {
+        match fs::remove_file(path) {
+            Ok(()) => cleared = true,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(cleared)
+}
+
+// Returns the key from an unexpired session for the database with `salt`.
+pub fn load_session(salt: [u8; SALT_LEN]) -> Option<MasterKey> {
+    let timeout = session_timeout();
+    if timeout == 0 {
+        return None;
+    }
+    load_at(&SessionPaths::locate()?, salt, now_unix(), timeout)
+}
+
+// Starts a session for a key that has just been verified against the database.
+pub fn store_session(key: &MasterKey) -> CredentialResult<()> {
+    let timeout = session_timeout();
+    match SessionPaths::locate() {
+        Some(paths) if timeout > 0 => store_at(&paths, key, now_unix(), timeout),
+        _ => Ok(()),
+    }
+}
+
+// Removes any session from both the keyring and the session files, so turning
+// `keyring` off does not leave a key behind; returns whether one existed.
+pub fn clear_session() -> CredentialResult<bool> {
+    let in_keyring = keyring::clear()?;
+    let in_files = match SessionPaths::locate() {
+        Some(paths) => clear_at(&paths)?,
+        None => false,
+    };
+    Ok(in_keyring || in_files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: u64 = 300;
+
+    fn temp_paths(dir: &Path) -> SessionPaths {
+        SessionPaths {
+            token: dir.join("session"),
+            wrapping_key: dir.join("runtime").join("crab-session.key"),
+            token_in_keyring: false,
+        }
+    }
+
+    fn test_key() -> MasterKey {
+        MasterKey::from_parts([3u8; SALT_LEN], [9u8; KEY_LEN])
+    }
+
+    #[test]
+    fn stored_session_is_reused_until_expiry() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let paths = temp_paths(temp_dir.path());
+        let key = test_key();
+
+        store_at(&paths, &key, 1_000, TIMEOUT).expect("store should succeed");
+
+        let loaded = load_at(&paths, key.salt, 1_000 + TIMEOUT - 1, TIMEOUT)
+            .expect("session should be valid");
+        assert_eq!(loaded.bytes(), key.bytes());
+        assert!(load_at(&paths, key.salt, 1_000 + TIMEOUT, TIMEOUT).is_none());
+    }
+
+    #[test]
+    fn session_does_not_store_key_in_plaintext() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let paths = temp_paths(temp_dir.path());
+        let key = test_key();
+
+        store_at(&paths, &key, 1_000, TIMEOUT).expect("store should succeed");
+
+        let token = fs::read(&paths.token).expect("token");
+        assert!(!token.windows(KEY_LEN).any(|window| window == key.bytes()));
+    }
+
+    #[test]
+    fn session_is_rejected_for_other_database_or_rewound_clock() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let paths = temp_paths(temp_dir.path());
+        let key = test_key();
+
+        store_at(&paths, &key, 1_000, TIMEOUT).expect("store should succeed");
+
+        assert!(load_at(&paths, [4u8; SALT_LEN], 1_000, TIMEOUT).is_none());
+        assert!(load_at(&paths, key.salt, 1, TIMEOUT).is_none());
+    }
+
+    #[test]
+    fn storing_again_keeps_original_expiry() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let paths = temp_paths(temp_dir.path());
+        let key = test_key();
+
+        store_at(&paths, &key, 1_000, TIMEOUT).expect("store should succeed");
+        store_at(&paths, &key, 1_200, TIMEOUT).expect("store should succeed");
+
+        assert!(load_at(&paths, key.salt, 1_000 + TIMEOUT, TIMEOUT).is_none());
+    }
+
+    #[test]
+    fn clear_removes_session() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let paths = temp_paths(temp_dir.path());
+        let key = test_key();
+        store_at(&paths, &key, 1_000, TIMEOUT).expect("store should succeed");
+
+        assert!(clear_at(&paths).expect("clear should succeed"));
+        assert!(load_at(&paths, key.salt, 1_000, TIMEOUT).is_none());
+        assert!(!clear_at(&paths).expect("clear should succeed"));
+    }
+}
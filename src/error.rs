@@ -4,9 +4,28 @@ use std::fmt;
 pub enum CredentialError {
     DatabaseNotFound,
     CredentialNotStored,
-    CredentialNotFound(String),
+    CredentialNotFound {
+        service: String,
+        suggestions: Vec<String>,
+    },
     IoError(std::io::Error),
     SerializationError(serde_json::Error),
+    DecryptionFailed,
+    ClipboardUnavailable(String),
+    InvalidInput(String),
+    ConfigError(String),
+    UnsupportedVersion(String),
+    IntegrityCheckFailed(usize),
+    DatabaseCorrupted {
+        path: std::path::PathBuf,
+        source: serde_json::Error,
+    },
+    SaveVerificationFailed(std::path::PathBuf),
+    ReadOnly,
+    VaultLocked(std::path::PathBuf),
+    DiagnosticsFailed(usize),
+    PolicyViolation(String),
+    Interrupted,
     UserCancelled,
 }
 
@@ -22,7 +41,7 @@ impl fmt::Display for CredentialError {
             CredentialError::CredentialNotStored => {
                 write!(f, "No Credentials Stored yet.")
             }
-            CredentialError::CredentialNotFound(service) => {
+            CredentialError::CredentialNotFound { service, .. } => {
                 write!(f, "No credential found for '{service}'")
             }
             CredentialError::IoError(err) => {
@@ -31,6 +50,67 @@ impl fmt::Display for CredentialError {
             CredentialError::SerializationError(err) => {
                 write!(f, "Data serialization failed: {err}")
             }
+            CredentialError::DecryptionFailed => {
+                write!(
+                    f,
+                    "Failed to decrypt the database. Is the master password correct?"
+                )
+            }
+            CredentialError::ClipboardUnavailable(reason) => {
+                write!(f, "Clipboard is not available: {reason}")
+            }
+            CredentialError::InvalidInput(reason) => {
+                write!(f, "Invalid input: {reason}")
+            }
+            CredentialError::ConfigError(reason) => {
+                write!(f, "Invalid configuration: {reason}")
+            }
+            CredentialError::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "Database schema version '{version}' is not supported by this version of crab. Please upgrade crab."
+                )
+            }
+            CredentialError::IntegrityCheckFailed(count) => {
+                write!(f, "Database integrity check found {count} problem(s)")
+            }
+            CredentialError::DatabaseCorrupted { path, source } => {
+                write!(
+                    f,
+                    "The database at {} is corrupted: {source}",
+                    path.display()
+                )
+            }
+            CredentialError::SaveVerificationFailed(path) => {
+                write!(
+                    f,
+                    "The database written to {} did not read back correctly; the previous version was restored",
+                    path.display()
+                )
+            }
+            CredentialError::ReadOnly => {
+                write!(
+                    f,
+                    "The vault is open read-only (--read-only or CRAB_READONLY); this command would modify it"
+                )
+            }
+            CredentialError::VaultLocked(path) => {
+                write!(
+                    f,
+                    "Another crab process is using the vault (lock held on {}); try again once it finishes",
+                    path.display()
+                )
+            }
+            CredentialError::DiagnosticsFailed(count) => {
+                write!(f, "crab doctor found {count} problem(s) that need fixing")
+            }
+            CredentialError::PolicyViolation(violations) => {
+                write!(
+                    f,
+                    "The secret does not meet the password policy: {violations} (use --force to save it anyway)"
+                )
+            }
+            CredentialError::Interrupted => write!(f, "cancelled, no changes written"),
             CredentialError::UserCancelled => {
                 write!(f, "Operation cancelled by user")
             }
@@ -43,6 +123,7 @@ impl std::error::Error for CredentialError {
         match self {
             CredentialError::IoError(err) => Some(err),
             CredentialError::SerializationError(err) => Some(err),
+            CredentialError::DatabaseCorrupted { source, .. } => Some(source),
             _ => None,
         }
     }
@@ -64,7 +145,17 @@ pub type CredentialResult<T> = Result<T, CredentialError>;
 
 impl CredentialError {
     pub fn credential_not_found(service: &str) -> Self {
-        CredentialError::CredentialNotFound(service.to_string())
+        CredentialError::CredentialNotFound {
+            service: service.to_string(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    pub fn credential_not_found_with_suggestions(service: &str, suggestions: Vec<String>) -> Self {
+        CredentialError::CredentialNotFound {
+            service: service.to_string(),
+            suggestions,
+        }
     }
 
     pub fn database_not_found() -> Self {
@@ -75,18 +166,42 @@ impl CredentialError {
         CredentialError::CredentialNotStored
     }
 
+    pub fn clipboard_unavailable(reason: &str) -> Self {
+        CredentialError::ClipboardUnavailable(reason.to_string())
+    }
+
+    pub fn invalid_input(reason: &str) -> Self {
+        CredentialError::InvalidInput(reason.to_string())
+    }
+
+    pub fn config_error(reason: &str) -> Self {
+        CredentialError::ConfigError(reason.to_string())
+    }
+
     pub fn user_cancelled() -> Self {
         CredentialError::UserCancelled
     }
 
     pub fn exit_code(&self) -> i32 {
         match self {
-            CredentialError::UserCancelled => 100, // Ctrl+C convention
+            CredentialError::UserCancelled | CredentialError::Interrupted => 100, // Ctrl+C convention
             CredentialError::DatabaseNotFound => 1,
-            CredentialError::CredentialNotFound(_) => 2,
+            CredentialError::CredentialNotFound { .. } => 2,
             CredentialError::CredentialNotStored => 3,
             CredentialError::IoError(_) => 4,
             CredentialError::SerializationError(_) => 5,
+            CredentialError::DecryptionFailed => 6,
+            CredentialError::ClipboardUnavailable(_) => 7,
+            CredentialError::InvalidInput(_) => 8,
+            CredentialError::ConfigError(_) => 9,
+            CredentialError::UnsupportedVersion(_) => 10,
+            CredentialError::IntegrityCheckFailed(_) => 11,
+            CredentialError::DatabaseCorrupted { .. } => 12,
+            CredentialError::SaveVerificationFailed(_) => 13,
+            CredentialError::ReadOnly => 14,
+            CredentialError::VaultLocked(_) => 15,
+            CredentialError::DiagnosticsFailed(_) => 16,
+            CredentialError::PolicyViolation(_) => 17,
         }
     }
 }
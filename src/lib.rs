@@ -0,0 +1,159 @@
+//! crab is a local, encrypted credential manager.
+//!
+//! The `crab` binary is a thin wrapper around this crate; the same database,
+//! model and storage layers can be used directly:
+//!
+//! ```
+//! use crab::{CredentialDatabase, CredentialEntry};
+//!
+//! let mut db = CredentialDatabase::new();
+//! db.add_entry(CredentialEntry::new(
+//!     "github".to_string(),
+//!     "octocat".to_string(),
+//!     "s3cret".to_string(),
+//! ));
+//!
+//! assert_eq!(db.len(), 1);
+//! assert_eq!(db.find_entry("GitHub", None).unwrap().account, "octocat");
+//! ```
+//!
+//! Reading and writing the on-disk database goes through [`load_database`] and
+//! [`save_database`], which prompt for the master password when no unlock
+//! session is active:
+//!
+//! ```no_run
+//! use crab::{load_database, save_database, CredentialEntry};
+//!
+//! let mut db = load_database()?;
+//! db.add_entry(CredentialEntry::new(
+//!     "example.com".to_string(),
+//!     "me@example.com".to_string(),
+//!     "hunter2".to_string(),
+//! ));
+//! save_database(&db)?;
+//! # Ok::<(), crab::CredentialError>(())
+//! ```
+
+pub mod cli;
+pub mod config;
+pub mod crypto;
+pub mod error;
+pub mod model;
+pub mod storage;
+pub mod util;
+
+use std::io::IsTerminal;
+
+use cli::commands::Cli;
+use cli::Context;
+use config::Config;
+use util::style::{errln, outln};
+
+pub use error::{CredentialError, CredentialResult};
+pub use model::{CredentialDatabase, CredentialEntry};
+pub use storage::{
+    backup_database, database_exists, delete_database, get_database_info, init_database,
+    load_database, save_database,
+};
+
+pub fn run(cli: Cli) -> CredentialResult<()> {
+    util::style::init(cli.no_color);
+    let config = Config::load()?;
+    if let Some(path) = &config.vault_path {
+        storage::file::set_configured_database_path(path.clone());
+    }
+    if let Some(dir) = &config.backup_dir {
+        storage::file::set_configured_backup_dir(dir.clone());
+    }
+    crypto::session::set_session_timeout(config.session_timeout);
+    if config.keyring && !crypto::keyring::AVAILABLE {
+        errln!("⚠️  keyring = true needs crab built with the `keyring` feature on Linux; using the session file");
+    }
+    crypto::session::set_use_keyring(config.keyring);
+    storage::file::set_trash_retention_days(config.trash_retention_days);
+    storage::file::set_verify_saves(config.verify_saves);
+    storage::file::set_read_only(cli.read_only);
+
+    let ctx = Context {
+        time_format: cli.time_format.unwrap_or(config.time_format),
+        config,
+        json: cli.json,
+        dry_run: cli.dry_run,
+    };
+    cli.command.execute(&ctx)
+}
+
+// Prints an error the way the CLI reports it: a JSON object in `--json` mode,
+// otherwise a message plus a hint for the common failures.
+pub fn report_error(e: &CredentialError, json: bool) {
+    // verify has already printed its report with the individual issues.
+    if json && matches!(e, CredentialError::IntegrityCheckFailed(_)) {
+        return;
+    }
+    if json {
+        let error = serde_json::json!({ "error": e.to_string(), "code": e.exit_code() });
+        println!("{error}");
+        return;
+    }
+
+    match e {
+        CredentialError::UserCancelled => {
+            outln!("ℹ️  Operation cancelled.");
+        }
+        CredentialError::Interrupted => {
+            errln!("ℹ️  {e}");
+        }
+        CredentialError::DatabaseNotFound => {
+            errln!("❌  {e}");
+            errln!("💡 Try running 'crab init' to set up crab, or 'crab add' to create your first credential.");
+        }
+        CredentialError::DatabaseCorrupted { path, .. } => {
+            errln!("❌ {e}");
+            match storage::file::latest_backup() {
+                Some(backup) => errln!(
+                    "💡 Run 'crab restore' to recover; the most recent backup is {}",
+                    backup.display()
+                ),
+                None => errln!("💡 No backups were found to restore from."),
+            }
+            offer_quarantine(path);
+        }
+        CredentialError::CredentialNotFound {
+            service,
+            suggestions,
+        } => {
+            errln!("❌ {e}");
+            if suggestions.is_empty() {
+                errln!(
+                    "💡 Try 'crab list' to see available services or 'crab add {service}' to create it."
+                );
+            } else {
+                errln!("💡 Did you mean: {}?", suggestions.join(", "));
+            }
+        }
+        _ => {
+            errln!("❌ Error: {e}");
+        }
+    }
+}
+
+// Moving the file aside lets the user start over while keeping the damaged
+// copy around for manual recovery.
+fn offer_quarantine(path: &std::path::Path) {
+    if !std::io::stdin().is_terminal() || storage::file::is_read_only() {
+        return;
+    }
+    let move_aside = dialoguer::Confirm::new()
+        .with_prompt("Move the corrupt file aside so you can start fresh?")
+        .default(false)
+        .interact()
+        .unwrap_or(false);
+    if !move_aside {
+        return;
+    }
+
+    match storage::file::quarantine_database(path) {
+        Ok(moved) => outln!("📦 Moved the corrupt database to {}", moved.display()),
+        Err(err) => errln!("❌ Could not move the corrupt database: {err}"),
+    }
+}
@@ -1,36 +1,12 @@
-mod cli;
-mod error;
-mod model;
-mod storage;
-mod util;
-
 use clap::Parser;
-use cli::commands::Cli;
-use error::CredentialError;
+use crab::cli::commands::Cli;
 
 fn main() {
     let cli = Cli::parse();
+    let json = cli.json;
 
-    if let Err(e) = cli.command.execute() {
-        match &e {
-            CredentialError::UserCancelled => {
-                println!("ℹ️  Operation cancelled.");
-            }
-            CredentialError::DatabaseNotFound => {
-                eprintln!("❌  {e}");
-                eprintln!("💡 Try running 'crab add' to create your first credential.");
-            }
-            CredentialError::CredentialNotFound(service) => {
-                eprintln!("❌ {e}");
-                eprintln!(
-                    "💡 Try 'crab list' to see available services or 'crab add {service}' to create it."
-                );
-            }
-            _ => {
-                eprintln!("❌ Error: {e}");
-            }
-        }
-
+    if let Err(e) = crab::run(cli) {
+        crab::report_error(&e, json);
         std::process::exit(e.exit_code());
     }
 }
@@ -1,46 +1,846 @@
-use crate::model::CredentialEntry;
+use crate::model::entry::normalize_service;
+use crate::model::migrate::CURRENT_VERSION;
+use crate::model::{same_service, CredentialEntry, TrashedEntry};
+use clap::ValueEnum;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
 
-#[derive(Debug, Serialize, Deserialize)]
+// Typos further than this many edits away are not worth suggesting.
+const MAX_SUGGESTION_DISTANCE: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CredentialDatabase {
     pub entries: Vec<CredentialEntry>,
     pub version: String,
+    // Removed entries, oldest deletion first.
+    #[serde(default)]
+    pub trash: Vec<TrashedEntry>,
+    // Normalized service -> positions of its entries in `entries`, one per
+    // account, in stored order. Built lazily on the first lookup and patched
+    // in place by the methods below. Code that mutates `entries` directly
+    // should call `reindex` afterwards.
+    #[serde(skip)]
+    index: OnceLock<HashMap<String, Vec<usize>>>,
 }
 
-impl CredentialDatabase {
-    pub fn new() -> Self {
+impl Default for CredentialDatabase {
+    fn default() -> Self {
         Self {
             entries: Vec::new(),
-            version: String::from("1.0"), // TODO: explicit versioning
+            version: String::from(CURRENT_VERSION),
+            trash: Vec::new(),
+            index: OnceLock::new(),
         }
     }
+}
+
+impl CredentialDatabase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reindex(&mut self) {
+        self.index = OnceLock::new();
+    }
+
+    fn index(&self) -> &HashMap<String, Vec<usize>> {
+        self.index.get_or_init(|| {
+            let mut index: HashMap<String, Vec<usize>> = HashMap::with_capacity(self.entries.len());
+            for (position, entry) in self.entries.iter().enumerate() {
+                index
+                    .entry(normalize_service(&entry.service))
+                    .or_default()
+                    .push(position);
+            }
+            index
+        })
+    }
+
+    // The entry for `service` with exactly `account`, or its first entry when
+    // no account is given.
+    fn position(&self, service: &str, account: Option<&str>) -> Option<usize> {
+        let matches = |entry: &CredentialEntry| {
+            same_service(&entry.service, service) && account.is_none_or(|a| entry.account == a)
+        };
+        let positions = self.index().get(&normalize_service(service))?;
+        let in_sync = positions.iter().all(|&position| {
+            self.entries
+                .get(position)
+                .is_some_and(|entry| same_service(&entry.service, service))
+        });
+        if in_sync {
+            positions
+                .iter()
+                .copied()
+                .find(|&position| matches(&self.entries[position]))
+        } else {
+            // `entries` was changed behind the index's back.
+            self.entries.iter().position(matches)
+        }
+    }
+
+    // Every position for `service`, in stored order.
+    fn positions(&self, service: &str) -> Vec<usize> {
+        let Some(positions) = self.index().get(&normalize_service(service)) else {
+            return Vec::new();
+        };
+        let in_sync = positions.iter().all(|&position| {
+            self.entries
+                .get(position)
+                .is_some_and(|entry| same_service(&entry.service, service))
+        });
+        if in_sync {
+            positions.clone()
+        } else {
+            (0..self.entries.len())
+                .filter(|&position| same_service(&self.entries[position].service, service))
+                .collect()
+        }
+    }
+
+    // Moves `moved` (in stored order) from one service's slot to another's
+    // after a rename.
+    fn move_positions(&mut self, from: &str, to: &str, moved: &[usize]) {
+        let (from, to) = (normalize_service(from), normalize_service(to));
+        if from == to {
+            return;
+        }
+        let Some(index) = self.index.get_mut() else {
+            return;
+        };
+        if let Some(positions) = index.get_mut(&from) {
+            positions.retain(|position| moved.binary_search(position).is_err());
+            if positions.is_empty() {
+                index.remove(&from);
+            }
+        }
+        let positions = index.entry(to).or_default();
+        positions.extend_from_slice(moved);
+        positions.sort_unstable();
+    }
+
+    // Drops `removed` (in stored order) from the index and shifts every
+    // later position down to match.
+    fn forget_positions(&mut self, removed: &[usize]) {
+        let Some(index) = self.index.get_mut() else {
+            return;
+        };
+        index.retain(|_, positions| {
+            positions.retain(|position| removed.binary_search(position).is_err());
+            for position in positions.iter_mut() {
+                *position -= removed.partition_point(|&gone| gone < *position);
+            }
+            !positions.is_empty()
+        });
+    }
 
     pub fn add_entry(&mut self, entry: CredentialEntry) {
+        let key = normalize_service(&entry.service);
         self.entries.push(entry);
+        if let Some(index) = self.index.get_mut() {
+            index.entry(key).or_default().push(self.entries.len() - 1);
+        }
     }
 
-    pub fn remove_entry(&mut self, service: &str) {
-        self.entries.retain(|entry| entry.service != service);
+    // Without an account, removes every entry for `service`.
+    pub fn remove_entry(&mut self, service: &str, account: Option<&str>) {
+        let removed: Vec<usize> = self
+            .positions(service)
+            .into_iter()
+            .filter(|&position| account.is_none_or(|a| self.entries[position].account == a))
+            .collect();
+        if removed.is_empty() {
+            return;
+        }
+        let mut position = 0;
+        self.entries.retain(|_| {
+            let keep = removed.binary_search(&position).is_err();
+            position += 1;
+            keep
+        });
+        self.forget_positions(&removed);
     }
 
-    pub fn find_entry(&self, service: &str) -> Option<&CredentialEntry> {
-        self.entries.iter().find(|entry| entry.service == service)
+    // All entries stored under `service`, one per account, in stored order.
+    pub fn entries_for(&self, service: &str) -> Vec<&CredentialEntry> {
+        self.positions(service)
+            .into_iter()
+            .map(|position| &self.entries[position])
+            .collect()
     }
 
-    pub fn edit_entry(&mut self, service: &str) -> Option<&mut CredentialEntry> {
-        self.entries
-            .iter_mut()
-            .find(|entry| entry.service == service)
+    // Renames every entry for `service`, one per account, keeping lookups in
+    // sync. Returns how many were renamed.
+    pub fn rename_entry(&mut self, service: &str, new_service: String) -> usize {
+        let moved = self.positions(service);
+        for &position in &moved {
+            self.entries[position].update_service(new_service.clone());
+        }
+        self.move_positions(service, &new_service, &moved);
+        moved.len()
+    }
+
+    // Renames just the entry for `service` and `account` (its first entry
+    // without an account). Returns false if there is no such entry.
+    pub fn move_entry(
+        &mut self,
+        service: &str,
+        account: Option<&str>,
+        new_service: String,
+    ) -> bool {
+        let Some(position) = self.position(service, account) else {
+            return false;
+        };
+        let entry = &mut self.entries[position];
+        let old_service = std::mem::take(&mut entry.service);
+        entry.update_service(new_service);
+        let new_service = entry.service.clone();
+        self.move_positions(&old_service, &new_service, &[position]);
+        true
+    }
+
+    // Moves the entry into the trash instead of dropping it. Returns false if
+    // there was nothing to remove.
+    pub fn trash_entry(&mut self, service: &str, account: Option<&str>, now: u64) -> bool {
+        let Some(index) = self.position(service, account) else {
+            return false;
+        };
+        let entry = self.entries.remove(index);
+        self.forget_positions(&[index]);
+        self.trash.push(TrashedEntry {
+            entry,
+            deleted_at: now,
+        });
+        true
+    }
+
+    // Brings back the most recently trashed entry for `service`. The caller
+    // is responsible for checking that its service and account are free again.
+    pub fn restore_from_trash(&mut self, service: &str) -> bool {
+        let Some(index) = self
+            .trash
+            .iter()
+            .rposition(|trashed| same_service(&trashed.entry.service, service))
+        else {
+            return false;
+        };
+        let trashed = self.trash.remove(index);
+        self.add_entry(trashed.entry);
+        true
+    }
+
+    pub fn empty_trash(&mut self) -> usize {
+        std::mem::take(&mut self.trash).len()
+    }
+
+    // Drops trashed entries deleted before `cutoff`; returns how many went.
+    pub fn purge_trash(&mut self, cutoff: u64) -> usize {
+        let before = self.trash.len();
+        self.trash.retain(|trashed| trashed.deleted_at >= cutoff);
+        before - self.trash.len()
+    }
+
+    // Without an account this is the service's first entry; use `entries_for`
+    // to see whether there are others.
+    pub fn find_entry(&self, service: &str, account: Option<&str>) -> Option<&CredentialEntry> {
+        self.position(service, account)
+            .map(|position| &self.entries[position])
+    }
+
+    // Changing the service through the returned entry would leave lookups
+    // stale; use `move_entry` or `rename_entry` for that.
+    pub fn edit_entry(
+        &mut self,
+        service: &str,
+        account: Option<&str>,
+    ) -> Option<&mut CredentialEntry> {
+        let position = self.position(service, account)?;
+        Some(&mut self.entries[position])
     }
 
     pub fn list_entries(&self) -> Vec<&CredentialEntry> {
         self.entries.iter().collect()
     }
 
-    // NOTE: For Debug
+    pub fn favorites(&self) -> Vec<&CredentialEntry> {
+        self.entries.iter().filter(|entry| entry.favorite).collect()
+    }
+
+    // Renames `old` on every entry carrying it; returns how many changed.
+    pub fn rename_tag(&mut self, old: &str, new: &str) -> usize {
+        self.entries
+            .iter_mut()
+            .map(|entry| entry.rename_tag(old, new))
+            .filter(|renamed| *renamed)
+            .count()
+    }
+
+    pub fn list_by_tag(&self, tag: &str) -> Vec<&CredentialEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.has_tag(tag))
+            .collect()
+    }
+
+    pub fn search(&self, query: &str) -> Vec<&CredentialEntry> {
+        let query = query.to_lowercase();
+        let mut matches: Vec<(u32, &CredentialEntry)> = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let service_score = match_score(&entry.service.to_lowercase(), &query);
+                // Account matches rank just below an equally good service match.
+                let account_score = match_score(&entry.account.to_lowercase(), &query)
+                    .map(|score| score.saturating_sub(5));
+                service_score.max(account_score).map(|score| (score, entry))
+            })
+            .collect();
+
+        matches.sort_by(|(score_a, entry_a), (score_b, entry_b)| {
+            score_b
+                .cmp(score_a)
+                .then_with(|| entry_a.service.cmp(&entry_b.service))
+        });
+        matches.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    // (service, account) for every service containing `pattern`, ignoring
+    // case, ordered by service and then account.
+    pub fn accounts_matching(&self, pattern: &str) -> Vec<(&str, &str)> {
+        let pattern = normalize_service(pattern);
+        let mut accounts: Vec<(&str, &str)> = self
+            .entries
+            .iter()
+            .filter(|entry| normalize_service(&entry.service).contains(&pattern))
+            .map(|entry| (entry.service.as_str(), entry.account.as_str()))
+            .collect();
+        accounts.sort_by(|a, b| {
+            normalize_service(a.0)
+                .cmp(&normalize_service(b.0))
+                .then_with(|| a.1.cmp(b.1))
+        });
+        accounts
+    }
+
+    // Entries where `pattern` matches the chosen field, ordered by service.
+    pub fn search_regex(&self, pattern: &Regex, field: SearchField) -> Vec<&CredentialEntry> {
+        let mut matches: Vec<&CredentialEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| {
+                let url = entry.url.as_deref().unwrap_or("");
+                match field {
+                    SearchField::Service => pattern.is_match(&entry.service),
+                    SearchField::Account => pattern.is_match(&entry.account),
+                    SearchField::Url => pattern.is_match(url),
+                    SearchField::Notes => entry
+                        .fields
+                        .get("notes")
+                        .is_some_and(|notes| pattern.is_match(notes)),
+                    SearchField::All => [entry.service.as_str(), &entry.account, url]
+                        .into_iter()
+                        .chain(entry.fields.values().map(String::as_str))
+                        .any(|value| pattern.is_match(value)),
+                }
+            })
+            .collect();
+        matches.sort_by(|a, b| a.service.cmp(&b.service));
+        matches
+    }
+
+    pub fn merge(&mut self, other: CredentialDatabase, strategy: ConflictStrategy) -> MergeSummary {
+        let mut summary = MergeSummary::default();
+
+        for theirs in other.entries {
+            let Some(position) = self.position(&theirs.service, Some(&theirs.account)) else {
+                self.add_entry(theirs);
+                summary.added += 1;
+                continue;
+            };
+            // Replacing with an entry of the same service and account keeps the
+            // index valid.
+            let mine = &mut self.entries[position];
+
+            let take_theirs = match strategy {
+                ConflictStrategy::Newer => theirs.updated_at > mine.updated_at,
+                ConflictStrategy::Mine => false,
+                ConflictStrategy::Theirs => true,
+            };
+            if take_theirs {
+                *mine = theirs;
+                summary.overwritten += 1;
+            } else {
+                summary.skipped += 1;
+            }
+        }
+        summary
+    }
+
+    // How `other` differs from this database, matching entries by service and
+    // account. Secrets are compared but never copied into the result.
+    pub fn diff(&self, other: &CredentialDatabase) -> DbDiff {
+        let mut diff = DbDiff::default();
+
+        for mine in &self.entries {
+            let Some(theirs) = other.counterpart(self, mine) else {
+                diff.only_mine.push(mine.service.clone());
+                continue;
+            };
+            let fields: Vec<&'static str> = [
+                ("account", mine.account != theirs.account),
+                ("secret", mine.secret != theirs.secret),
+                ("updated_at", mine.updated_at != theirs.updated_at),
+            ]
+            .into_iter()
+            .filter_map(|(field, differs)| differs.then_some(field))
+            .collect();
+            if !fields.is_empty() {
+                diff.differing.push(ServiceDiff {
+                    service: mine.service.clone(),
+                    fields,
+                });
+            }
+        }
+        diff.only_theirs = other
+            .entries
+            .iter()
+            .filter(|theirs| self.counterpart(other, theirs).is_none())
+            .map(|theirs| theirs.service.clone())
+            .collect();
+
+        diff.only_mine
+            .sort_by_key(|service| normalize_service(service));
+        diff.only_theirs
+            .sort_by_key(|service| normalize_service(service));
+        diff.differing
+            .sort_by_key(|entry| normalize_service(&entry.service));
+        diff
+    }
+
+    // The entry here that `entry` (from `from`) corresponds to: the one with
+    // the same service and account or, when each side has a single entry for
+    // the service, that one, so a changed account shows up as a difference.
+    fn counterpart(
+        &self,
+        from: &CredentialDatabase,
+        entry: &CredentialEntry,
+    ) -> Option<&CredentialEntry> {
+        if let Some(exact) = self.find_entry(&entry.service, Some(&entry.account)) {
+            return Some(exact);
+        }
+        match (
+            self.entries_for(&entry.service).as_slice(),
+            from.entries_for(&entry.service).len(),
+        ) {
+            ([only], 1) => Some(only),
+            _ => None,
+        }
+    }
+
+    // Entries last updated in [since, before); either bound may be open.
+    pub fn filter_by_updated(
+        &self,
+        since: Option<u64>,
+        before: Option<u64>,
+    ) -> Vec<&CredentialEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| since.is_none_or(|since| entry.updated_at >= since))
+            .filter(|entry| before.is_none_or(|before| entry.updated_at < before))
+            .collect()
+    }
+
+    // Groups of entries sharing an identical secret, each sorted by service.
+    pub fn find_duplicate_secrets(&self) -> Vec<Vec<&CredentialEntry>> {
+        let mut by_secret: HashMap<&str, Vec<&CredentialEntry>> = HashMap::new();
+        for entry in &self.entries {
+            by_secret.entry(&entry.secret).or_default().push(entry);
+        }
+
+        let mut groups: Vec<Vec<&CredentialEntry>> = by_secret
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .map(|mut group| {
+                group.sort_by(|a, b| a.service.cmp(&b.service));
+                group
+            })
+            .collect();
+        groups.sort_by(|a, b| a[0].service.cmp(&b[0].service));
+        groups
+    }
+
+    // Entries with a rotation interval that are overdue at `now` or fall due
+    // within the next `within` seconds, soonest first.
+    pub fn expiring(&self, now: u64, within: u64) -> Vec<&CredentialEntry> {
+        let horizon = now.saturating_add(within);
+        let mut due: Vec<(u64, &CredentialEntry)> = self
+            .entries
+            .iter()
+            .filter_map(|entry| Some((entry.rotation_due_at()?, entry)))
+            .filter(|(due_at, _)| *due_at <= horizon)
+            .collect();
+        due.sort_by(|(a, entry_a), (b, entry_b)| {
+            a.cmp(b).then_with(|| entry_a.service.cmp(&entry_b.service))
+        });
+        due.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    // Entries not updated for more than `max_age` seconds before `now`, oldest
+    // first.
+    pub fn stale(&self, now: u64, max_age: u64) -> Vec<&CredentialEntry> {
+        let cutoff = now.saturating_sub(max_age);
+        let mut stale: Vec<&CredentialEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.updated_at < cutoff)
+            .collect();
+        stale.sort_by(|a, b| {
+            a.updated_at
+                .cmp(&b.updated_at)
+                .then_with(|| a.service.cmp(&b.service))
+        });
+        stale
+    }
+
+    // Positions of entries with the same service, account, and secret. Each
+    // group starts with the entry to keep: the one created first.
+    fn duplicate_positions(&self) -> Vec<Vec<usize>> {
+        let mut by_key: HashMap<(String, &str, &str), Vec<usize>> = HashMap::new();
+        for (position, entry) in self.entries.iter().enumerate() {
+            let key = (
+                normalize_service(&entry.service),
+                entry.account.as_str(),
+                entry.secret.as_str(),
+            );
+            by_key.entry(key).or_default().push(position);
+        }
+
+        let mut groups: Vec<Vec<usize>> = by_key
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .map(|mut group| {
+                group.sort_by_key(|&position| self.entries[position].created_at);
+                group
+            })
+            .collect();
+        groups.sort_by_key(|group| normalize_service(&self.entries[group[0]].service));
+        groups
+    }
+
+    // Groups of entries with the same service, account, and secret. Each group
+    // starts with the entry to keep: the one created first.
+    pub fn find_exact_duplicates(&self) -> Vec<Vec<&CredentialEntry>> {
+        self.duplicate_positions()
+            .into_iter()
+            .map(|group| {
+                group
+                    .into_iter()
+                    .map(|position| &self.entries[position])
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Removes everything `find_exact_duplicates` would report except the
+    // keeper of each group; returns how many entries were dropped.
+    pub fn remove_exact_duplicates(&mut self) -> usize {
+        let drop: HashSet<usize> = self
+            .duplicate_positions()
+            .into_iter()
+            .flat_map(|group| group.into_iter().skip(1))
+            .collect();
+
+        let mut position = 0;
+        self.entries.retain(|_| {
+            let keep = !drop.contains(&position);
+            position += 1;
+            keep
+        });
+        let mut removed: Vec<usize> = drop.into_iter().collect();
+        removed.sort_unstable();
+        self.forget_positions(&removed);
+        removed.len()
+    }
+
+    // Up to `n` service names within a few edits of `query`, closest first.
+    pub fn closest_services(&self, query: &str, n: usize) -> Vec<&str> {
+        let query = normalize_service(query);
+        let threshold = MAX_SUGGESTION_DISTANCE.min(query.chars().count().saturating_sub(1));
+
+        let mut candidates: Vec<(usize, &str)> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let distance = strsim::levenshtein(&normalize_service(&entry.service), &query);
+                (distance, entry.service.as_str())
+            })
+            .filter(|(distance, _)| *distance <= threshold)
+            .collect();
+
+        candidates.sort();
+        candidates.dedup();
+        candidates
+            .into_iter()
+            .take(n)
+            .map(|(_, service)| service)
+            .collect()
+    }
+
+    // Problems a well-behaved crab would never write: blank names, entries
+    // that collide on service and account, and timestamps from the future or
+    // out of order.
+    pub fn integrity_issues(&self, now: u64) -> Vec<String> {
+        let mut issues = Vec::new();
+        let mut seen: HashMap<(String, &str), &str> = HashMap::new();
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let label = format!("entry {} ('{}')", i + 1, entry.service);
+            if entry.service.trim().is_empty() {
+                issues.push(format!("{label}: service is empty"));
+            } else if let Some(first) = seen.insert(
+                (normalize_service(&entry.service), entry.account.as_str()),
+                &entry.service,
+            ) {
+                issues.push(format!(
+                    "{label}: duplicates service '{first}' for account '{}'",
+                    entry.account
+                ));
+            }
+            if entry.account.trim().is_empty() {
+                issues.push(format!("{label}: account is empty"));
+            }
+            if entry.created_at > now {
+                issues.push(format!("{label}: created_at is in the future"));
+            }
+            if entry.updated_at > now {
+                issues.push(format!("{label}: updated_at is in the future"));
+            }
+            if entry.updated_at < entry.created_at {
+                issues.push(format!("{label}: updated_at is before created_at"));
+            }
+        }
+        issues
+    }
+
+    // Returns false when there is no such service.
+    pub fn record_access(&mut self, service: &str, account: Option<&str>, now: u64) -> bool {
+        match self.edit_entry(service, account) {
+            Some(entry) => {
+                entry.record_access(now);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Up to `n` entries that have been revealed at least once, most used
+    // first; ties go to the most recently used.
+    pub fn most_used(&self, n: usize) -> Vec<&CredentialEntry> {
+        let mut used: Vec<&CredentialEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.access_count > 0)
+            .collect();
+        used.sort_by(|a, b| {
+            b.access_count
+                .cmp(&a.access_count)
+                .then_with(|| b.last_accessed.cmp(&a.last_accessed))
+                .then_with(|| a.service.cmp(&b.service))
+        });
+        used.truncate(n);
+        used
+    }
+
+    // Up to `n` entries that have been revealed at least once, newest first.
+    pub fn recently_used(&self, n: usize) -> Vec<&CredentialEntry> {
+        let mut used: Vec<&CredentialEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.last_accessed.is_some())
+            .collect();
+        used.sort_by(|a, b| {
+            b.last_accessed
+                .cmp(&a.last_accessed)
+                .then_with(|| a.service.cmp(&b.service))
+        });
+        used.truncate(n);
+        used
+    }
+
     pub fn len(&self) -> usize {
         self.entries.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// Scores how well `query` matches `candidate` (both already lowercased):
+// exact > prefix > substring > in-order subsequence, so "gh" still finds "github".
+fn match_score(candidate: &str, query: &str) -> Option<u32> {
+    if candidate == query {
+        return Some(100);
+    }
+    if candidate.starts_with(query) {
+        return Some(80);
+    }
+    if candidate.contains(query) {
+        return Some(60);
+    }
+
+    let mut candidate_chars = candidate.chars();
+    let is_subsequence = query
+        .chars()
+        .all(|q| candidate_chars.by_ref().any(|c| c == q));
+    if !is_subsequence {
+        return None;
+    }
+
+    // Tighter subsequences (query covers more of the candidate) rank higher.
+    let coverage = query.chars().count() as u32 * 20 / candidate.chars().count().max(1) as u32;
+    Some(20 + coverage)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    #[default]
+    Name,
+    Created,
+    Updated,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SearchField {
+    Service,
+    Account,
+    Url,
+    Notes,
+    // Service, account, URL, notes, and custom field values.
+    #[default]
+    All,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ConflictStrategy {
+    // Most recently updated entry wins; ties keep the local entry.
+    #[default]
+    #[value(name = "keep-newer")]
+    Newer,
+    #[value(name = "keep-mine")]
+    Mine,
+    #[value(name = "keep-theirs")]
+    Theirs,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    pub added: usize,
+    pub overwritten: usize,
+    pub skipped: usize,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DbDiff {
+    pub only_mine: Vec<String>,
+    pub only_theirs: Vec<String>,
+    pub differing: Vec<ServiceDiff>,
+}
+
+// A service present on both sides, with the names of the fields that differ.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ServiceDiff {
+    pub service: String,
+    pub fields: Vec<&'static str>,
+}
+
+impl DbDiff {
+    pub fn is_empty(&self) -> bool {
+        self.only_mine.is_empty() && self.only_theirs.is_empty() && self.differing.is_empty()
+    }
+}
+
+// What saving `after` over `before` would do, by service name. Entries are
+// paired up by content first, so removing one of two identical copies shows
+// up as a removal; a rename is a removal plus an addition.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ChangeSummary {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl ChangeSummary {
+    pub fn between(before: &CredentialDatabase, after: &CredentialDatabase) -> Self {
+        let mut unmatched: HashMap<String, Vec<&CredentialEntry>> = HashMap::new();
+        for entry in &before.entries {
+            unmatched
+                .entry(normalize_service(&entry.service))
+                .or_default()
+                .push(entry);
+        }
+
+        let mut new_entries = Vec::new();
+        for entry in &after.entries {
+            let bucket = unmatched
+                .entry(normalize_service(&entry.service))
+                .or_default();
+            match bucket.iter().position(|old| *old == entry) {
+                Some(position) => {
+                    bucket.remove(position);
+                }
+                None => new_entries.push(entry),
+            }
+        }
+
+        let mut summary = ChangeSummary::default();
+        for entry in new_entries {
+            let bucket = unmatched
+                .get_mut(&normalize_service(&entry.service))
+                .expect("bucket created above");
+            if bucket.pop().is_some() {
+                summary.changed.push(entry.service.clone());
+            } else {
+                summary.added.push(entry.service.clone());
+            }
+        }
+        summary.removed = unmatched
+            .into_values()
+            .flatten()
+            .map(|entry| entry.service.clone())
+            .collect();
+
+        summary.added.sort();
+        summary.removed.sort();
+        summary.changed.sort();
+        summary
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+// Orders a listing without touching the stored order. Ties fall back to the
+// service name so the output is always deterministic.
+pub fn sort_entries(entries: &mut [&CredentialEntry], key: SortKey) {
+    entries.sort_by(|a, b| {
+        let by_name = normalize_service(&a.service)
+            .cmp(&normalize_service(&b.service))
+            .then_with(|| a.service.cmp(&b.service));
+        match key {
+            SortKey::Name => by_name,
+            SortKey::Created => a.created_at.cmp(&b.created_at).then(by_name),
+            SortKey::Updated => a.updated_at.cmp(&b.updated_at).then(by_name),
+        }
+    });
+}
+
+// Moves favorites to the front while keeping the existing order otherwise.
+pub fn favorites_first(entries: &mut [&CredentialEntry]) {
+    entries.sort_by_key(|entry| !entry.favorite);
 }
 
 #[cfg(test)]
@@ -59,11 +859,13 @@ mod tests {
     fn add_entry_success() {
         let mut database = CredentialDatabase::new();
         let service = "github";
-        let entry = sample_entry(&service);
+        let entry = sample_entry(service);
 
         database.add_entry(entry);
 
-        let result = database.find_entry(&service).expect("entry should exist");
+        let result = database
+            .find_entry(service, None)
+            .expect("entry should exist");
         assert_eq!(result.service, "github");
         assert_eq!(result.account, "account");
         assert_eq!(result.secret, "secret");
@@ -73,19 +875,19 @@ mod tests {
     fn remove_entry_by_exist_service() {
         let mut database = CredentialDatabase::new();
         let service = "github";
-        database.add_entry(sample_entry(&service));
+        database.add_entry(sample_entry(service));
 
-        database.remove_entry(&service);
+        database.remove_entry(service, None);
 
-        assert!(database.find_entry("github").is_none());
+        assert!(database.find_entry("github", None).is_none());
     }
 
     #[test]
     fn remove_entry_by_missing_service() {
         let mut database = CredentialDatabase::new();
         let service = "github";
-        database.add_entry(sample_entry(&service));
-        database.remove_entry("not-there-service");
+        database.add_entry(sample_entry(service));
+        database.remove_entry("not-there-service", None);
 
         assert_eq!(database.len(), 1);
     }
@@ -94,18 +896,344 @@ mod tests {
     fn edit_entry() {
         let mut database = CredentialDatabase::new();
         let service = "github";
-        database.add_entry(sample_entry(&service));
+        database.add_entry(sample_entry(service));
 
-        let entry = database.edit_entry(&service).expect("entry should exist");
+        let entry = database
+            .edit_entry(service, None)
+            .expect("entry should exist");
         entry.update_account("update-account".to_string());
         entry.update_secret("update-secret".to_string());
-        let result = database.find_entry(&service).expect("entry should exist");
+        let result = database
+            .find_entry(service, None)
+            .expect("entry should exist");
 
         assert_eq!(result.service, "github");
         assert_eq!(result.account, "update-account");
         assert_eq!(result.secret, "update-secret");
     }
 
+    #[test]
+    fn lookup_ignores_case_and_whitespace() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(sample_entry("GitHub"));
+
+        for query in ["GitHub", "github", " github ", "GITHUB\t"] {
+            let entry = database
+                .find_entry(query, None)
+                .expect("entry should exist");
+            assert_eq!(entry.service, "GitHub");
+        }
+
+        database
+            .edit_entry(" github", None)
+            .expect("entry should exist")
+            .update_account("alice".to_string());
+        assert_eq!(
+            database.find_entry("github", None).expect("entry").account,
+            "alice"
+        );
+
+        database.remove_entry("GITHUB ", None);
+        assert!(database.find_entry("GitHub", None).is_none());
+    }
+
+    #[test]
+    fn search_matches_case_insensitive_substring() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(sample_entry("GitHub"));
+        database.add_entry(sample_entry("gitlab"));
+        database.add_entry(sample_entry("aws"));
+
+        let services: Vec<&str> = database
+            .search("GIT")
+            .iter()
+            .map(|entry| entry.service.as_str())
+            .collect();
+
+        assert_eq!(services, vec!["GitHub", "gitlab"]);
+    }
+
+    #[test]
+    fn search_matches_fuzzy_subsequence() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(sample_entry("github"));
+        database.add_entry(sample_entry("aws"));
+
+        let results = database.search("gh");
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].service, "github");
+    }
+
+    #[test]
+    fn search_ranks_exact_before_fuzzy() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(sample_entry("my-git-host"));
+        database.add_entry(sample_entry("github"));
+        database.add_entry(sample_entry("git"));
+
+        let services: Vec<&str> = database
+            .search("git")
+            .iter()
+            .map(|entry| entry.service.as_str())
+            .collect();
+
+        assert_eq!(services, vec!["git", "github", "my-git-host"]);
+    }
+
+    #[test]
+    fn search_matches_account() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(CredentialEntry::new(
+            "work-mail".to_string(),
+            "alice@example.com".to_string(),
+            "secret".to_string(),
+        ));
+
+        assert_eq!(database.search("alice").len(), 1);
+        assert!(database.search("bob").is_empty());
+    }
+
+    #[test]
+    fn list_by_tag_filters_entries() {
+        let mut database = CredentialDatabase::new();
+        let mut work = sample_entry("jira");
+        work.set_tags(vec!["work".to_string()]);
+        database.add_entry(work);
+        database.add_entry(sample_entry("netflix"));
+
+        let tagged = database.list_by_tag("Work");
+
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].service, "jira");
+    }
+
+    #[test]
+    fn filter_by_updated_applies_bounds() {
+        let mut database = CredentialDatabase::new();
+        for (service, updated_at) in [("old", 100), ("mid", 200), ("new", 300)] {
+            let mut entry = sample_entry(service);
+            entry.updated_at = updated_at;
+            database.add_entry(entry);
+        }
+
+        let services = |entries: Vec<&CredentialEntry>| -> Vec<String> {
+            entries.iter().map(|entry| entry.service.clone()).collect()
+        };
+
+        assert_eq!(
+            services(database.filter_by_updated(Some(200), None)),
+            vec!["mid", "new"]
+        );
+        assert_eq!(
+            services(database.filter_by_updated(None, Some(200))),
+            vec!["old"]
+        );
+        assert_eq!(
+            services(database.filter_by_updated(Some(150), Some(300))),
+            vec!["mid"]
+        );
+        assert_eq!(database.filter_by_updated(None, None).len(), 3);
+    }
+
+    fn sorting_fixture() -> CredentialDatabase {
+        let mut database = CredentialDatabase::new();
+        for (service, created_at, updated_at) in [
+            ("mail", 300, 400),
+            ("Bank", 100, 500),
+            ("github", 200, 200),
+            ("aws", 300, 100),
+        ] {
+            let mut entry = sample_entry(service);
+            entry.created_at = created_at;
+            entry.updated_at = updated_at;
+            database.add_entry(entry);
+        }
+        database
+    }
+
+    fn sorted_services(database: &CredentialDatabase, key: SortKey) -> Vec<&str> {
+        let mut entries = database.list_entries();
+        sort_entries(&mut entries, key);
+        entries.iter().map(|entry| entry.service.as_str()).collect()
+    }
+
+    #[test]
+    fn sort_by_name_ignores_case() {
+        let database = sorting_fixture();
+
+        assert_eq!(
+            sorted_services(&database, SortKey::Name),
+            vec!["aws", "Bank", "github", "mail"]
+        );
+    }
+
+    #[test]
+    fn sort_by_created_breaks_ties_by_name() {
+        let database = sorting_fixture();
+
+        assert_eq!(
+            sorted_services(&database, SortKey::Created),
+            vec!["Bank", "github", "aws", "mail"]
+        );
+    }
+
+    #[test]
+    fn sort_by_updated() {
+        let database = sorting_fixture();
+
+        assert_eq!(
+            sorted_services(&database, SortKey::Updated),
+            vec!["aws", "github", "mail", "Bank"]
+        );
+    }
+
+    #[test]
+    fn sorting_leaves_stored_order_untouched() {
+        let database = sorting_fixture();
+        sorted_services(&database, SortKey::Name);
+
+        assert_eq!(database.entries[0].service, "mail");
+    }
+
+    fn merge_fixture(entries: &[(&str, &str, u64)]) -> CredentialDatabase {
+        let mut database = CredentialDatabase::new();
+        for (service, secret, updated_at) in entries {
+            let mut entry = sample_entry(service);
+            entry.secret = secret.to_string();
+            entry.updated_at = *updated_at;
+            database.add_entry(entry);
+        }
+        database
+    }
+
+    fn secret_of<'a>(database: &'a CredentialDatabase, service: &str) -> &'a str {
+        &database
+            .find_entry(service, None)
+            .expect("entry should exist")
+            .secret
+    }
+
+    #[test]
+    fn merge_keep_newer_prefers_latest_update() {
+        let mut mine = merge_fixture(&[("github", "mine", 200), ("mail", "mine", 300)]);
+        let theirs = merge_fixture(&[("github", "theirs", 300), ("mail", "theirs", 100)]);
+
+        let summary = mine.merge(theirs, ConflictStrategy::Newer);
+
+        assert_eq!(secret_of(&mine, "github"), "theirs");
+        assert_eq!(secret_of(&mine, "mail"), "mine");
+        assert_eq!(
+            summary,
+            MergeSummary {
+                added: 0,
+                overwritten: 1,
+                skipped: 1
+            }
+        );
+    }
+
+    #[test]
+    fn merge_keep_newer_tie_keeps_mine() {
+        let mut mine = merge_fixture(&[("github", "mine", 200)]);
+        let theirs = merge_fixture(&[("github", "theirs", 200)]);
+
+        let summary = mine.merge(theirs, ConflictStrategy::Newer);
+
+        assert_eq!(secret_of(&mine, "github"), "mine");
+        assert_eq!(summary.skipped, 1);
+    }
+
+    #[test]
+    fn merge_keep_mine_and_keep_theirs() {
+        let theirs = || merge_fixture(&[("GitHub", "theirs", 100)]);
+
+        let mut mine = merge_fixture(&[("github", "mine", 200)]);
+        mine.merge(theirs(), ConflictStrategy::Mine);
+        assert_eq!(secret_of(&mine, "github"), "mine");
+
+        let mut mine = merge_fixture(&[("github", "mine", 200)]);
+        mine.merge(theirs(), ConflictStrategy::Theirs);
+        assert_eq!(secret_of(&mine, "github"), "theirs");
+        assert_eq!(mine.len(), 1);
+    }
+
+    #[test]
+    fn merge_adds_entries_missing_locally() {
+        let mut mine = merge_fixture(&[("github", "mine", 200)]);
+        let theirs = merge_fixture(&[("gitlab", "theirs", 100), ("aws", "theirs", 100)]);
+
+        let summary = mine.merge(theirs, ConflictStrategy::Mine);
+
+        assert_eq!(summary.added, 2);
+        assert_eq!(mine.len(), 3);
+        assert_eq!(secret_of(&mine, "aws"), "theirs");
+    }
+
+    #[test]
+    fn find_duplicate_secrets_groups_shared_passwords() {
+        let mut database = CredentialDatabase::new();
+        for (service, secret) in [
+            ("gitlab", "shared"),
+            ("github", "shared"),
+            ("bitbucket", "shared"),
+            ("mail", "unique"),
+            ("bank", "pin"),
+            ("card", "pin"),
+        ] {
+            database.add_entry(CredentialEntry::new(
+                service.to_string(),
+                "account".to_string(),
+                secret.to_string(),
+            ));
+        }
+
+        let groups: Vec<Vec<&str>> = database
+            .find_duplicate_secrets()
+            .iter()
+            .map(|group| group.iter().map(|entry| entry.service.as_str()).collect())
+            .collect();
+
+        assert_eq!(
+            groups,
+            vec![vec!["bank", "card"], vec!["bitbucket", "github", "gitlab"]]
+        );
+    }
+
+    #[test]
+    fn find_duplicate_secrets_empty_when_all_unique() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(sample_entry("a"));
+        let mut other = sample_entry("b");
+        other.update_secret("different".to_string());
+        database.add_entry(other);
+
+        assert!(database.find_duplicate_secrets().is_empty());
+    }
+
+    #[test]
+    fn closest_services_suggests_near_typos() {
+        let mut database = CredentialDatabase::new();
+        for service in ["github", "gitlab", "GMail", "bank"] {
+            database.add_entry(sample_entry(service));
+        }
+
+        assert_eq!(
+            database.closest_services("githb", 3),
+            vec!["github", "gitlab"]
+        );
+        assert_eq!(database.closest_services("githb", 1), vec!["github"]);
+        assert_eq!(
+            database.closest_services("gitlb", 3),
+            vec!["gitlab", "github"]
+        );
+        assert_eq!(database.closest_services("gmial", 1), vec!["GMail"]);
+        assert!(database
+            .closest_services("completely-different", 3)
+            .is_empty());
+        assert!(database.closest_services("x", 3).is_empty());
+    }
+
     #[test]
     fn list_services() {
         let mut database = CredentialDatabase::new();
@@ -117,4 +1245,561 @@ mod tests {
 
         assert_eq!(services, vec!["service-a", "service-b"]);
     }
+
+    #[test]
+    fn integrity_issues_reports_each_problem() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(sample_entry("github"));
+        database.add_entry(sample_entry("GitHub"));
+        let mut blank = sample_entry("aws");
+        blank.account = " ".to_string();
+        blank.updated_at = u64::MAX;
+        database.add_entry(blank);
+
+        let issues = database.integrity_issues(i64::MAX as u64);
+
+        assert_eq!(issues.len(), 3, "{issues:?}");
+        assert!(issues[0].contains("duplicates service 'github'"));
+        assert!(issues[1].contains("account is empty"));
+        assert!(issues[2].contains("updated_at is in the future"));
+    }
+
+    #[test]
+    fn integrity_issues_is_empty_for_a_clean_database() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(sample_entry("github"));
+        let now = database.entries[0].updated_at;
+
+        assert!(database.integrity_issues(now).is_empty());
+    }
+
+    #[test]
+    fn trashed_entry_can_be_restored() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(sample_entry("github"));
+
+        assert!(database.trash_entry("GitHub", None, 100));
+        assert!(!database.trash_entry("github", None, 100));
+        assert!(database.find_entry("github", None).is_none());
+        assert_eq!(database.trash[0].deleted_at, 100);
+
+        assert!(database.restore_from_trash("github"));
+        assert!(database.find_entry("github", None).is_some());
+        assert!(database.trash.is_empty());
+        assert!(!database.restore_from_trash("github"));
+    }
+
+    fn account_entry(service: &str, account: &str) -> CredentialEntry {
+        let mut entry = sample_entry(service);
+        entry.account = account.to_string();
+        entry.secret = format!("{account}-secret");
+        entry
+    }
+
+    #[test]
+    fn several_accounts_share_a_service() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(account_entry("github", "personal"));
+        database.add_entry(account_entry("aws", "root"));
+        database.add_entry(account_entry("GitHub", "work"));
+
+        assert_eq!(database.entries_for("github").len(), 2);
+        assert_eq!(
+            database.find_entry("github", None).expect("entry").account,
+            "personal"
+        );
+        assert_eq!(
+            database
+                .find_entry("github", Some("work"))
+                .expect("entry")
+                .secret,
+            "work-secret"
+        );
+        assert!(database.find_entry("github", Some("root")).is_none());
+
+        database.remove_entry("github", Some("personal"));
+        assert_eq!(database.entries_for("github").len(), 1);
+        assert!(database.find_entry("github", Some("work")).is_some());
+        assert!(database.find_entry("aws", Some("root")).is_some());
+
+        database.add_entry(account_entry("github", "personal"));
+        database.remove_entry("github", None);
+        assert!(database.find_entry("github", None).is_none());
+        assert_eq!(database.len(), 1);
+    }
+
+    #[test]
+    fn trash_entry_takes_only_the_named_account() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(account_entry("github", "personal"));
+        database.add_entry(account_entry("github", "work"));
+
+        assert!(database.trash_entry("github", Some("work"), 100));
+        assert!(!database.trash_entry("github", Some("work"), 100));
+        assert_eq!(database.trash[0].entry.account, "work");
+        assert!(database.find_entry("github", Some("personal")).is_some());
+    }
+
+    #[test]
+    fn merge_and_integrity_key_on_service_and_account() {
+        let mut mine = CredentialDatabase::new();
+        mine.add_entry(account_entry("github", "personal"));
+        let mut theirs = CredentialDatabase::new();
+        theirs.add_entry(account_entry("github", "work"));
+
+        let summary = mine.merge(theirs, ConflictStrategy::Mine);
+
+        assert_eq!(summary.added, 1);
+        assert_eq!(mine.entries_for("github").len(), 2);
+        let now = mine.entries[0].updated_at.max(mine.entries[1].updated_at);
+        assert!(mine.integrity_issues(now).is_empty());
+
+        mine.add_entry(account_entry("GitHub", "work"));
+        let issues = mine.integrity_issues(now.max(mine.entries[2].updated_at));
+        assert_eq!(issues.len(), 1, "{issues:?}");
+        assert!(issues[0].contains("for account 'work'"));
+    }
+
+    #[test]
+    fn purge_trash_drops_only_old_entries() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(sample_entry("old"));
+        database.add_entry(sample_entry("new"));
+        database.trash_entry("old", None, 100);
+        database.trash_entry("new", None, 500);
+
+        assert_eq!(database.purge_trash(200), 1);
+        assert_eq!(database.trash.len(), 1);
+        assert_eq!(database.trash[0].entry.service, "new");
+        assert_eq!(database.empty_trash(), 1);
+        assert!(database.trash.is_empty());
+    }
+
+    #[test]
+    fn databases_without_trash_still_load() {
+        let json = r#"{"entries": [], "version": "1.1"}"#;
+
+        let database: CredentialDatabase = serde_json::from_str(json).expect("should parse");
+
+        assert!(database.trash.is_empty());
+    }
+
+    #[test]
+    fn favorites_are_listed_and_moved_first() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(sample_entry("aws"));
+        database.add_entry(sample_entry("github"));
+        database.add_entry(sample_entry("zoom"));
+        database.edit_entry("zoom", None).expect("entry").favorite = true;
+
+        let favorites: Vec<&str> = database
+            .favorites()
+            .iter()
+            .map(|entry| entry.service.as_str())
+            .collect();
+        assert_eq!(favorites, vec!["zoom"]);
+
+        let mut entries = database.list_entries();
+        favorites_first(&mut entries);
+        let services: Vec<&str> = entries.iter().map(|entry| entry.service.as_str()).collect();
+        assert_eq!(services, vec!["zoom", "aws", "github"]);
+    }
+
+    #[test]
+    fn search_regex_honours_anchors() {
+        let mut database = CredentialDatabase::new();
+        let mut old = sample_entry("github");
+        old.account = "alice@oldcompany.com".to_string();
+        let mut new = sample_entry("gitlab");
+        new.account = "alice@oldcompany.com.au".to_string();
+        database.add_entry(old);
+        database.add_entry(new);
+
+        let pattern = Regex::new(r"@oldcompany\.com$").expect("valid regex");
+        let matches = database.search_regex(&pattern, SearchField::Account);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].service, "github");
+    }
+
+    #[test]
+    fn search_regex_is_scoped_to_the_chosen_field() {
+        let mut database = CredentialDatabase::new();
+        let mut entry = sample_entry("work-mail");
+        entry.url = Some("https://mail.example.com".to_string());
+        entry.set_field("region".to_string(), "eu-west-1".to_string());
+        database.add_entry(entry);
+        database.add_entry(sample_entry("example"));
+        let mut noted = sample_entry("bank");
+        noted.set_field("notes".to_string(), "see example.org".to_string());
+        database.add_entry(noted);
+
+        let pattern = Regex::new("example").expect("valid regex");
+        let services = |field| -> Vec<String> {
+            database
+                .search_regex(&pattern, field)
+                .iter()
+                .map(|entry| entry.service.clone())
+                .collect()
+        };
+
+        assert_eq!(services(SearchField::Service), vec!["example"]);
+        assert_eq!(services(SearchField::Url), vec!["work-mail"]);
+        assert!(services(SearchField::Account).is_empty());
+        assert_eq!(services(SearchField::Notes), vec!["bank"]);
+        assert_eq!(
+            services(SearchField::All),
+            vec!["bank", "example", "work-mail"]
+        );
+
+        let region = Regex::new("^eu-").expect("valid regex");
+        assert_eq!(database.search_regex(&region, SearchField::All).len(), 1);
+    }
+
+    #[test]
+    fn find_exact_duplicates_keeps_the_oldest_first() {
+        let mut database = CredentialDatabase::new();
+        for (service, secret, created_at) in [
+            ("github", "s1", 30),
+            ("GitHub", "s1", 10),
+            ("github", "s2", 5),
+            ("aws", "s3", 1),
+            ("aws", "s3", 2),
+        ] {
+            let mut entry = sample_entry(service);
+            entry.secret = secret.to_string();
+            entry.created_at = created_at;
+            database.add_entry(entry);
+        }
+
+        let groups = database.find_exact_duplicates();
+        let created: Vec<Vec<u64>> = groups
+            .iter()
+            .map(|group| group.iter().map(|entry| entry.created_at).collect())
+            .collect();
+        assert_eq!(created, vec![vec![1, 2], vec![10, 30]]);
+
+        assert!(database.find_entry("aws", None).is_some());
+        assert_eq!(database.remove_exact_duplicates(), 2);
+        let remaining: Vec<u64> = database.entries.iter().map(|e| e.created_at).collect();
+        assert_eq!(remaining, vec![10, 5, 1]);
+        let aws = database
+            .find_entry("aws", None)
+            .map(|entry| entry.created_at);
+        assert_eq!(aws, Some(1));
+        assert!(database.find_exact_duplicates().is_empty());
+    }
+
+    #[test]
+    fn expiring_includes_overdue_and_upcoming_entries() {
+        const DAY: u64 = 86_400;
+        let mut database = CredentialDatabase::new();
+        for (service, rotate_after_days) in [
+            ("overdue", Some(10)),
+            ("soon", Some(15)),
+            ("later", Some(60)),
+            ("never", None),
+        ] {
+            let mut entry = sample_entry(service);
+            entry.updated_at = 0;
+            entry.set_rotation(rotate_after_days);
+            database.add_entry(entry);
+        }
+        let now = 12 * DAY;
+
+        let services = |within| -> Vec<&str> {
+            database
+                .expiring(now, within)
+                .iter()
+                .map(|entry| entry.service.as_str())
+                .collect()
+        };
+
+        assert_eq!(services(0), vec!["overdue"]);
+        assert_eq!(services(7 * DAY), vec!["overdue", "soon"]);
+        assert_eq!(services(365 * DAY), vec!["overdue", "soon", "later"]);
+    }
+
+    #[test]
+    fn stale_lists_old_entries_oldest_first() {
+        const DAY: u64 = 86_400;
+        let mut database = CredentialDatabase::new();
+        for (service, updated_days_ago) in [("recent", 5), ("old", 400), ("older", 900)] {
+            let mut entry = sample_entry(service);
+            entry.updated_at = 1_000 * DAY - updated_days_ago * DAY;
+            database.add_entry(entry);
+        }
+
+        let services: Vec<&str> = database
+            .stale(1_000 * DAY, 365 * DAY)
+            .iter()
+            .map(|entry| entry.service.as_str())
+            .collect();
+
+        assert_eq!(services, vec!["older", "old"]);
+        assert!(database.stale(1_000 * DAY, 1_000 * DAY).is_empty());
+    }
+
+    #[test]
+    fn rename_moves_every_account_of_a_service() {
+        let mut database = CredentialDatabase::new();
+        for account in ["me", "work"] {
+            database.add_entry(CredentialEntry::new(
+                "github".to_string(),
+                account.to_string(),
+                "secret".to_string(),
+            ));
+        }
+        database.add_entry(sample_entry("gitlab"));
+
+        assert_eq!(database.rename_entry("GitHub", "code".to_string()), 2);
+
+        assert!(database.find_entry("github", None).is_none());
+        assert_eq!(database.entries_for("code").len(), 2);
+        assert!(database.find_entry("code", Some("work")).is_some());
+        assert_eq!(database.rename_entry("missing", "x".to_string()), 0);
+    }
+
+    #[test]
+    fn index_stays_correct_across_many_mutations() {
+        let mut database = CredentialDatabase::new();
+        for i in 0..2_000 {
+            database.add_entry(sample_entry(&format!("Service-{i}")));
+        }
+
+        for i in (0..2_000).step_by(7) {
+            assert!(database.trash_entry(&format!("service-{i}"), None, 1));
+        }
+        for i in (0..2_000).step_by(11) {
+            database.remove_entry(&format!("SERVICE-{i}"), None);
+        }
+        for i in (1..2_000).step_by(13) {
+            database.rename_entry(&format!("service-{i}"), format!("renamed-{i}"));
+        }
+        for i in (2..2_000).step_by(17) {
+            database.move_entry(&format!("service-{i}"), None, format!("edited-{i}"));
+            if let Some(entry) = database.edit_entry(&format!("edited-{i}"), None) {
+                entry.record_access(1);
+            }
+        }
+        assert!(database.restore_from_trash("service-14"));
+
+        let expected: HashMap<String, &String> = database
+            .entries
+            .iter()
+            .map(|entry| (entry.service.to_lowercase(), &entry.service))
+            .collect();
+        for i in 0..2_000 {
+            for name in [
+                format!("service-{i}"),
+                format!("renamed-{i}"),
+                format!("edited-{i}"),
+            ] {
+                assert_eq!(
+                    database.find_entry(&name, None).map(|entry| &entry.service),
+                    expected.get(&name).copied(),
+                    "lookup of {name}"
+                );
+            }
+        }
+        assert!(database.find_entry("service-14", None).is_some());
+        assert!(database.find_entry("service-7", None).is_none());
+        assert!(database.find_entry("renamed-1", None).is_some());
+    }
+
+    #[test]
+    fn edits_and_removals_patch_the_index_in_place() {
+        let mut database = CredentialDatabase::new();
+        for (service, account) in [("github", "me"), ("aws", "root"), ("github", "work")] {
+            database.add_entry(CredentialEntry::new(
+                service.to_string(),
+                account.to_string(),
+                "secret".to_string(),
+            ));
+        }
+        database.add_entry(sample_entry("gitlab"));
+        assert!(database.find_entry("github", None).is_some());
+
+        database
+            .edit_entry("github", Some("work"))
+            .expect("entry")
+            .record_access(1);
+        database.trash_entry("aws", None, 1);
+        assert!(database.move_entry("GitHub", Some("me"), "code".to_string()));
+        assert!(database.index.get().is_some());
+
+        let accounts = |database: &CredentialDatabase, service| -> Vec<String> {
+            database
+                .entries_for(service)
+                .iter()
+                .map(|entry| entry.account.clone())
+                .collect()
+        };
+        assert_eq!(accounts(&database, "github"), vec!["work"]);
+        assert_eq!(accounts(&database, "code"), vec!["me"]);
+
+        database.remove_entry("code", None);
+        assert!(database.index.get().is_some());
+        assert_eq!(accounts(&database, "github"), vec!["work"]);
+        assert_eq!(accounts(&database, "gitlab"), vec!["account"]);
+        assert!(database.find_entry("code", None).is_none());
+
+        let rebuilt: HashMap<_, _> = {
+            let mut fresh = database.clone();
+            fresh.reindex();
+            fresh.index().clone()
+        };
+        assert_eq!(database.index(), &rebuilt);
+    }
+
+    #[test]
+    fn reindex_picks_up_direct_changes_to_entries() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(sample_entry("github"));
+        assert!(database.find_entry("github", None).is_some());
+
+        database.entries.insert(0, sample_entry("gitlab"));
+        assert!(database.find_entry("github", None).is_some());
+
+        database.reindex();
+        assert!(database.find_entry("gitlab", None).is_some());
+    }
+
+    #[test]
+    fn change_summary_reports_added_removed_and_changed() {
+        let mut before = CredentialDatabase::new();
+        for service in ["github", "gitlab"] {
+            before.add_entry(sample_entry(service));
+        }
+        let aws = sample_entry("aws");
+        before.add_entry(aws.clone());
+        before.add_entry(aws);
+
+        let mut after = before.clone();
+        after.add_entry(sample_entry("slack"));
+        after.trash_entry("gitlab", None, 1);
+        after.edit_entry("github", None).expect("entry").account = "someone-else".to_string();
+        after.remove_exact_duplicates();
+
+        let summary = ChangeSummary::between(&before, &after);
+
+        assert_eq!(summary.added, vec!["slack"]);
+        assert_eq!(summary.removed, vec!["aws", "gitlab"]);
+        assert_eq!(summary.changed, vec!["github"]);
+        assert!(ChangeSummary::between(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn rename_tag_updates_every_carrier() {
+        let mut database = CredentialDatabase::new();
+        for (service, tags) in [("a", "work"), ("b", "Work, home"), ("c", "home")] {
+            let mut entry = sample_entry(service);
+            entry.set_tags(crate::model::parse_tags(tags));
+            database.add_entry(entry);
+        }
+
+        assert_eq!(database.rename_tag("work", "job"), 2);
+        assert_eq!(database.list_by_tag("job").len(), 2);
+        assert!(database.list_by_tag("work").is_empty());
+        assert_eq!(
+            database.find_entry("c", None).expect("entry").tags,
+            vec!["home"]
+        );
+    }
+
+    #[test]
+    fn accounts_matching_lists_service_and_account_pairs() {
+        let mut database = CredentialDatabase::new();
+        for (service, account) in [
+            ("AWS-prod", "ops"),
+            ("aws-dev", "dev"),
+            ("github", "octocat"),
+            ("aws-dev", "admin"),
+        ] {
+            database.add_entry(CredentialEntry::new(
+                service.to_string(),
+                account.to_string(),
+                "secret".to_string(),
+            ));
+        }
+
+        assert_eq!(
+            database.accounts_matching("aws"),
+            vec![
+                ("aws-dev", "admin"),
+                ("aws-dev", "dev"),
+                ("AWS-prod", "ops")
+            ]
+        );
+        assert!(database.accounts_matching("gitlab").is_empty());
+    }
+
+    #[test]
+    fn default_matches_new_and_is_empty() {
+        let database = CredentialDatabase::default();
+
+        assert_eq!(database.version, CredentialDatabase::new().version);
+        assert_eq!(database.version, CURRENT_VERSION);
+        assert!(database.is_empty());
+        assert_eq!(database.len(), 0);
+    }
+
+    #[test]
+    fn most_used_orders_by_count_then_recency() {
+        let mut database = CredentialDatabase::new();
+        for service in ["aws", "github", "gitlab", "unused"] {
+            database.add_entry(sample_entry(service));
+        }
+        database.record_access("gitlab", None, 10);
+        database.record_access("github", None, 20);
+        database.record_access("GitHub", None, 30);
+        database.record_access("aws", None, 40);
+        database.record_access("aws", None, 5);
+        assert!(!database.record_access("missing", None, 50));
+
+        let services = |entries: Vec<&CredentialEntry>| -> Vec<String> {
+            entries.iter().map(|entry| entry.service.clone()).collect()
+        };
+        assert_eq!(
+            services(database.most_used(10)),
+            vec!["github", "aws", "gitlab"]
+        );
+        assert_eq!(services(database.most_used(1)), vec!["github"]);
+        assert_eq!(
+            services(database.recently_used(10)),
+            vec!["github", "gitlab", "aws"]
+        );
+    }
+
+    #[test]
+    fn diff_reports_each_side_and_changed_fields() {
+        let mut mine = CredentialDatabase::new();
+        let mut theirs = CredentialDatabase::new();
+        let shared = sample_entry("github");
+        mine.add_entry(shared.clone());
+        theirs.add_entry(shared);
+        mine.add_entry(sample_entry("aws"));
+        theirs.add_entry(sample_entry("Gitlab"));
+        let mut ours = sample_entry("email");
+        ours.updated_at = 10;
+        let mut changed = ours.clone();
+        changed.service = "EMAIL".to_string();
+        changed.secret = "rotated".to_string();
+        changed.updated_at = 20;
+        mine.add_entry(ours);
+        theirs.add_entry(changed);
+
+        let diff = mine.diff(&theirs);
+
+        assert_eq!(diff.only_mine, vec!["aws"]);
+        assert_eq!(diff.only_theirs, vec!["Gitlab"]);
+        assert_eq!(
+            diff.differing,
+            vec![ServiceDiff {
+                service: "email".to_string(),
+                fields: vec!["secret", "updated_at"],
+            }]
+        );
+        assert!(mine.diff(&mine.clone()).is_empty());
+    }
 }
@@ -1,57 +1,266 @@
+use crate::util::time::now_unix;
+use crate::util::totp::TotpParams;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub const DEFAULT_HISTORY_LIMIT: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoricalSecret {
+    pub secret: String,
+    pub replaced_at: u64,
+}
+
+// A removed entry kept so `crab trash restore` can bring it back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrashedEntry {
+    pub entry: CredentialEntry,
+    pub deleted_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CredentialEntry {
     pub service: String,
     pub account: String,
     pub secret: String,
     pub created_at: u64,
     pub updated_at: u64,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub totp_secret: Option<String>,
+    #[serde(default)]
+    pub totp_params: TotpParams,
+    // Previous secrets, oldest first.
+    #[serde(default)]
+    pub history: Vec<HistoricalSecret>,
+    // Extra per-service values such as a region or a recovery email.
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub rotate_after_days: Option<u32>,
+    #[serde(default)]
+    pub last_accessed: Option<u64>,
+    #[serde(default)]
+    pub access_count: u64,
 }
 
 impl CredentialEntry {
     pub fn new(service: String, account: String, secret: String) -> Self {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = now_unix();
         CredentialEntry {
-            service,
+            service: service.trim().to_string(),
             account,
             secret,
             created_at: now,
             updated_at: now,
+            tags: Vec::new(),
+            url: None,
+            totp_secret: None,
+            totp_params: TotpParams::default(),
+            history: Vec::new(),
+            fields: BTreeMap::new(),
+            favorite: false,
+            rotate_after_days: None,
+            last_accessed: None,
+            access_count: 0,
         }
     }
 
     pub fn update_service(&mut self, new_service: String) {
-        self.service = new_service;
-        self.updated_at = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        self.service = new_service.trim().to_string();
+        self.updated_at = now_unix();
     }
 
     pub fn update_account(&mut self, new_account: String) {
         self.account = new_account;
-        self.updated_at = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        self.updated_at = now_unix();
     }
 
     pub fn update_secret(&mut self, new_secret: String) {
-        self.secret = new_secret;
-        self.updated_at = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = now_unix();
+        if new_secret != self.secret {
+            let old_secret = std::mem::replace(&mut self.secret, new_secret);
+            self.history.push(HistoricalSecret {
+                secret: old_secret,
+                replaced_at: now,
+            });
+        }
+        self.updated_at = now;
+    }
+
+    // Drops the oldest history entries so at most `keep` remain.
+    pub fn prune_history(&mut self, keep: usize) {
+        let excess = self.history.len().saturating_sub(keep);
+        self.history.drain(..excess);
+    }
+
+    pub fn update_url(&mut self, new_url: Option<String>) {
+        self.url = new_url;
+        self.updated_at = now_unix();
+    }
+
+    // Clearing the secret also resets the parameters to the defaults.
+    pub fn update_totp_secret(&mut self, new_totp_secret: Option<String>, params: TotpParams) {
+        self.totp_params = if new_totp_secret.is_some() {
+            params
+        } else {
+            TotpParams::default()
+        };
+        self.totp_secret = new_totp_secret;
+        self.updated_at = now_unix();
+    }
+
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags = tags;
+        self.updated_at = now_unix();
+    }
+
+    pub fn add_tag(&mut self, tag: String) {
+        if self.has_tag(&tag) {
+            return;
+        }
+        self.tags.push(tag);
+        self.updated_at = now_unix();
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let before = self.tags.len();
+        self.tags.retain(|t| !t.eq_ignore_ascii_case(tag.trim()));
+        if self.tags.len() == before {
+            return false;
+        }
+        self.updated_at = now_unix();
+        true
+    }
+
+    // Replaces `old` with `new` in place; if the entry already carries `new`
+    // the old tag is simply dropped.
+    pub fn rename_tag(&mut self, old: &str, new: &str) -> bool {
+        let Some(position) = self
+            .tags
+            .iter()
+            .position(|t| t.eq_ignore_ascii_case(old.trim()))
+        else {
+            return false;
+        };
+        if self.tags[position] == new {
+            return false;
+        }
+        if self.has_tag(new) && !new.eq_ignore_ascii_case(old.trim()) {
+            self.tags.remove(position);
+        } else {
+            self.tags[position] = new.to_string();
+        }
+        self.updated_at = now_unix();
+        true
+    }
+
+    pub fn set_field(&mut self, name: String, value: String) {
+        self.fields.insert(name.trim().to_string(), value);
+        self.updated_at = now_unix();
+    }
+
+    pub fn remove_field(&mut self, name: &str) -> bool {
+        if self.fields.remove(name.trim()).is_none() {
+            return false;
+        }
+        self.updated_at = now_unix();
+        true
+    }
+
+    // Notes live in the "notes" field; blank notes remove it.
+    pub fn update_notes(&mut self, notes: &str) {
+        if notes.trim().is_empty() {
+            self.remove_field("notes");
+        } else {
+            self.set_field("notes".to_string(), notes.to_string());
+        }
+    }
+
+    // Deliberately leaves updated_at alone: the rotation clock runs from the
+    // last real change, so marking an old entry can make it due right away.
+    pub fn set_rotation(&mut self, days: Option<u32>) {
+        self.rotate_after_days = days.filter(|days| *days > 0);
+    }
+
+    // When the secret should next be rotated, if a rotation interval is set.
+    pub fn rotation_due_at(&self) -> Option<u64> {
+        self.rotate_after_days.map(|days| {
+            self.updated_at
+                .saturating_add(u64::from(days) * 24 * 60 * 60)
+        })
+    }
+
+    // Reading an entry is not a change to it, so updated_at stays put.
+    pub fn record_access(&mut self, now: u64) {
+        self.access_count = self.access_count.saturating_add(1);
+        self.last_accessed = Some(now);
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim()))
+    }
+
+    // A new entry sharing this one's metadata (tags, URL, rotation interval,
+    // and custom fields that don't look sensitive) but none of its secrets,
+    // history, or usage.
+    pub fn from_template(&self, service: String, account: String, secret: String) -> Self {
+        let mut entry = CredentialEntry::new(service, account, secret);
+        entry.tags = self.tags.clone();
+        entry.url = self.url.clone();
+        entry.rotate_after_days = self.rotate_after_days;
+        entry.fields = self
+            .fields
+            .iter()
+            .filter(|(name, _)| !is_sensitive_field(name))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        entry
+    }
+}
+
+// Field names that suggest the value should be masked like the secret.
+const SENSITIVE_FIELD_HINTS: &[&str] = &["pin", "key", "secret", "password", "token", "code"];
+
+pub fn is_sensitive_field(name: &str) -> bool {
+    let name = name.to_lowercase();
+    SENSITIVE_FIELD_HINTS.iter().any(|hint| name.contains(hint))
+}
+
+// Services are looked up ignoring case and surrounding whitespace; the
+// stored name keeps the casing the user typed.
+pub fn normalize_service(service: &str) -> String {
+    service.trim().to_lowercase()
+}
+
+pub fn same_service(a: &str, b: &str) -> bool {
+    normalize_service(a) == normalize_service(b)
+}
+
+// Parses comma-separated user input like "work, client-x" into a tag list.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+    {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
     }
+    tags
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::totp::TotpAlgorithm;
 
     #[test]
     fn new_initializes_fields() {
@@ -66,6 +275,85 @@ mod tests {
         assert_eq!(entry.secret, "secret");
     }
 
+    #[test]
+    fn clearing_totp_secret_resets_params() {
+        let mut entry = CredentialEntry::new(
+            "service".to_string(),
+            "account".to_string(),
+            "secret".to_string(),
+        );
+        let params = TotpParams {
+            digits: 8,
+            period: 60,
+            ..TotpParams::default()
+        };
+
+        entry.update_totp_secret(Some("JBSWY3DP".to_string()), params);
+        assert_eq!(entry.totp_params, params);
+
+        entry.update_totp_secret(None, params);
+        assert_eq!(entry.totp_secret, None);
+        assert!(entry.totp_params.is_default());
+    }
+
+    #[test]
+    fn totp_params_default_when_missing_and_round_trip() {
+        let old = r#"{"service":"s","account":"a","secret":"x","created_at":1,"updated_at":1,"totp_secret":"JBSWY3DP"}"#;
+        let entry: CredentialEntry = serde_json::from_str(old).expect("parse");
+        assert!(entry.totp_params.is_default());
+
+        let custom = r#"{"service":"s","account":"a","secret":"x","created_at":1,"updated_at":1,
+            "totp_secret":"JBSWY3DP","totp_params":{"algorithm":"SHA256","digits":8}}"#;
+        let entry: CredentialEntry = serde_json::from_str(custom).expect("parse");
+        assert_eq!(entry.totp_params.algorithm, TotpAlgorithm::Sha256);
+        assert_eq!(entry.totp_params.digits, 8);
+        assert_eq!(entry.totp_params.period, 30);
+
+        let json = serde_json::to_string(&entry).expect("serialize");
+        let reparsed: CredentialEntry = serde_json::from_str(&json).expect("parse");
+        assert_eq!(reparsed, entry);
+    }
+
+    #[test]
+    fn from_template_copies_metadata_but_no_secrets() {
+        let mut template = CredentialEntry::new(
+            "aws-prod".to_string(),
+            "admin".to_string(),
+            "secret".to_string(),
+        );
+        template.add_tag("work".to_string());
+        template.update_url(Some("https://aws.amazon.com".to_string()));
+        template.update_totp_secret(Some("JBSWY3DP".to_string()), TotpParams::default());
+        template.update_secret("rotated".to_string());
+        template.set_field("notes".to_string(), "billing account".to_string());
+        template.set_field("recovery_code".to_string(), "1234".to_string());
+        template.set_rotation(Some(90));
+        template.favorite = true;
+        template.record_access(10);
+
+        let entry = template.from_template(
+            "aws-staging".to_string(),
+            "deployer".to_string(),
+            "new-secret".to_string(),
+        );
+
+        assert_eq!(entry.service, "aws-staging");
+        assert_eq!(entry.account, "deployer");
+        assert_eq!(entry.secret, "new-secret");
+        assert_eq!(entry.tags, vec!["work"]);
+        assert_eq!(entry.url.as_deref(), Some("https://aws.amazon.com"));
+        assert_eq!(entry.rotate_after_days, Some(90));
+        assert_eq!(
+            entry.fields.get("notes").map(String::as_str),
+            Some("billing account")
+        );
+        assert!(!entry.fields.contains_key("recovery_code"));
+        assert_eq!(entry.totp_secret, None);
+        assert!(entry.history.is_empty());
+        assert!(!entry.favorite);
+        assert_eq!(entry.access_count, 0);
+    }
+
     #[test]
     fn update_methods_replace_field_values() {
         let mut entry = CredentialEntry::new(
@@ -81,4 +369,237 @@ mod tests {
         assert_eq!(entry.account, "account2");
         assert_eq!(entry.secret, "secret2");
     }
+
+    #[test]
+    fn update_secret_records_history() {
+        let mut entry = CredentialEntry::new(
+            "service".to_string(),
+            "account".to_string(),
+            "first".to_string(),
+        );
+        entry.update_secret("second".to_string());
+        entry.update_secret("second".to_string());
+        entry.update_secret("third".to_string());
+
+        let history: Vec<&str> = entry.history.iter().map(|h| h.secret.as_str()).collect();
+        assert_eq!(history, vec!["first", "second"]);
+        assert_eq!(entry.secret, "third");
+    }
+
+    #[test]
+    fn prune_history_keeps_newest() {
+        let mut entry = CredentialEntry::new(
+            "service".to_string(),
+            "account".to_string(),
+            "v0".to_string(),
+        );
+        for version in 1..=4 {
+            entry.update_secret(format!("v{version}"));
+        }
+
+        entry.prune_history(2);
+
+        let history: Vec<&str> = entry.history.iter().map(|h| h.secret.as_str()).collect();
+        assert_eq!(history, vec!["v2", "v3"]);
+
+        entry.prune_history(0);
+        assert!(entry.history.is_empty());
+    }
+
+    #[test]
+    fn update_url_sets_and_clears() {
+        let mut entry = CredentialEntry::new(
+            "service".to_string(),
+            "account".to_string(),
+            "secret".to_string(),
+        );
+        entry.update_url(Some("https://example.com".to_string()));
+        assert_eq!(entry.url.as_deref(), Some("https://example.com"));
+
+        entry.update_url(None);
+        assert!(entry.url.is_none());
+    }
+
+    #[test]
+    fn update_notes_sets_and_blank_removes() {
+        let mut entry = CredentialEntry::new(
+            "service".to_string(),
+            "account".to_string(),
+            "secret".to_string(),
+        );
+        entry.update_notes("billing account");
+        assert_eq!(
+            entry.fields.get("notes").map(String::as_str),
+            Some("billing account")
+        );
+
+        entry.update_notes("  ");
+        assert!(!entry.fields.contains_key("notes"));
+    }
+
+    #[test]
+    fn add_tag_ignores_duplicates() {
+        let mut entry = CredentialEntry::new(
+            "service".to_string(),
+            "account".to_string(),
+            "secret".to_string(),
+        );
+        entry.add_tag("work".to_string());
+        entry.add_tag("Work".to_string());
+
+        assert_eq!(entry.tags, vec!["work"]);
+        assert!(entry.has_tag("WORK"));
+    }
+
+    #[test]
+    fn remove_tag_matches_case_insensitively() {
+        let mut entry = CredentialEntry::new(
+            "service".to_string(),
+            "account".to_string(),
+            "secret".to_string(),
+        );
+        entry.set_tags(vec!["work".to_string(), "client".to_string()]);
+
+        assert!(entry.remove_tag(" WORK "));
+        assert!(!entry.remove_tag("work"));
+        assert_eq!(entry.tags, vec!["client"]);
+    }
+
+    #[test]
+    fn rename_tag_replaces_in_place_or_merges() {
+        let mut entry = CredentialEntry::new(
+            "service".to_string(),
+            "account".to_string(),
+            "secret".to_string(),
+        );
+        entry.set_tags(vec!["work".to_string(), "client".to_string()]);
+
+        assert!(entry.rename_tag("work", "job"));
+        assert_eq!(entry.tags, vec!["job", "client"]);
+        assert!(entry.rename_tag("job", "client"));
+        assert_eq!(entry.tags, vec!["client"]);
+        assert!(entry.rename_tag("client", "Client"));
+        assert_eq!(entry.tags, vec!["Client"]);
+        assert!(!entry.rename_tag("missing", "other"));
+    }
+
+    #[test]
+    fn service_names_are_trimmed_and_compared_case_insensitively() {
+        let entry = CredentialEntry::new(
+            " GitHub ".to_string(),
+            "account".to_string(),
+            "secret".to_string(),
+        );
+
+        assert_eq!(entry.service, "GitHub");
+        assert_eq!(normalize_service(" GitHub "), "github");
+        assert!(same_service("GitHub", " github "));
+        assert!(!same_service("github", "gitlab"));
+    }
+
+    #[test]
+    fn parse_tags_trims_and_dedupes() {
+        assert_eq!(
+            parse_tags(" work, personal ,,Work"),
+            vec!["work", "personal"]
+        );
+        assert!(parse_tags("  ").is_empty());
+    }
+
+    #[test]
+    fn entry_without_tags_deserializes() {
+        let json = r#"{
+            "service": "github",
+            "account": "alice",
+            "secret": "secret",
+            "created_at": 1,
+            "updated_at": 2
+        }"#;
+
+        let entry: CredentialEntry = serde_json::from_str(json).expect("legacy entry should load");
+
+        assert!(entry.tags.is_empty());
+        assert!(entry.url.is_none());
+        assert!(entry.history.is_empty());
+        assert_eq!(entry.access_count, 0);
+        assert!(entry.last_accessed.is_none());
+    }
+
+    #[test]
+    fn record_access_counts_without_touching_updated_at() {
+        let mut entry = CredentialEntry::new(
+            "service".to_string(),
+            "account".to_string(),
+            "secret".to_string(),
+        );
+        entry.updated_at = 1;
+
+        entry.record_access(10);
+        entry.record_access(20);
+
+        assert_eq!(entry.access_count, 2);
+        assert_eq!(entry.last_accessed, Some(20));
+        assert_eq!(entry.updated_at, 1);
+    }
+
+    #[test]
+    fn set_and_remove_field_bump_updated_at() {
+        let mut entry = CredentialEntry::new(
+            "service".to_string(),
+            "account".to_string(),
+            "secret".to_string(),
+        );
+        entry.updated_at = 0;
+
+        entry.set_field(" region ".to_string(), "eu-west-1".to_string());
+        assert_eq!(
+            entry.fields.get("region").map(String::as_str),
+            Some("eu-west-1")
+        );
+        assert!(entry.updated_at > 0);
+
+        entry.updated_at = 0;
+        assert!(!entry.remove_field("missing"));
+        assert_eq!(entry.updated_at, 0);
+        assert!(entry.remove_field("region"));
+        assert!(entry.fields.is_empty());
+        assert!(entry.updated_at > 0);
+    }
+
+    #[test]
+    fn entries_without_fields_still_load() {
+        let json =
+            r#"{"service": "s", "account": "a", "secret": "x", "created_at": 1, "updated_at": 1}"#;
+
+        let entry: CredentialEntry = serde_json::from_str(json).expect("should parse");
+
+        assert!(entry.fields.is_empty());
+    }
+
+    #[test]
+    fn sensitive_field_names_are_detected() {
+        assert!(is_sensitive_field("Security PIN"));
+        assert!(is_sensitive_field("api_key"));
+        assert!(is_sensitive_field("Recovery codes"));
+        assert!(!is_sensitive_field("region"));
+        assert!(!is_sensitive_field("recovery email"));
+    }
+
+    #[test]
+    fn rotation_is_due_after_the_interval() {
+        let mut entry = CredentialEntry::new(
+            "service".to_string(),
+            "account".to_string(),
+            "secret".to_string(),
+        );
+        entry.updated_at = 1_000;
+        assert_eq!(entry.rotation_due_at(), None);
+
+        entry.set_rotation(Some(2));
+        assert_eq!(entry.rotation_due_at(), Some(1_000 + 2 * 86_400));
+        assert_eq!(entry.updated_at, 1_000);
+
+        entry.set_rotation(Some(0));
+        assert_eq!(entry.rotate_after_days, None);
+    }
 }
@@ -0,0 +1,141 @@
+use crate::error::{CredentialError, CredentialResult};
+use crate::util::time::now_unix;
+use serde_json::{json, Value};
+
+pub const CURRENT_VERSION: &str = "1.1";
+
+type Migration = fn(&mut Value);
+
+// Ordered upgrade steps: each one takes a database at `from` to the next version.
+const MIGRATIONS: &[(&str, Migration)] =
+    &[("0.9", migrate_0_9_to_1_0), ("1.0", migrate_1_0_to_1_1)];
+
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let (major, minor) = version.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+fn next_version(version: &str) -> Option<&'static str> {
+    let index = MIGRATIONS.iter().position(|(from, _)| *from == version)?;
+    Some(
+        MIGRATIONS
+            .get(index + 1)
+            .map(|(from, _)| *from)
+            .unwrap_or(CURRENT_VERSION),
+    )
+}
+
+// Upgrades a raw database document to CURRENT_VERSION. Returns whether anything
+// changed so the caller knows to rewrite the file.
+pub fn migrate(mut database: Value) -> CredentialResult<(Value, bool)> {
+    let mut version = database
+        .get("version")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+
+    let current = parse_version(CURRENT_VERSION).expect("CURRENT_VERSION is well-formed");
+    match parse_version(&version) {
+        Some(parsed) if parsed <= current => {}
+        _ => return Err(CredentialError::UnsupportedVersion(version)),
+    }
+
+    let mut migrated = false;
+    while version != CURRENT_VERSION {
+        let (_, step) = MIGRATIONS
+            .iter()
+            .find(|(from, _)| *from == version)
+            .ok_or_else(|| CredentialError::UnsupportedVersion(version.clone()))?;
+        step(&mut database);
+
+        version = next_version(&version)
+            .expect("a migration step exists for this version")
+            .to_string();
+        database["version"] = json!(version);
+        migrated = true;
+    }
+
+    Ok((database, migrated))
+}
+
+fn entries_mut(database: &mut Value) -> impl Iterator<Item = &mut serde_json::Map<String, Value>> {
+    database
+        .get_mut("entries")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+}
+
+// 0.9 entries had no timestamps; stamp them with the time of the upgrade.
+fn migrate_0_9_to_1_0(database: &mut Value) {
+    let now = now_unix();
+
+    for entry in entries_mut(database) {
+        entry.entry("created_at").or_insert(json!(now));
+        entry.entry("updated_at").or_insert(json!(now));
+    }
+}
+
+// 1.1 adds tags and url to every entry.
+fn migrate_1_0_to_1_1(database: &mut Value) {
+    for entry in entries_mut(database) {
+        entry.entry("tags").or_insert(json!([]));
+        entry.entry("url").or_insert(Value::Null);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::CredentialDatabase;
+
+    #[test]
+    fn migrates_0_9_fixture_to_current() {
+        let fixture = json!({
+            "version": "0.9",
+            "entries": [
+                { "service": "github", "account": "alice", "secret": "s1" }
+            ]
+        });
+
+        let (migrated, changed) = migrate(fixture).expect("migration should succeed");
+        let database: CredentialDatabase =
+            serde_json::from_value(migrated).expect("migrated database should load");
+
+        assert!(changed);
+        assert_eq!(database.version, CURRENT_VERSION);
+        let entry = database
+            .find_entry("github", None)
+            .expect("entry should exist");
+        assert!(entry.created_at > 0);
+        assert!(entry.tags.is_empty());
+    }
+
+    #[test]
+    fn current_version_is_left_alone() {
+        let document = json!({ "version": CURRENT_VERSION, "entries": [] });
+
+        let (migrated, changed) = migrate(document.clone()).expect("migration should succeed");
+
+        assert!(!changed);
+        assert_eq!(migrated, document);
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let result = migrate(json!({ "version": "9.0", "entries": [] }));
+
+        assert!(matches!(result, Err(CredentialError::UnsupportedVersion(v)) if v == "9.0"));
+    }
+
+    #[test]
+    fn unknown_version_is_rejected() {
+        let result = migrate(json!({ "version": "beta", "entries": [] }));
+
+        assert!(matches!(
+            result,
+            Err(CredentialError::UnsupportedVersion(_))
+        ));
+    }
+}
@@ -1,5 +1,9 @@
 pub mod database;
 pub mod entry;
+pub mod migrate;
 
-pub use database::CredentialDatabase;
-pub use entry::CredentialEntry;
+pub use database::{
+    favorites_first, sort_entries, ChangeSummary, ConflictStrategy, CredentialDatabase, DbDiff,
+    SearchField, SortKey,
+};
+pub use entry::{is_sensitive_field, parse_tags, same_service, CredentialEntry, TrashedEntry};
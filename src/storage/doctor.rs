@@ -0,0 +1,322 @@
+use crate::crypto;
+use crate::storage::file::{self, get_database_path};
+use crate::util::clipboard::SystemClipboard;
+use crate::util::time::unix_secs;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+// 2020-01-01: anything earlier means the clock was never set.
+const PLAUSIBLE_UNIX_TIME: u64 = 1_577_836_800;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+// Runs every check without prompting or writing anything. The vault is only
+// inspected as far as it can be without the master password.
+pub fn run_checks() -> Vec<Check> {
+    let database_path = get_database_path();
+    let mut checks = vec![check_home(
+        dirs::home_dir().as_deref(),
+        database_path.is_ok(),
+    )];
+
+    match database_path {
+        Ok(path) => {
+            checks.push(check_vault_dir(file::database_dir(&path)));
+            checks.push(check_database_file(&path));
+        }
+        Err(e) => checks.push(Check::fail(
+            "database",
+            format!("the database path can't be resolved: {e}"),
+            "Set CRAB_DB_PATH or database_path in the config",
+        )),
+    }
+
+    checks.push(check_clock(SystemTime::now()));
+    checks.push(check_clipboard());
+    checks
+}
+
+fn check_home(home: Option<&Path>, database_resolves: bool) -> Check {
+    match home {
+        Some(home) => Check::pass("home", home.display().to_string()),
+        // CRAB_DB_PATH or the config can still point somewhere usable.
+        None if database_resolves => Check::warn(
+            "home",
+            "home directory not found",
+            "Sessions and the default config location need HOME (USERPROFILE on Windows)",
+        ),
+        None => Check::fail(
+            "home",
+            "home directory not found",
+            "Set HOME (USERPROFILE on Windows) or CRAB_DB_PATH",
+        ),
+    }
+}
+
+fn check_vault_dir(dir: &Path) -> Check {
+    let metadata = match fs::metadata(dir) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Check::warn(
+                "vault directory",
+                format!("{} does not exist", dir.display()),
+                "Run 'crab init' to create it",
+            );
+        }
+        Err(e) => {
+            return Check::fail(
+                "vault directory",
+                format!("{}: {e}", dir.display()),
+                "Check that the directory is readable by you",
+            );
+        }
+    };
+    if !metadata.is_dir() {
+        return Check::fail(
+            "vault directory",
+            format!("{} is not a directory", dir.display()),
+            "Move the file out of the way, then run 'crab init'",
+        );
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = metadata.permissions().mode() & 0o777;
+        if mode & !file::PRIVATE_DIR_MODE != 0 {
+            return Check::warn(
+                "vault directory",
+                format!("{} is mode {mode:o}", dir.display()),
+                format!("Run 'chmod 700 {}'", dir.display()),
+            );
+        }
+    }
+    Check::pass("vault directory", dir.display().to_string())
+}
+
+fn check_database_file(path: &Path) -> Check {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Check::warn(
+                "database",
+                format!("{} does not exist", path.display()),
+                "Run 'crab init' or 'crab add' to create it",
+            );
+        }
+        Err(e) => {
+            return Check::fail(
+                "database",
+                format!("{} can't be read: {e}", path.display()),
+                format!(
+                    "Check the file's owner and permissions ('chmod 600 {}')",
+                    path.display()
+                ),
+            );
+        }
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = fs::metadata(path) {
+            let mode = metadata.permissions().mode() & 0o777;
+            if mode & !file::PRIVATE_FILE_MODE != 0 {
+                return Check::warn(
+                    "database",
+                    format!("{} is mode {mode:o}", path.display()),
+                    format!("Run 'chmod 600 {}'", path.display()),
+                );
+            }
+        }
+    }
+
+    if crypto::is_encrypted(&data) {
+        return match crypto::read_header(&data) {
+            Ok(header) => Check::pass(
+                "database",
+                format!(
+                    "{} (encrypted, Argon2id {} MiB)",
+                    path.display(),
+                    header.kdf.memory_kib / 1024
+                ),
+            ),
+            Err(e) => Check::fail(
+                "database",
+                format!("{} has an unreadable header: {e}", path.display()),
+                "Restore a backup with 'crab restore'",
+            ),
+        };
+    }
+
+    match file::parse_database(&data) {
+        Ok(_) => Check::warn(
+            "database",
+            format!("{} is not encrypted", path.display()),
+            "Run any command to set a master password",
+        ),
+        Err(e) => Check::fail(
+            "database",
+            format!("{} does not parse: {e}", path.display()),
+            "Run 'crab verify' for details, or restore a backup with 'crab restore'",
+        ),
+    }
+}
+
+fn check_clock(now: SystemTime) -> Check {
+    let secs = unix_secs(now);
+    if secs == 0 {
+        return Check::fail(
+            "clock",
+            "the system clock is before 1970",
+            "Set the system date; timestamps and TOTP codes depend on it",
+        );
+    }
+    if secs < PLAUSIBLE_UNIX_TIME {
+        return Check::warn(
+            "clock",
+            "the system clock is set before 2020",
+            "Set the system date; TOTP codes will be wrong until you do",
+        );
+    }
+    Check::pass("clock", format!("{secs} seconds since the Unix epoch"))
+}
+
+// The clipboard is optional: `get --show` works without it.
+fn check_clipboard() -> Check {
+    match SystemClipboard::new() {
+        Ok(_) => Check::pass("clipboard", "available"),
+        Err(e) => Check::warn(
+            "clipboard",
+            e.to_string(),
+            "Install a clipboard tool (xclip, xsel, or wl-clipboard) or use 'crab get --show'",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn missing_home_fails_only_without_a_database_path() {
+        assert_eq!(check_home(None, false).status, CheckStatus::Fail);
+        assert_eq!(check_home(None, true).status, CheckStatus::Warn);
+        assert_eq!(
+            check_home(Some(Path::new("/home/me")), true).status,
+            CheckStatus::Pass
+        );
+    }
+
+    #[test]
+    fn clock_before_epoch_fails() {
+        let before_epoch = UNIX_EPOCH - Duration::from_secs(60);
+        assert_eq!(check_clock(before_epoch).status, CheckStatus::Fail);
+        assert_eq!(
+            check_clock(UNIX_EPOCH + Duration::from_secs(1_000)).status,
+            CheckStatus::Warn
+        );
+        assert_eq!(
+            check_clock(UNIX_EPOCH + Duration::from_secs(1_800_000_000)).status,
+            CheckStatus::Pass
+        );
+    }
+
+    #[test]
+    fn database_file_checks_report_missing_corrupt_and_plaintext() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let path = temp_dir.path().join("credentials.json");
+        assert_eq!(check_database_file(&path).status, CheckStatus::Warn);
+
+        fs::write(&path, b"{ not json").expect("write");
+        set_private(&path);
+        assert_eq!(check_database_file(&path).status, CheckStatus::Fail);
+
+        fs::write(&path, plaintext_database()).expect("write");
+        let check = check_database_file(&path);
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.detail.contains("not encrypted"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn loose_permissions_are_flagged() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let dir = temp_dir.path().join(".crab");
+        fs::create_dir(&dir).expect("mkdir");
+
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).expect("chmod");
+        assert_eq!(check_vault_dir(&dir).status, CheckStatus::Warn);
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700)).expect("chmod");
+        assert_eq!(check_vault_dir(&dir).status, CheckStatus::Pass);
+
+        let path = dir.join("credentials.json");
+        fs::write(&path, plaintext_database()).expect("write");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).expect("chmod");
+        let check = check_database_file(&path);
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert!(check.hint.is_some_and(|hint| hint.contains("chmod 600")));
+    }
+
+    fn plaintext_database() -> Vec<u8> {
+        serde_json::to_vec(&crate::model::CredentialDatabase::new()).expect("json")
+    }
+
+    fn set_private(path: &Path) {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600)).expect("chmod");
+        }
+        #[cfg(not(unix))]
+        let _ = path;
+    }
+}
@@ -0,0 +1,301 @@
+use crate::error::{CredentialError, CredentialResult};
+use crate::model::{CredentialDatabase, CredentialEntry};
+use clap::ValueEnum;
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Jsonl,
+    Csv,
+    Env,
+}
+
+// Formats for writing a single entry with `crab get --out`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EntryFormat {
+    Json,
+    Env,
+}
+
+pub const CSV_HEADERS: [&str; 5] = ["service", "account", "secret", "created_at", "updated_at"];
+
+pub fn export_database(
+    database: &CredentialDatabase,
+    format: ExportFormat,
+    writer: impl Write,
+) -> CredentialResult<()> {
+    match format {
+        ExportFormat::Json => export_json(database, writer),
+        ExportFormat::Jsonl => export_jsonl(database, writer),
+        ExportFormat::Env => export_env(database, writer),
+        ExportFormat::Csv => export_csv(database, writer),
+    }
+}
+
+pub fn export_json(database: &CredentialDatabase, mut writer: impl Write) -> CredentialResult<()> {
+    serde_json::to_writer_pretty(&mut writer, &database.entries)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+// One compact entry per line, written and flushed as it goes, so large vaults
+// never need a second copy in memory and readers can stream the records.
+pub fn export_jsonl(database: &CredentialDatabase, mut writer: impl Write) -> CredentialResult<()> {
+    for entry in &database.entries {
+        serde_json::to_writer(&mut writer, entry)?;
+        writeln!(writer)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+// A `.env` file: each service as a variable name (see `env_var_prefix`) set to
+// its secret. Accounts and other fields have nowhere to go and are left out.
+pub fn export_env(database: &CredentialDatabase, mut writer: impl Write) -> CredentialResult<()> {
+    for entry in &database.entries {
+        writeln!(
+            writer,
+            "{}={}",
+            env_var_prefix(&entry.service),
+            dotenv_quote(&entry.secret)
+        )?;
+    }
+    Ok(())
+}
+
+// Leaves simple values bare and double-quotes the rest, escaping what a
+// dotenv parser would otherwise interpret: quotes, backslashes, `$`
+// interpolation, and line breaks.
+fn dotenv_quote(value: &str) -> String {
+    let is_bare = |c: char| c.is_ascii_alphanumeric() || "_-./:@%+,".contains(c);
+    if value.chars().all(is_bare) {
+        return value.to_string();
+    }
+
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' | '\\' | '$' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+pub fn export_csv(database: &CredentialDatabase, writer: impl Write) -> CredentialResult<()> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+
+    csv_writer.write_record(CSV_HEADERS).map_err(csv_error)?;
+    for entry in &database.entries {
+        csv_writer
+            .write_record([
+                entry.service.as_str(),
+                entry.account.as_str(),
+                entry.secret.as_str(),
+                &entry.created_at.to_string(),
+                &entry.updated_at.to_string(),
+            ])
+            .map_err(csv_error)?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+pub fn export_entry(
+    entry: &CredentialEntry,
+    format: EntryFormat,
+    mut writer: impl Write,
+) -> CredentialResult<()> {
+    match format {
+        EntryFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, entry)?;
+            writeln!(writer)?;
+        }
+        EntryFormat::Env => {
+            let prefix = env_var_prefix(&entry.service);
+            writeln!(
+                writer,
+                "export {prefix}_ACCOUNT={}",
+                shell_quote(&entry.account)
+            )?;
+            writeln!(
+                writer,
+                "export {prefix}_SECRET={}",
+                shell_quote(&entry.secret)
+            )?;
+            if let Some(url) = &entry.url {
+                writeln!(writer, "export {prefix}_URL={}", shell_quote(url))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// "my-api.example" becomes MY_API_EXAMPLE; a leading digit gets an underscore
+// in front since variable names can't start with one.
+pub fn env_var_prefix(service: &str) -> String {
+    let name: String = service
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{name}")
+    } else {
+        name
+    }
+}
+
+// Single quotes keep the shell from expanding anything; embedded quotes are
+// closed, escaped, and reopened.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn csv_error(err: csv::Error) -> CredentialError {
+    CredentialError::IoError(std::io::Error::other(format!("CSV export failed: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::CredentialEntry;
+
+    fn sample_database() -> CredentialDatabase {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(CredentialEntry::new(
+            "github".to_string(),
+            "alice".to_string(),
+            "p@ss, \"quoted\"".to_string(),
+        ));
+        database.add_entry(CredentialEntry::new(
+            "aws".to_string(),
+            "bob".to_string(),
+            "secret".to_string(),
+        ));
+        database
+    }
+
+    #[test]
+    fn export_json_writes_all_entries() {
+        let mut output = Vec::new();
+
+        export_json(&sample_database(), &mut output).expect("export should succeed");
+        let entries: Vec<CredentialEntry> =
+            serde_json::from_slice(&output).expect("output should parse");
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].service, "github");
+        assert_eq!(entries[0].secret, "p@ss, \"quoted\"");
+    }
+
+    #[test]
+    fn export_jsonl_writes_one_entry_per_line() {
+        let mut database = sample_database();
+        database.add_entry(CredentialEntry::new(
+            "multi".to_string(),
+            "carol".to_string(),
+            "line one\nline two".to_string(),
+        ));
+        let mut output = Vec::new();
+
+        export_jsonl(&database, &mut output).expect("export should succeed");
+        let text = String::from_utf8(output).expect("utf8");
+        let entries: Vec<CredentialEntry> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("each line should parse"))
+            .collect();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries, database.entries);
+        assert!(text.ends_with('\n'));
+    }
+
+    #[test]
+    fn dotenv_quote_leaves_simple_values_bare() {
+        assert_eq!(dotenv_quote("abc-123_x.y"), "abc-123_x.y");
+        assert_eq!(dotenv_quote(""), "");
+        assert_eq!(dotenv_quote("two words"), "\"two words\"");
+        assert_eq!(dotenv_quote("a\"b\\c$d\ne"), "\"a\\\"b\\\\c\\$d\\ne\"");
+    }
+
+    #[test]
+    fn export_csv_writes_header_and_escapes_values() {
+        let mut output = Vec::new();
+
+        export_csv(&sample_database(), &mut output).expect("export should succeed");
+        let text = String::from_utf8(output).expect("utf8");
+        let mut lines = text.lines();
+
+        assert_eq!(
+            lines.next(),
+            Some("service,account,secret,created_at,updated_at")
+        );
+        assert!(lines
+            .next()
+            .expect("first row")
+            .starts_with("github,alice,\"p@ss, \"\"quoted\"\"\","));
+    }
+
+    #[test]
+    fn export_csv_round_trips_through_reader() {
+        let mut output = Vec::new();
+
+        export_csv(&sample_database(), &mut output).expect("export should succeed");
+        let mut reader = csv::Reader::from_reader(output.as_slice());
+        let secrets: Vec<String> = reader
+            .records()
+            .map(|record| record.expect("record")[2].to_string())
+            .collect();
+
+        assert_eq!(secrets, vec!["p@ss, \"quoted\"", "secret"]);
+    }
+
+    #[test]
+    fn export_entry_env_quotes_values() {
+        let mut entry = CredentialEntry::new(
+            "my-api.example".to_string(),
+            "bob".to_string(),
+            "it's $HOME".to_string(),
+        );
+        entry.url = Some("https://api.example".to_string());
+        let mut output = Vec::new();
+
+        export_entry(&entry, EntryFormat::Env, &mut output).expect("export should succeed");
+
+        assert_eq!(
+            String::from_utf8(output).expect("utf8"),
+            "export MY_API_EXAMPLE_ACCOUNT='bob'\n\
+             export MY_API_EXAMPLE_SECRET='it'\\''s $HOME'\n\
+             export MY_API_EXAMPLE_URL='https://api.example'\n"
+        );
+        assert_eq!(env_var_prefix("1password"), "_1PASSWORD");
+    }
+
+    #[test]
+    fn export_entry_json_round_trips() {
+        let database = sample_database();
+        let mut output = Vec::new();
+
+        export_entry(&database.entries[0], EntryFormat::Json, &mut output)
+            .expect("export should succeed");
+        let entry: CredentialEntry = serde_json::from_slice(&output).expect("output should parse");
+
+        assert_eq!(entry, database.entries[0]);
+    }
+}
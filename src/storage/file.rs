@@ -1,9 +1,208 @@
+use crate::crypto;
+use crate::crypto::kdf::KdfParams;
 use crate::error::{CredentialError, CredentialResult};
+use crate::model::migrate::{self, CURRENT_VERSION};
 use crate::model::CredentialDatabase;
+use crate::util::style::{errln, outln};
+use crate::util::time::now_unix;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+pub const DB_PATH_ENV: &str = "CRAB_DB_PATH";
+pub const READ_ONLY_ENV: &str = "CRAB_READONLY";
+
+// Location from the config file; CRAB_DB_PATH still takes precedence over it.
+fn configured_database_path() -> &'static Mutex<Option<PathBuf>> {
+    static PATH: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+    PATH.get_or_init(|| Mutex::new(None))
+}
+
+pub const DEFAULT_TRASH_RETENTION_DAYS: u64 = 30;
+
+// Trashed entries older than this are dropped whenever the database is saved.
+// Zero keeps them until `crab trash empty`.
+fn trash_retention_setting() -> &'static Mutex<u64> {
+    static DAYS: OnceLock<Mutex<u64>> = OnceLock::new();
+    DAYS.get_or_init(|| Mutex::new(DEFAULT_TRASH_RETENTION_DAYS))
+}
+
+pub fn set_trash_retention_days(days: u64) {
+    *trash_retention_setting()
+        .lock()
+        .expect("trash retention lock poisoned") = days;
+}
+
+fn trash_cutoff(now: u64) -> Option<u64> {
+    let days = *trash_retention_setting()
+        .lock()
+        .expect("trash retention lock poisoned");
+    (days > 0).then(|| now.saturating_sub(days.saturating_mul(24 * 60 * 60)))
+}
+
+// Re-reads every save to make sure it round-trips. Off by default since it
+// costs an extra read and decrypt per save.
+fn verify_saves_setting() -> &'static Mutex<bool> {
+    static VERIFY: OnceLock<Mutex<bool>> = OnceLock::new();
+    VERIFY.get_or_init(|| Mutex::new(false))
+}
+
+pub fn set_verify_saves(enabled: bool) {
+    *verify_saves_setting()
+        .lock()
+        .expect("verify saves lock poisoned") = enabled;
+}
+
+fn verify_saves() -> bool {
+    *verify_saves_setting()
+        .lock()
+        .expect("verify saves lock poisoned")
+}
+
+// Where backups go when the config names a folder; beside the vault otherwise.
+fn configured_backup_dir() -> &'static Mutex<Option<PathBuf>> {
+    static DIR: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+    DIR.get_or_init(|| Mutex::new(None))
+}
+
+pub fn set_configured_backup_dir(dir: PathBuf) {
+    *configured_backup_dir()
+        .lock()
+        .expect("backup dir lock poisoned") = Some(dir);
+}
+
+// Set by --read-only; CRAB_READONLY turns it on as well. Every function here
+// that writes the vault checks it, so nothing reaches the file even if a
+// command slips past the check in the CLI.
+fn read_only_setting() -> &'static Mutex<bool> {
+    static READ_ONLY: OnceLock<Mutex<bool>> = OnceLock::new();
+    READ_ONLY.get_or_init(|| Mutex::new(false))
+}
+
+pub fn set_read_only(enabled: bool) {
+    *read_only_setting().lock().expect("read-only lock poisoned") = enabled;
+}
+
+pub fn is_read_only() -> bool {
+    *read_only_setting().lock().expect("read-only lock poisoned")
+        || std::env::var_os(READ_ONLY_ENV).is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+fn ensure_writable() -> CredentialResult<()> {
+    if is_read_only() {
+        return Err(CredentialError::ReadOnly);
+    }
+    Ok(())
+}
+
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// An exclusive advisory lock on `<database>.lock`, released when dropped. It
+// only coordinates crab processes; nothing stops other programs writing.
+pub struct VaultLock {
+    _file: fs::File,
+}
+
+impl VaultLock {
+    pub fn acquire(database: &Path, timeout: Duration) -> CredentialResult<Self> {
+        let path = lock_path(database);
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(false);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(PRIVATE_FILE_MODE);
+        }
+        let file = options.open(&path)?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(Self { _file: file }),
+                Err(fs::TryLockError::WouldBlock) if Instant::now() < deadline => {
+                    std::thread::sleep(LOCK_POLL_INTERVAL)
+                }
+                Err(fs::TryLockError::WouldBlock) => {
+                    return Err(CredentialError::VaultLocked(path))
+                }
+                Err(fs::TryLockError::Error(e)) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+fn lock_path(database: &Path) -> PathBuf {
+    let mut file_name = database.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".lock");
+    database.with_file_name(file_name)
+}
+
+// The lock this process holds on the live vault, if any.
+fn held_lock() -> &'static Mutex<Option<VaultLock>> {
+    static HELD: OnceLock<Mutex<Option<VaultLock>>> = OnceLock::new();
+    HELD.get_or_init(|| Mutex::new(None))
+}
+
+// Set for commands that modify the vault: the lock taken by their first load is
+// kept until the process exits, so load, edit and save form one critical
+// section. Other commands only hold it while a load or save is in progress.
+fn hold_lock_setting() -> &'static Mutex<bool> {
+    static HOLD: OnceLock<Mutex<bool>> = OnceLock::new();
+    HOLD.get_or_init(|| Mutex::new(false))
+}
+
+pub fn set_hold_lock(enabled: bool) {
+    *hold_lock_setting().lock().expect("hold lock poisoned") = enabled;
+}
+
+fn hold_lock() -> bool {
+    *hold_lock_setting().lock().expect("hold lock poisoned")
+}
+
+// Releases the vault lock when dropped if this scope took it and the command
+// doesn't need it kept.
+struct LockScope {
+    acquired: bool,
+}
+
+impl Drop for LockScope {
+    fn drop(&mut self) {
+        if self.acquired && !hold_lock() {
+            *held_lock().lock().expect("vault lock poisoned") = None;
+        }
+    }
+}
+
+fn lock_vault(timeout: Duration) -> CredentialResult<LockScope> {
+    let mut held = held_lock().lock().expect("vault lock poisoned");
+    if held.is_some() {
+        return Ok(LockScope { acquired: false });
+    }
+    *held = Some(VaultLock::acquire(&get_database_path()?, timeout)?);
+    Ok(LockScope { acquired: true })
+}
+
+pub fn set_configured_database_path(path: PathBuf) {
+    *configured_database_path()
+        .lock()
+        .expect("database path lock poisoned") = Some(path);
+}
 
 pub fn get_database_path() -> CredentialResult<PathBuf> {
+    if let Some(path) = std::env::var_os(DB_PATH_ENV).filter(|path| !path.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    if let Some(path) = configured_database_path()
+        .lock()
+        .expect("database path lock poisoned")
+        .clone()
+    {
+        return Ok(path);
+    }
+
     let home_dir = dirs::home_dir().ok_or_else(|| {
         CredentialError::IoError(std::io::Error::new(
             std::io::ErrorKind::NotFound,
@@ -13,34 +212,556 @@ pub fn get_database_path() -> CredentialResult<PathBuf> {
     Ok(home_dir.join(".crab").join("credentials.json"))
 }
 
+// Owner-only access for the vault directory and database file on Unix.
+#[cfg(unix)]
+pub const PRIVATE_DIR_MODE: u32 = 0o700;
+#[cfg(unix)]
+pub const PRIVATE_FILE_MODE: u32 = 0o600;
+
+// The folder holding the database; a bare file name lives in the current
+// directory.
+pub fn database_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+// Folders crab creates are owner-only. One that already exists keeps its mode:
+// CRAB_DB_PATH and backup_dir may point into shared or synced folders.
+fn create_private_dir(dir: &Path) -> CredentialResult<()> {
+    if dir.is_dir() {
+        return Ok(());
+    }
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(PRIVATE_DIR_MODE);
+    }
+    builder.create(dir)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn warn_if_insecure(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Ok(metadata) = fs::metadata(path) {
+        let mode = metadata.permissions().mode() & 0o777;
+        if mode & !PRIVATE_FILE_MODE != 0 {
+            errln!(
+                "⚠️  {} is accessible by other users (mode {mode:o}). Run 'chmod 600' on it.",
+                path.display()
+            );
+        }
+    }
+}
+
 pub fn save_database(database: &CredentialDatabase) -> CredentialResult<()> {
+    ensure_writable()?;
     let path = get_database_path()?;
 
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-    }
+    create_private_dir(database_dir(&path))?;
+    let _lock = lock_vault(LOCK_TIMEOUT)?;
+
+    let purged = trash_cutoff(now_unix())
+        .filter(|cutoff| database.trash.iter().any(|t| t.deleted_at < *cutoff))
+        .map(|cutoff| {
+            let mut purged = database.clone();
+            purged.purge_trash(cutoff);
+            purged
+        });
+    let database = purged.as_ref().unwrap_or(database);
 
-    let json_data = serde_json::to_string_pretty(database)?;
+    let json_data = zeroize::Zeroizing::new(serde_json::to_string_pretty(database)?);
+    let key = crypto::master_key()?;
+    let encrypted = crypto::encrypt(&key, json_data.as_bytes())?;
 
-    fs::write(&path, json_data)?;
+    if verify_saves() {
+        write_verified(&path, &encrypted, &json_data, &key, |_| Ok(()))?;
+    } else {
+        write_atomically(&path, &encrypted)?;
+    }
+    remember_session(&key);
 
     Ok(())
 }
 
+// For bookkeeping writes nobody asked for, such as access counters: reloads
+// the vault under the lock and applies `update`, returning false instead of
+// prompting when the vault isn't already unlocked or waiting when another
+// process holds the lock.
+pub fn update_database_if_unlocked(
+    update: impl FnOnce(&mut CredentialDatabase) -> bool,
+) -> CredentialResult<bool> {
+    if is_read_only() || !crypto::master::is_unlocked() {
+        return Ok(false);
+    }
+    let _lock = match lock_vault(Duration::ZERO) {
+        Ok(lock) => lock,
+        Err(CredentialError::VaultLocked(_)) => return Ok(false),
+        Err(e) => return Err(e),
+    };
+
+    let mut database = load_database()?;
+    if !update(&mut database) {
+        return Ok(false);
+    }
+    save_database(&database)?;
+    Ok(true)
+}
+
+// Sessions are a convenience; failing to write one never fails the command.
+fn remember_session(key: &crypto::master::MasterKey) {
+    if let Err(e) = crypto::store_session(key) {
+        errln!("⚠️  Could not save the unlock session: {e}");
+    }
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".tmp");
+    path.with_file_name(file_name)
+}
+
+// Writes to a sibling temp file and renames it over `path`, so a crash or a full
+// disk never leaves a truncated database behind.
+fn write_atomically(path: &Path, data: &[u8]) -> CredentialResult<()> {
+    write_atomically_with(path, data, |_| Ok(()))
+}
+
+fn write_atomically_with(
+    path: &Path,
+    data: &[u8],
+    before_rename: impl FnOnce(&Path) -> std::io::Result<()>,
+) -> CredentialResult<()> {
+    let tmp_path = temp_path(path);
+
+    let result = (|| {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(PRIVATE_FILE_MODE);
+        }
+        let mut file = options.open(&tmp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        before_rename(&tmp_path)?;
+        fs::rename(&tmp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    Ok(result?)
+}
+
+// Writes `data`, then reads the file back and checks that it decrypts to
+// exactly `expected`. On a mismatch the previous file is put back (or the new
+// one removed if there was none) and the save is reported as failed.
+fn write_verified(
+    path: &Path,
+    data: &[u8],
+    expected: &str,
+    key: &crypto::master::MasterKey,
+    before_rename: impl FnOnce(&Path) -> std::io::Result<()>,
+) -> CredentialResult<()> {
+    let previous = match fs::read(path) {
+        Ok(bytes) => Some(bytes),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    write_atomically_with(path, data, before_rename)?;
+    if reads_back_as(path, expected, key) {
+        return Ok(());
+    }
+
+    match previous {
+        Some(previous) => write_atomically(path, &previous)?,
+        None => fs::remove_file(path)?,
+    }
+    Err(CredentialError::SaveVerificationFailed(path.to_path_buf()))
+}
+
+fn reads_back_as(path: &Path, expected: &str, key: &crypto::master::MasterKey) -> bool {
+    let Ok(written) = fs::read(path) else {
+        return false;
+    };
+    let Ok(plaintext) = crypto::decrypt(key, &written).map(zeroize::Zeroizing::new) else {
+        return false;
+    };
+    serde_json::from_slice::<CredentialDatabase>(&plaintext)
+        .and_then(|database| serde_json::to_string_pretty(&database))
+        .map(zeroize::Zeroizing::new)
+        .is_ok_and(|json| json.as_str() == expected)
+}
+
+pub fn init_database() -> CredentialResult<PathBuf> {
+    let path = get_database_path()?;
+
+    create_private_dir(database_dir(&path))?;
+
+    if !path.exists() {
+        save_database(&CredentialDatabase::new())?;
+    }
+
+    Ok(path)
+}
+
 pub fn load_database() -> CredentialResult<CredentialDatabase> {
     let path = get_database_path()?;
 
     if !path.exists() {
         return Ok(CredentialDatabase::new());
     }
+    // Readers that can't create the lock file (say, a read-only mount) still
+    // get their data; only contention with a writer is an error.
+    let _lock = if is_read_only() {
+        None
+    } else {
+        match lock_vault(LOCK_TIMEOUT) {
+            Ok(lock) => Some(lock),
+            Err(CredentialError::IoError(_)) if !hold_lock() => None,
+            Err(e) => return Err(e),
+        }
+    };
+
+    #[cfg(unix)]
+    warn_if_insecure(&path);
+
+    let data = fs::read(&path)?;
+
+    let (database, migrated) = decode_database(&data).map_err(|e| match e {
+        CredentialError::SerializationError(source) => {
+            CredentialError::DatabaseCorrupted { path, source }
+        }
+        other => other,
+    })?;
+    if migrated && is_read_only() {
+        errln!(
+            "ℹ️  Database upgraded to schema version {CURRENT_VERSION} in memory only (read-only)."
+        );
+    } else if migrated {
+        // Best effort: the upgraded data is usable even if it can't be written back.
+        match save_database(&database) {
+            Ok(()) => errln!("ℹ️  Database upgraded to schema version {CURRENT_VERSION}."),
+            Err(e) => errln!("⚠️  Could not save the upgraded database: {e}"),
+        }
+    } else if !crypto::is_encrypted(&data) && !is_read_only() {
+        offer_encryption(&database)?;
+    }
+
+    Ok(database)
+}
+
+pub fn parse_database(json_data: &[u8]) -> CredentialResult<(CredentialDatabase, bool)> {
+    let document: serde_json::Value = serde_json::from_slice(json_data)?;
+    let (document, migrated) = migrate::migrate(document)?;
+    let database: CredentialDatabase = serde_json::from_value(document)?;
+    Ok((database, migrated))
+}
+
+// Accepts both encrypted files and legacy plaintext JSON. The flag reports
+// whether the schema was upgraded while loading.
+fn decode_database(data: &[u8]) -> CredentialResult<(CredentialDatabase, bool)> {
+    if !crypto::is_encrypted(data) {
+        return parse_database(data);
+    }
+
+    let key = crypto::unlock_key(crypto::read_header(data)?)?;
+    let json_data = match crypto::decrypt(&key, data) {
+        Ok(json_data) => zeroize::Zeroizing::new(json_data),
+        Err(e) => {
+            crypto::forget_master_key();
+            let _ = crypto::clear_session();
+            return Err(e);
+        }
+    };
+    remember_session(&key);
+    if key.kdf.is_weaker_than_default() {
+        static WARNED: std::sync::Once = std::sync::Once::new();
+        WARNED.call_once(|| {
+            errln!("ℹ️  This vault uses weaker key-derivation settings than the current default; run 'crab rekey' to upgrade.")
+        });
+    }
+
+    parse_database(&json_data)
+}
+
+// Reads a database other than the live one without changing the unlocked key.
+pub fn load_database_from(path: &Path) -> CredentialResult<CredentialDatabase> {
+    let data = fs::read(path)?;
+    if !crypto::is_encrypted(&data) {
+        let (database, _) = parse_database(&data)?;
+        return Ok(database);
+    }
+
+    let label = path.display().to_string();
+    let key = crypto::master::unlock_other_key(crypto::read_header(&data)?, &label)?;
+    let json_data = zeroize::Zeroizing::new(crypto::decrypt(&key, &data)?);
+    let (database, _) = parse_database(&json_data)?;
+    Ok(database)
+}
+
+// Read-only check of the live file. Problems in the document itself are
+// returned as issues; failing to read or decrypt it is still an error.
+pub fn verify_database() -> CredentialResult<Vec<String>> {
+    let path = get_database_path()?;
+    if !path.exists() {
+        return Err(CredentialError::database_not_found());
+    }
+
+    let data = fs::read(&path)?;
+    if !crypto::is_encrypted(&data) {
+        return Ok(check_document(&data, now_unix()));
+    }
+
+    let key = crypto::unlock_key(crypto::read_header(&data)?)?;
+    let json_data = match crypto::decrypt(&key, &data) {
+        Ok(json_data) => zeroize::Zeroizing::new(json_data),
+        Err(e) => {
+            crypto::forget_master_key();
+            return Err(e);
+        }
+    };
+    Ok(check_document(&json_data, now_unix()))
+}
+
+fn check_document(json_data: &[u8], now: u64) -> Vec<String> {
+    let document: serde_json::Value = match serde_json::from_slice(json_data) {
+        Ok(document) => document,
+        Err(e) => return vec![format!("not valid JSON: {e}")],
+    };
+    let document = match migrate::migrate(document) {
+        Ok((document, _)) => document,
+        Err(e) => return vec![e.to_string()],
+    };
+    match serde_json::from_value::<CredentialDatabase>(document) {
+        Ok(database) => database.integrity_issues(now),
+        Err(e) => vec![format!("unexpected structure: {e}")],
+    }
+}
+
+// Re-asks for the master password before a secret is revealed, ignoring any
+// cached key or session. Plaintext databases have no password to check.
+pub fn confirm_master_password() -> CredentialResult<()> {
+    let data = fs::read(get_database_path()?)?;
+    if !crypto::is_encrypted(&data) {
+        return Ok(());
+    }
+
+    let key = crypto::master::prompt_key(
+        crypto::read_header(&data)?,
+        "Master Password (to reveal the secret)",
+    )?;
+    crypto::decrypt(&key, &data).map(|_| ())
+}
+
+// Re-encrypts the live database under a new master password.
+pub fn change_master_password(keep: usize) -> CredentialResult<()> {
+    let path = get_database_path()?;
+    if !path.exists() {
+        return Err(CredentialError::database_not_found());
+    }
+    let _lock = lock_vault(LOCK_TIMEOUT)?;
+
+    let data = fs::read(&path)?;
+    if !crypto::is_encrypted(&data) {
+        return Err(CredentialError::invalid_input(
+            "The database is not encrypted yet; run any command to set a master password",
+        ));
+    }
+    let current =
+        crypto::master::prompt_key(crypto::read_header(&data)?, "Current Master Password")?;
+    crypto::decrypt(&current, &data)?;
+
+    let new_key = crypto::master::prompt_new_master_key()?;
+    rekey_database(&path, &current, new_key, keep)
+}
+
+// Re-encrypts the live database under the same master password with a fresh
+// salt and the current default key-derivation costs. Returns the parameters
+// the vault used before.
+pub fn rekey_vault(keep: usize) -> CredentialResult<KdfParams> {
+    ensure_writable()?;
+    let path = get_database_path()?;
+    if !path.exists() {
+        return Err(CredentialError::database_not_found());
+    }
+    let _lock = lock_vault(LOCK_TIMEOUT)?;
+
+    let data = fs::read(&path)?;
+    if !crypto::is_encrypted(&data) {
+        return Err(CredentialError::invalid_input(
+            "The database is not encrypted yet; run any command to set a master password",
+        ));
+    }
+    let header = crypto::read_header(&data)?;
+    let (current, upgraded) = crypto::master::prompt_rekey(header)?;
+    rekey_database(&path, &current, upgraded, keep)?;
+    Ok(header.kdf)
+}
+
+// The backup is taken before anything is written, and the new file replaces the
+// old one in a single rename, so the vault is always readable with one of the
+// two passwords.
+fn rekey_database(
+    path: &Path,
+    current: &crypto::master::MasterKey,
+    new_key: crypto::master::MasterKey,
+    keep: usize,
+) -> CredentialResult<()> {
+    ensure_writable()?;
+    let data = fs::read(path)?;
+    let json_data = zeroize::Zeroizing::new(crypto::decrypt(current, &data)?);
+    let encrypted = crypto::encrypt(&new_key, &json_data)?;
+
+    backup_database(keep, false)?;
+    write_atomically(path, &encrypted)?;
+
+    let _ = crypto::clear_session();
+    remember_session(&new_key);
+    crypto::master::cache_master_key(new_key);
+    Ok(())
+}
+
+// Portable backups hold the database JSON encrypted under their own
+// passphrase, so they can be restored without the live vault's key.
+fn seal_archive(
+    database: &CredentialDatabase,
+    key: &crypto::master::MasterKey,
+) -> CredentialResult<Vec<u8>> {
+    let json_data = zeroize::Zeroizing::new(serde_json::to_string_pretty(database)?);
+    crypto::cipher::encrypt_archive(key, json_data.as_bytes())
+}
+
+fn open_archive(
+    data: &[u8],
+    key: &crypto::master::MasterKey,
+) -> CredentialResult<CredentialDatabase> {
+    let json_data = zeroize::Zeroizing::new(crypto::cipher::decrypt_archive(key, data)?);
+    let (database, _) = parse_database(&json_data)?;
+    Ok(database)
+}
+
+// Makes sure the next save encrypts with the live vault's existing key, checking
+// the entered password against the file instead of trusting it blindly.
+fn unlock_live_database(path: &Path) -> CredentialResult<()> {
+    let data = fs::read(path)?;
+    if !crypto::is_encrypted(&data) {
+        return Ok(());
+    }
+
+    let key = crypto::unlock_key(crypto::read_header(&data)?)?;
+    if let Err(e) = crypto::decrypt(&key, &data) {
+        crypto::forget_master_key();
+        return Err(e);
+    }
+    Ok(())
+}
 
-    let json_data = fs::read_to_string(&path)?;
+// Replaces the live database with `backup` after checking that it decodes, and
+// keeps a safety backup of the current file first.
+pub fn restore_database(backup: &Path, keep: usize) -> CredentialResult<CredentialDatabase> {
+    ensure_writable()?;
+    let data = fs::read(backup)?;
+    let archived = crypto::cipher::is_archive(&data);
+    let decoded = if archived {
+        crypto::master::unlock_backup_key(crypto::cipher::read_archive_header(&data)?)
+            .and_then(|key| open_archive(&data, &key))
+    } else {
+        decode_database(&data).map(|(database, _)| database)
+    };
+    let database = decoded.map_err(|e| match e {
+        CredentialError::SerializationError(err) => CredentialError::invalid_input(&format!(
+            "'{}' is not a valid crab database: {err}",
+            backup.display()
+        )),
+        other => other,
+    })?;
 
-    let database: CredentialDatabase = serde_json::from_str(&json_data)?;
+    let path = get_database_path()?;
+    if path.exists() {
+        if archived {
+            unlock_live_database(&path)?;
+        }
+        backup_database(keep, false)?;
+    } else {
+        create_private_dir(database_dir(&path))?;
+    }
+    let _lock = lock_vault(LOCK_TIMEOUT)?;
 
+    if archived {
+        save_database(&database)?;
+    } else {
+        write_atomically(&path, &data)?;
+    }
     Ok(database)
 }
 
+// Renames an unreadable database to `<name>.corrupt` (or `.corrupt.N` if that
+// is taken) so a fresh one can be created without losing the original.
+pub fn quarantine_database(path: &Path) -> CredentialResult<PathBuf> {
+    ensure_writable()?;
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".corrupt");
+    let first = path.with_file_name(&file_name);
+
+    let target = std::iter::once(first.clone())
+        .chain((1..).map(|n| {
+            let mut numbered = file_name.clone();
+            numbered.push(format!(".{n}"));
+            path.with_file_name(numbered)
+        }))
+        .find(|candidate| !candidate.exists())
+        .expect("an unused name always exists");
+    fs::rename(path, &target)?;
+    Ok(target)
+}
+
+pub fn latest_backup() -> Option<PathBuf> {
+    let dir = backup_dir().ok()?;
+    list_backups(&dir)
+        .ok()?
+        .into_iter()
+        .next()
+        .map(|backup| backup.path)
+}
+
+pub fn backup_dir() -> CredentialResult<PathBuf> {
+    if let Some(dir) = configured_backup_dir()
+        .lock()
+        .expect("backup dir lock poisoned")
+        .clone()
+    {
+        return Ok(dir);
+    }
+    Ok(database_dir(&get_database_path()?).to_path_buf())
+}
+
+// Legacy plaintext databases have no salt header. They stay readable, and any
+// save encrypts them, but we offer to do it right away.
+fn offer_encryption(database: &CredentialDatabase) -> CredentialResult<()> {
+    errln!("⚠️  Your credential database is stored in plaintext.");
+
+    let encrypt_now = dialoguer::Confirm::new()
+        .with_prompt("Encrypt it with a master password now?")
+        .default(true)
+        .interact()
+        .unwrap_or(false);
+
+    if encrypt_now {
+        save_database(database)?;
+        outln!("🔒 Database encrypted.");
+    }
+    Ok(())
+}
+
 pub fn database_exists() -> bool {
     if let Ok(path) = get_database_path() {
         path.exists()
@@ -50,11 +771,13 @@ pub fn database_exists() -> bool {
 }
 
 pub fn delete_database() -> CredentialResult<()> {
+    ensure_writable()?;
     let path = get_database_path()?;
 
     if path.exists() {
+        let _lock = lock_vault(LOCK_TIMEOUT)?;
         fs::remove_file(&path)?;
-        println!("✅ Database file deleted: {}", path.display());
+        outln!("✅ Database file deleted: {}", path.display());
     } else {
         return Err(CredentialError::database_not_found());
     }
@@ -68,28 +791,164 @@ pub fn get_database_info() -> CredentialResult<std::fs::Metadata> {
     Ok(metadata)
 }
 
-pub fn backup_database() -> CredentialResult<()> {
+pub const DEFAULT_BACKUP_KEEP: usize = 10;
+
+const BACKUP_PREFIX: &str = "credentials_";
+const BACKUP_SUFFIX: &str = ".json.bak";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupFile {
+    pub path: PathBuf,
+    pub timestamp: u64,
+    sequence: u32,
+}
+
+// "credentials_<unix time>.json.bak", with "-<n>" before the suffix for a
+// further backup taken within the same second.
+fn parse_backup_name(file_name: &str) -> Option<(u64, u32)> {
+    let stem = file_name
+        .strip_prefix(BACKUP_PREFIX)?
+        .strip_suffix(BACKUP_SUFFIX)?;
+    let (timestamp, sequence) = match stem.split_once('-') {
+        Some((timestamp, sequence)) => (timestamp, sequence.parse().ok()?),
+        None => (stem, 0),
+    };
+    Some((timestamp.parse().ok()?, sequence))
+}
+
+pub fn parse_backup_timestamp(file_name: &str) -> Option<u64> {
+    parse_backup_name(file_name).map(|(timestamp, _)| timestamp)
+}
+
+fn backup_file_name(timestamp: u64, sequence: u32) -> String {
+    if sequence == 0 {
+        format!("{BACKUP_PREFIX}{timestamp}{BACKUP_SUFFIX}")
+    } else {
+        format!("{BACKUP_PREFIX}{timestamp}-{sequence}{BACKUP_SUFFIX}")
+    }
+}
+
+// Claims the first free backup name for `timestamp`, so a second backup in
+// the same second doesn't overwrite the first.
+fn create_backup_file(dir: &Path, timestamp: u64) -> CredentialResult<(PathBuf, fs::File)> {
+    let mut sequence = 0;
+    loop {
+        let path = dir.join(backup_file_name(timestamp, sequence));
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(PRIVATE_FILE_MODE);
+        }
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => sequence += 1,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+// Backups among `paths` (e.g. a directory listing), newest first. Files that
+// don't follow the backup naming scheme are ignored.
+pub fn backups_from_listing(paths: impl IntoIterator<Item = PathBuf>) -> Vec<BackupFile> {
+    let mut backups: Vec<BackupFile> = paths
+        .into_iter()
+        .filter_map(|path| {
+            let (timestamp, sequence) = parse_backup_name(path.file_name()?.to_str()?)?;
+            Some(BackupFile {
+                path,
+                timestamp,
+                sequence,
+            })
+        })
+        .collect();
+    backups.sort_by_key(|backup| std::cmp::Reverse((backup.timestamp, backup.sequence)));
+    backups
+}
+
+// Backups in `dir`, newest first.
+pub fn list_backups(dir: &Path) -> CredentialResult<Vec<BackupFile>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let paths = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path());
+    Ok(backups_from_listing(paths))
+}
+
+// Backups to delete: everything past the `keep` newest, plus anything older
+// than `older_than` at time `now`. Either rule alone is enough to select a file.
+pub fn select_backups_to_purge(
+    paths: impl IntoIterator<Item = PathBuf>,
+    keep: Option<usize>,
+    older_than: Option<std::time::Duration>,
+    now: u64,
+) -> Vec<BackupFile> {
+    let cutoff = older_than.map(|age| now.saturating_sub(age.as_secs()));
+    backups_from_listing(paths)
+        .into_iter()
+        .enumerate()
+        .filter(|(i, backup)| {
+            keep.is_some_and(|keep| *i >= keep)
+                || cutoff.is_some_and(|cutoff| backup.timestamp < cutoff)
+        })
+        .map(|(_, backup)| backup)
+        .collect()
+}
+
+// Deletes all but the `keep` newest backups in `dir` and returns what was removed.
+pub fn prune_backups(dir: &Path, keep: usize) -> CredentialResult<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    for backup in list_backups(dir)?.into_iter().skip(keep) {
+        fs::remove_file(&backup.path)?;
+        removed.push(backup.path);
+    }
+    Ok(removed)
+}
+
+pub fn backup_database(keep: usize, encrypt: bool) -> CredentialResult<()> {
+    backup_database_to(&backup_dir()?, keep, encrypt)
+}
+
+// `keep == 0` disables pruning. With `encrypt`, the backup is a portable
+// archive under a separate passphrase rather than a copy of the live file.
+// `dir` is created if needed, so it can point at a freshly mounted drive.
+pub fn backup_database_to(dir: &Path, keep: usize, encrypt: bool) -> CredentialResult<()> {
     let path = get_database_path()?;
 
     if !path.exists() {
         return Err(CredentialError::database_not_found());
     }
 
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| {
-            CredentialError::IoError(std::io::Error::other(format!(
-                "Failed to get system time: {e}"
-            )))
-        })?
-        .as_secs();
+    let data = if encrypt {
+        let (database, _) = decode_database(&fs::read(&path)?)?;
+        let key = crypto::master::new_backup_key()?;
+        seal_archive(&database, &key)?
+    } else {
+        fs::read(&path)?
+    };
 
-    let backup_filename = format!("credentials_{timestamp}.json.bak");
-    let backup_path = path.with_file_name(backup_filename);
+    create_private_dir(dir)?;
+    let (backup_path, mut file) = create_backup_file(dir, now_unix())?;
+    if let Err(e) = file.write_all(&data).and_then(|_| file.sync_all()) {
+        let _ = fs::remove_file(&backup_path);
+        return Err(e.into());
+    }
 
-    fs::copy(&path, &backup_path)?;
+    if encrypt {
+        outln!("🔒 Encrypted backup created: {}", backup_path.display());
+    } else {
+        outln!("✅ Database backup created: {}", backup_path.display());
+    }
 
-    println!("✅ Database backup created: {}", backup_path.display());
+    if keep > 0 {
+        for removed in prune_backups(dir, keep)? {
+            outln!("🧹 Removed old backup: {}", removed.display());
+        }
+    }
     Ok(())
 }
 
@@ -97,7 +956,6 @@ pub fn backup_database() -> CredentialResult<()> {
 mod tests {
     use super::*;
     use std::path::Path;
-    use std::sync::{Mutex, OnceLock};
 
     fn home_env_lock() -> std::sync::MutexGuard<'static, ()> {
         static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
@@ -109,23 +967,32 @@ mod tests {
     struct HomeGuard {
         original_home: Option<String>,
         original_userprofile: Option<String>,
+        original_db_path: Option<String>,
     }
 
     impl HomeGuard {
         fn new(temp_home: &Path) -> Self {
             let original_home = std::env::var("HOME").ok();
             let original_userprofile = std::env::var("USERPROFILE").ok();
+            let original_db_path = std::env::var(DB_PATH_ENV).ok();
             std::env::set_var("HOME", temp_home);
             std::env::set_var("USERPROFILE", temp_home);
+            std::env::remove_var(DB_PATH_ENV);
+            crate::crypto::master::cache_master_key(test_master_key());
             Self {
                 original_home,
                 original_userprofile,
+                original_db_path,
             }
         }
     }
 
     impl Drop for HomeGuard {
         fn drop(&mut self) {
+            match &self.original_db_path {
+                Some(value) => std::env::set_var(DB_PATH_ENV, value),
+                None => std::env::remove_var(DB_PATH_ENV),
+            }
             match &self.original_home {
                 Some(value) => std::env::set_var("HOME", value),
                 None => std::env::remove_var("HOME"),
@@ -137,6 +1004,12 @@ mod tests {
         }
     }
 
+    fn test_master_key() -> crate::crypto::master::MasterKey {
+        static KEY: OnceLock<crate::crypto::master::MasterKey> = OnceLock::new();
+        KEY.get_or_init(|| crate::crypto::master::MasterKey::derive("test-password", [0u8; 16]))
+            .clone()
+    }
+
     fn sample_database() -> CredentialDatabase {
         let mut database = CredentialDatabase::new();
         let entry = crate::model::CredentialEntry::new(
@@ -149,7 +1022,31 @@ mod tests {
     }
 
     #[test]
-    fn save_and_load_database_round_trip() {
+    fn database_path_defaults_to_home() {
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+
+        let path = get_database_path().expect("path should resolve");
+
+        assert_eq!(path, temp_dir.path().join(".crab").join("credentials.json"));
+    }
+
+    #[test]
+    fn database_path_env_var_overrides_default() {
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        let custom = temp_dir.path().join("synced").join("vault.json");
+        std::env::set_var(DB_PATH_ENV, &custom);
+
+        let path = get_database_path().expect("path should resolve");
+
+        assert_eq!(path, custom);
+    }
+
+    #[test]
+    fn save_and_load_database_round_trip() {
         let _lock = home_env_lock();
         let temp_dir = tempfile::tempdir().expect("tempdir");
         let _guard = HomeGuard::new(temp_dir.path());
@@ -159,10 +1056,118 @@ mod tests {
         let loaded = load_database().expect("load should succeed");
 
         assert_eq!(loaded.len(), 1);
-        let entry = loaded.find_entry("service").expect("entry should exist");
+        let entry = loaded
+            .find_entry("service", None)
+            .expect("entry should exist");
         assert_eq!(entry.account, "account");
     }
 
+    #[test]
+    fn saved_database_is_encrypted_on_disk() {
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+
+        save_database(&sample_database()).expect("save should succeed");
+        let raw = std::fs::read(get_database_path().expect("path")).expect("read");
+
+        assert!(crate::crypto::is_encrypted(&raw));
+        assert!(!String::from_utf8_lossy(&raw).contains("secret"));
+    }
+
+    #[test]
+    fn failed_write_leaves_original_file_intact() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let path = temp_dir.path().join("credentials.json");
+        std::fs::write(&path, b"original").expect("write");
+
+        let result = write_atomically_with(&path, b"replacement", |_| {
+            Err(std::io::Error::other("simulated crash"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read(&path).expect("read"), b"original");
+        assert!(!temp_path(&path).exists());
+    }
+
+    #[test]
+    fn verified_write_accepts_a_faithful_copy() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let path = temp_dir.path().join("credentials.json");
+        let key = test_master_key();
+        let json = serde_json::to_string_pretty(&sample_database()).expect("json");
+        let encrypted = crate::crypto::encrypt(&key, json.as_bytes()).expect("encrypt");
+
+        write_verified(&path, &encrypted, &json, &key, |_| Ok(())).expect("verified write");
+
+        assert_eq!(std::fs::read(&path).expect("read"), encrypted);
+    }
+
+    #[test]
+    fn verified_write_restores_previous_file_on_mismatch() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let path = temp_dir.path().join("credentials.json");
+        std::fs::write(&path, b"original").expect("write");
+        let key = test_master_key();
+        let json = serde_json::to_string_pretty(&sample_database()).expect("json");
+        let encrypted = crate::crypto::encrypt(&key, json.as_bytes()).expect("encrypt");
+
+        // Simulate the disk handing back something other than what was written.
+        let result = write_verified(&path, &encrypted, &json, &key, |tmp| {
+            std::fs::write(tmp, b"bit rot")
+        });
+
+        assert!(matches!(
+            result,
+            Err(CredentialError::SaveVerificationFailed(_))
+        ));
+        assert_eq!(std::fs::read(&path).expect("read"), b"original");
+    }
+
+    #[test]
+    fn atomic_write_replaces_file() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let path = temp_dir.path().join("credentials.json");
+        std::fs::write(&path, b"original").expect("write");
+
+        write_atomically(&path, b"replacement").expect("write should succeed");
+
+        assert_eq!(std::fs::read(&path).expect("read"), b"replacement");
+        assert!(!temp_path(&path).exists());
+    }
+
+    #[test]
+    fn load_database_migrates_and_rewrites_old_schema() {
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        let mut database = sample_database();
+        database.version = "1.0".to_string();
+        save_database(&database).expect("save should succeed");
+
+        let loaded = load_database().expect("load should succeed");
+
+        assert_eq!(loaded.version, CURRENT_VERSION);
+        let raw = std::fs::read(get_database_path().expect("path")).expect("read");
+        let (reloaded, migrated) = decode_database(&raw).expect("decode");
+        assert!(!migrated, "the upgraded file should have been written back");
+        assert_eq!(reloaded.version, CURRENT_VERSION);
+    }
+
+    #[test]
+    fn load_database_from_reads_another_file() {
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        save_database(&sample_database()).expect("save should succeed");
+        let other = temp_dir.path().join("other.json");
+        std::fs::copy(get_database_path().expect("path"), &other).expect("copy");
+
+        let loaded = load_database_from(&other).expect("load should succeed");
+
+        assert!(loaded.find_entry("service", None).is_some());
+    }
+
     #[test]
     fn database_exists_and_delete_behave_as_expected() {
         let _lock = home_env_lock();
@@ -179,6 +1184,96 @@ mod tests {
         assert!(!database_exists());
     }
 
+    #[test]
+    fn init_database_creates_empty_database() {
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+
+        let path = init_database().expect("init should succeed");
+
+        assert!(path.exists());
+        let loaded = load_database().expect("load should succeed");
+        assert_eq!(loaded.len(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn init_database_restricts_directory_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+
+        let path = init_database().expect("init should succeed");
+        let parent = path.parent().expect("parent dir");
+        let mode = std::fs::metadata(parent)
+            .expect("metadata")
+            .permissions()
+            .mode();
+
+        assert_eq!(mode & 0o777, 0o700);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn save_database_restricts_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+
+        save_database(&sample_database()).expect("save should succeed");
+        let path = get_database_path().expect("path");
+        let file_mode = std::fs::metadata(&path)
+            .expect("metadata")
+            .permissions()
+            .mode();
+        let dir_mode = std::fs::metadata(path.parent().expect("parent"))
+            .expect("metadata")
+            .permissions()
+            .mode();
+
+        assert_eq!(file_mode & 0o777, 0o600);
+        assert_eq!(dir_mode & 0o777, 0o700);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn save_leaves_an_existing_custom_folder_mode_alone() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        let shared = temp_dir.path().join("shared");
+        fs::create_dir(&shared).expect("mkdir");
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o755)).expect("chmod");
+        std::env::set_var(DB_PATH_ENV, shared.join("vault.json"));
+
+        save_database(&sample_database()).expect("save should succeed");
+        let mode = fs::metadata(&shared)
+            .expect("metadata")
+            .permissions()
+            .mode();
+
+        assert_eq!(mode & 0o777, 0o755);
+        assert!(shared.join("vault.json").exists());
+    }
+
+    #[test]
+    fn bare_file_name_lives_in_the_current_directory() {
+        assert_eq!(database_dir(Path::new("vault.json")), Path::new("."));
+        assert_eq!(
+            database_dir(Path::new("/tmp/vault.json")),
+            Path::new("/tmp")
+        );
+        create_private_dir(database_dir(Path::new("vault.json")))
+            .expect("the current directory needs no creating");
+    }
+
     #[test]
     fn backup_database_creates_bak_file() {
         let _lock = home_env_lock();
@@ -187,27 +1282,499 @@ mod tests {
 
         let database = sample_database();
         save_database(&database).expect("save should succeed");
-        backup_database().expect("backup should succeed");
+        backup_database(DEFAULT_BACKUP_KEEP, false).expect("backup should succeed");
 
         let database_path = get_database_path().expect("path should exist");
         let parent = database_path.parent().expect("parent dir");
         let backup_count = std::fs::read_dir(parent)
             .expect("read dir")
             .filter_map(Result::ok)
-            .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "bak"))
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "bak"))
             .count();
 
         assert_eq!(backup_count, 1);
     }
 
+    #[test]
+    fn backup_database_to_creates_the_target_dir() {
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        save_database(&sample_database()).expect("save should succeed");
+        let target = temp_dir.path().join("external").join("crab-backups");
+
+        backup_database_to(&target, DEFAULT_BACKUP_KEEP, false).expect("backup should succeed");
+
+        assert_eq!(list_backups(&target).expect("list").len(), 1);
+        assert!(list_backups(&backup_dir().expect("dir"))
+            .expect("list")
+            .is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn backup_database_to_only_restricts_folders_it_creates() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        save_database(&sample_database()).expect("save should succeed");
+        let mounted = temp_dir.path().join("usb");
+        fs::create_dir(&mounted).expect("mkdir");
+        fs::set_permissions(&mounted, fs::Permissions::from_mode(0o755)).expect("chmod");
+        let created = temp_dir.path().join("new-backups");
+
+        backup_database_to(&mounted, DEFAULT_BACKUP_KEEP, false).expect("backup should succeed");
+        backup_database_to(&created, DEFAULT_BACKUP_KEEP, false).expect("backup should succeed");
+        let mode = |dir: &Path| fs::metadata(dir).expect("metadata").permissions().mode() & 0o777;
+
+        assert_eq!(mode(&mounted), 0o755);
+        assert_eq!(mode(&created), 0o700);
+    }
+
+    #[test]
+    fn parse_backup_timestamp_reads_embedded_value() {
+        assert_eq!(
+            parse_backup_timestamp("credentials_1704067200.json.bak"),
+            Some(1704067200)
+        );
+        assert_eq!(
+            parse_backup_timestamp("credentials_1704067200-2.json.bak"),
+            Some(1704067200)
+        );
+        assert_eq!(parse_backup_timestamp("credentials.json"), None);
+        assert_eq!(parse_backup_timestamp("credentials_abc.json.bak"), None);
+        assert_eq!(parse_backup_timestamp("credentials_1-x.json.bak"), None);
+    }
+
+    #[test]
+    fn backups_within_one_second_get_their_own_files() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        for _ in 0..3 {
+            create_backup_file(temp_dir.path(), 42).expect("create");
+        }
+        fs::write(temp_dir.path().join(backup_file_name(41, 0)), b"older").expect("write");
+
+        let names: Vec<String> = list_backups(temp_dir.path())
+            .expect("list")
+            .iter()
+            .map(|backup| {
+                backup
+                    .path
+                    .file_name()
+                    .expect("file name")
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+
+        assert_eq!(
+            names,
+            vec![
+                "credentials_42-2.json.bak",
+                "credentials_42-1.json.bak",
+                "credentials_42.json.bak",
+                "credentials_41.json.bak",
+            ]
+        );
+    }
+
+    #[test]
+    fn prune_backups_keeps_newest() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        for timestamp in [100, 400, 200, 300, 500] {
+            let name = format!("credentials_{timestamp}.json.bak");
+            std::fs::write(temp_dir.path().join(name), b"backup").expect("write");
+        }
+        std::fs::write(temp_dir.path().join("credentials.json"), b"live").expect("write");
+
+        let removed = prune_backups(temp_dir.path(), 2).expect("prune should succeed");
+
+        assert_eq!(removed.len(), 3);
+        let remaining: Vec<u64> = list_backups(temp_dir.path())
+            .expect("list")
+            .iter()
+            .map(|backup| backup.timestamp)
+            .collect();
+        assert_eq!(remaining, vec![500, 400]);
+        assert!(temp_dir.path().join("credentials.json").exists());
+    }
+
+    #[test]
+    fn restore_database_replaces_live_file() {
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        let backup_path = temp_dir.path().join("credentials_1.json.bak");
+
+        save_database(&sample_database()).expect("save should succeed");
+        std::fs::copy(get_database_path().expect("path"), &backup_path).expect("copy");
+        save_database(&CredentialDatabase::new()).expect("save should succeed");
+
+        restore_database(&backup_path, 0).expect("restore should succeed");
+
+        let loaded = load_database().expect("load should succeed");
+        assert!(loaded.find_entry("service", None).is_some());
+        let backups = list_backups(&backup_dir().expect("dir")).expect("list");
+        assert_eq!(backups.len(), 1, "a safety backup should be created");
+    }
+
+    #[test]
+    fn restore_database_rejects_invalid_file() {
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        save_database(&sample_database()).expect("save should succeed");
+        let garbage = temp_dir.path().join("garbage.json.bak");
+        std::fs::write(&garbage, b"not json").expect("write");
+
+        let result = restore_database(&garbage, 0);
+
+        assert!(matches!(result, Err(CredentialError::InvalidInput(_))));
+        let loaded = load_database().expect("live database should be untouched");
+        assert!(loaded.find_entry("service", None).is_some());
+    }
+
     #[test]
     fn backup_database_errors_when_missing() {
         let _lock = home_env_lock();
         let temp_dir = tempfile::tempdir().expect("tempdir");
         let _guard = HomeGuard::new(temp_dir.path());
 
-        let result = backup_database();
+        let result = backup_database(DEFAULT_BACKUP_KEEP, false);
 
         assert!(matches!(result, Err(CredentialError::DatabaseNotFound)));
     }
+
+    #[test]
+    fn archive_opens_only_with_its_own_passphrase() {
+        let backup_key = crate::crypto::master::MasterKey::derive(
+            "backup passphrase",
+            crate::crypto::kdf::generate_salt(),
+        );
+        let archive = seal_archive(&sample_database(), &backup_key).expect("seal");
+
+        assert!(crate::crypto::cipher::is_archive(&archive));
+        assert!(!crate::crypto::is_encrypted(&archive));
+        let database = open_archive(&archive, &backup_key).expect("open");
+        assert!(database.find_entry("service", None).is_some());
+
+        let wrong_key = crate::crypto::master::MasterKey::derive("nope", backup_key.salt);
+        assert!(matches!(
+            open_archive(&archive, &wrong_key),
+            Err(CredentialError::DecryptionFailed)
+        ));
+    }
+
+    #[test]
+    fn select_backups_to_purge_combines_count_and_age() {
+        let listing: Vec<PathBuf> = [
+            "credentials_100.json.bak",
+            "credentials_400.json.bak",
+            "credentials_300.json.bak",
+            "credentials_200.json.bak",
+            "credentials.json",
+            "notes.txt",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        let timestamps = |selected: Vec<BackupFile>| -> Vec<u64> {
+            selected.iter().map(|backup| backup.timestamp).collect()
+        };
+
+        let by_count = select_backups_to_purge(listing.clone(), Some(2), None, 1_000);
+        assert_eq!(timestamps(by_count), vec![200, 100]);
+
+        let by_age = select_backups_to_purge(
+            listing.clone(),
+            None,
+            Some(std::time::Duration::from_secs(750)),
+            1_000,
+        );
+        assert_eq!(timestamps(by_age), vec![200, 100]);
+
+        let either = select_backups_to_purge(
+            listing.clone(),
+            Some(3),
+            Some(std::time::Duration::from_secs(750)),
+            1_000,
+        );
+        assert_eq!(timestamps(either), vec![200, 100]);
+
+        assert!(select_backups_to_purge(listing, None, None, 1_000).is_empty());
+    }
+
+    #[test]
+    fn check_document_reports_parse_and_version_problems() {
+        assert!(check_document(b"{\"entries\": [", 0)[0].starts_with("not valid JSON"));
+        assert!(
+            check_document(br#"{"entries": [], "version": "99.0"}"#, 0)[0]
+                .contains("not supported")
+        );
+        assert!(
+            check_document(br#"{"entries": {}, "version": "1.1"}"#, 0)[0]
+                .starts_with("unexpected structure")
+        );
+    }
+
+    #[test]
+    fn verify_database_accepts_a_saved_database() {
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        save_database(&sample_database()).expect("save should succeed");
+
+        let issues = verify_database().expect("verify should succeed");
+
+        assert!(issues.is_empty(), "{issues:?}");
+    }
+
+    #[test]
+    fn save_database_purges_expired_trash() {
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        let mut database = sample_database();
+        database.add_entry(crate::model::CredentialEntry::new(
+            "recent".to_string(),
+            "account".to_string(),
+            "secret".to_string(),
+        ));
+        database.trash_entry("service", None, 1);
+        database.trash_entry("recent", None, now_unix());
+
+        save_database(&database).expect("save should succeed");
+
+        let loaded = load_database().expect("load should succeed");
+        assert_eq!(loaded.trash.len(), 1);
+        assert_eq!(loaded.trash[0].entry.service, "recent");
+    }
+
+    #[test]
+    fn rekey_database_switches_to_the_new_password() {
+        use crate::crypto::master::MasterKey;
+
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        let old_key = MasterKey::derive("old password", crate::crypto::kdf::generate_salt());
+        crate::crypto::master::cache_master_key(old_key.clone());
+        save_database(&sample_database()).expect("save should succeed");
+        let path = get_database_path().expect("path");
+
+        let new_key = MasterKey::derive("new password", crate::crypto::kdf::generate_salt());
+        rekey_database(&path, &old_key, new_key, 0).expect("rekey should succeed");
+
+        let data = std::fs::read(&path).expect("read");
+        let salt = crate::crypto::read_header(&data).expect("header").salt;
+        assert_ne!(salt, old_key.salt);
+        let json = crate::crypto::decrypt(&MasterKey::derive("new password", salt), &data)
+            .expect("new password should decrypt");
+        let (database, _) = parse_database(&json).expect("parse");
+        assert!(database.find_entry("service", None).is_some());
+        assert!(matches!(
+            crate::crypto::decrypt(&MasterKey::derive("old password", salt), &data),
+            Err(CredentialError::DecryptionFailed)
+        ));
+
+        let backups = list_backups(&backup_dir().expect("dir")).expect("list");
+        assert_eq!(backups.len(), 1, "a backup should be taken first");
+    }
+
+    #[test]
+    fn rekey_database_with_wrong_current_key_changes_nothing() {
+        use crate::crypto::master::MasterKey;
+
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        crate::crypto::master::cache_master_key(test_master_key());
+        save_database(&sample_database()).expect("save should succeed");
+        let path = get_database_path().expect("path");
+        let before = std::fs::read(&path).expect("read");
+
+        let wrong = MasterKey::derive("wrong", test_master_key().salt);
+        let new_key = MasterKey::derive("new", crate::crypto::kdf::generate_salt());
+        let result = rekey_database(&path, &wrong, new_key, 0);
+
+        assert!(matches!(result, Err(CredentialError::DecryptionFailed)));
+        assert_eq!(std::fs::read(&path).expect("read"), before);
+    }
+
+    #[test]
+    fn load_database_reports_corruption() {
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        let path = get_database_path().expect("path");
+        std::fs::create_dir_all(path.parent().expect("parent")).expect("mkdir");
+        std::fs::write(&path, b"{\"entries\": [ garbage").expect("write");
+
+        let result = load_database();
+
+        match result {
+            Err(CredentialError::DatabaseCorrupted { path: reported, .. }) => {
+                assert_eq!(reported, path)
+            }
+            other => panic!("expected DatabaseCorrupted, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn quarantine_database_keeps_earlier_copies() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let path = temp_dir.path().join("credentials.json");
+
+        std::fs::write(&path, b"first").expect("write");
+        let first = quarantine_database(&path).expect("quarantine");
+        std::fs::write(&path, b"second").expect("write");
+        let second = quarantine_database(&path).expect("quarantine");
+
+        assert_eq!(first, temp_dir.path().join("credentials.json.corrupt"));
+        assert_eq!(second, temp_dir.path().join("credentials.json.corrupt.1"));
+        assert!(!path.exists());
+        assert_eq!(std::fs::read(&first).expect("read"), b"first");
+    }
+
+    #[test]
+    fn master_password_env_unlocks_the_database() {
+        use crate::crypto::master::{forget_master_key, MASTER_PASSWORD_ENV};
+
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        save_database(&sample_database()).expect("save");
+
+        forget_master_key();
+        std::env::set_var(MASTER_PASSWORD_ENV, "wrong-password");
+        let wrong = load_database();
+        forget_master_key();
+        std::env::set_var(MASTER_PASSWORD_ENV, "test-password");
+        let right = load_database();
+        std::env::remove_var(MASTER_PASSWORD_ENV);
+
+        assert!(matches!(wrong, Err(CredentialError::DecryptionFailed)));
+        assert_eq!(right.expect("load").len(), 1);
+    }
+
+    #[test]
+    fn read_only_refuses_writes_and_leaves_the_file_alone() {
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        save_database(&sample_database()).expect("save should succeed");
+        let path = get_database_path().expect("path");
+        let before = std::fs::read(&path).expect("read");
+
+        set_read_only(true);
+        let save = save_database(&CredentialDatabase::new());
+        let delete = delete_database();
+        set_read_only(false);
+        std::env::set_var(READ_ONLY_ENV, "1");
+        let env_save = save_database(&CredentialDatabase::new());
+        let loaded = load_database();
+        std::env::remove_var(READ_ONLY_ENV);
+
+        assert!(matches!(save, Err(CredentialError::ReadOnly)));
+        assert!(matches!(delete, Err(CredentialError::ReadOnly)));
+        assert!(matches!(env_save, Err(CredentialError::ReadOnly)));
+        assert_eq!(loaded.expect("reads still work").len(), 1);
+        assert_eq!(std::fs::read(&path).expect("read"), before);
+    }
+
+    #[test]
+    fn rekey_vault_upgrades_weak_parameters_and_keeps_the_password() {
+        use crate::crypto::master::{MasterKey, MASTER_PASSWORD_ENV};
+
+        let _lock = home_env_lock();
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let _guard = HomeGuard::new(temp_dir.path());
+        let weak = KdfParams {
+            memory_kib: 1024,
+            time_cost: 1,
+            ..KdfParams::default()
+        };
+        let old_key = MasterKey::derive_with("pw", crate::crypto::kdf::generate_salt(), weak)
+            .expect("derive");
+        crate::crypto::master::cache_master_key(old_key.clone());
+        save_database(&sample_database()).expect("save should succeed");
+        let path = get_database_path().expect("path");
+        assert_eq!(
+            crate::crypto::read_header(&std::fs::read(&path).expect("read"))
+                .expect("header")
+                .kdf,
+            weak
+        );
+
+        std::env::set_var(MASTER_PASSWORD_ENV, "pw");
+        let replaced = rekey_vault(0);
+        std::env::remove_var(MASTER_PASSWORD_ENV);
+
+        assert_eq!(replaced.expect("rekey should succeed"), weak);
+        let data = std::fs::read(&path).expect("read");
+        let header = crate::crypto::read_header(&data).expect("header");
+        assert_eq!(header.kdf, KdfParams::default());
+        assert_ne!(header.salt, old_key.salt);
+        let key = MasterKey::derive_for("pw", &header).expect("derive");
+        let json = crate::crypto::decrypt(&key, &data).expect("same password should decrypt");
+        let (database, _) = parse_database(&json).expect("parse");
+        assert!(database.find_entry("service", None).is_some());
+    }
+
+    #[test]
+    fn vault_lock_serializes_concurrent_writers() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let path = temp_dir.path().join("credentials.json");
+        std::fs::write(
+            &path,
+            serde_json::to_vec(&CredentialDatabase::new()).expect("json"),
+        )
+        .expect("write");
+
+        let writers: Vec<_> = (0..2)
+            .map(|writer| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    for i in 0..20 {
+                        let _lock = VaultLock::acquire(&path, LOCK_TIMEOUT).expect("lock");
+                        let data = std::fs::read(&path).expect("read");
+                        let mut database: CredentialDatabase =
+                            serde_json::from_slice(&data).expect("parse");
+                        std::thread::yield_now();
+                        database.add_entry(crate::model::CredentialEntry::new(
+                            format!("writer{writer}-{i}"),
+                            "account".to_string(),
+                            "secret".to_string(),
+                        ));
+                        write_atomically(&path, &serde_json::to_vec(&database).expect("json"))
+                            .expect("write");
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().expect("writer should finish");
+        }
+
+        let database: CredentialDatabase =
+            serde_json::from_slice(&std::fs::read(&path).expect("read")).expect("parse");
+        assert_eq!(database.entries.len(), 40, "no update should be lost");
+    }
+
+    #[test]
+    fn vault_lock_times_out_while_held() {
+        let temp_dir = tempfile::tempdir().expect("tempdir");
+        let path = temp_dir.path().join("credentials.json");
+
+        let held = VaultLock::acquire(&path, Duration::ZERO).expect("lock");
+        assert!(matches!(
+            VaultLock::acquire(&path, Duration::from_millis(100)),
+            Err(CredentialError::VaultLocked(_))
+        ));
+
+        drop(held);
+        assert!(VaultLock::acquire(&path, Duration::ZERO).is_ok());
+    }
 }
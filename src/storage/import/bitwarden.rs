@@ -0,0 +1,265 @@
+// Bitwarden's unencrypted JSON export: { "items": [ { "name", "login": { ... } } ] }.
+use super::{Importer, ParsedImport};
+use crate::error::{CredentialError, CredentialResult};
+use crate::model::CredentialEntry;
+use crate::util::{link, totp};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Read;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Export {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<Folder>,
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Folder {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    #[serde(default)]
+    name: String,
+    login: Option<Login>,
+    notes: Option<String>,
+    #[serde(default)]
+    fields: Vec<Field>,
+    folder_id: Option<String>,
+    creation_date: Option<String>,
+    revision_date: Option<String>,
+}
+
+// Linked fields point at another field and carry no value of their own.
+#[derive(Debug, Deserialize)]
+struct Field {
+    name: Option<String>,
+    value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Login {
+    username: Option<String>,
+    password: Option<String>,
+    #[serde(default)]
+    uris: Option<Vec<LoginUri>>,
+    totp: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoginUri {
+    uri: Option<String>,
+}
+
+fn parse_timestamp(value: Option<&str>) -> Option<u64> {
+    let datetime = chrono::DateTime::parse_from_rfc3339(value?).ok()?;
+    u64::try_from(datetime.timestamp()).ok()
+}
+
+// Accepts both a bare base32 secret and a full otpauth:// URI, which keeps
+// any non-standard algorithm, digits, or period.
+fn parse_totp(value: &str) -> Option<(String, totp::TotpParams)> {
+    if value.trim_start().starts_with("otpauth://") {
+        let otp = totp::parse_otpauth_uri(value).ok()?;
+        return Some((otp.secret, otp.params));
+    }
+    totp::validate_secret(value)
+        .ok()
+        .map(|_| (totp::normalize_secret(value), totp::TotpParams::default()))
+}
+
+impl Item {
+    fn into_entry(self, folder: Option<&str>) -> Result<CredentialEntry, String> {
+        let login = self.login.ok_or("no login")?;
+        let secret = login
+            .password
+            .filter(|password| !password.is_empty())
+            .ok_or("login has no password")?;
+        let url = login
+            .uris
+            .unwrap_or_default()
+            .into_iter()
+            .find_map(|uri| uri.uri.filter(|uri| !uri.is_empty()));
+        let service = match self.name.trim() {
+            "" => url.clone().ok_or("item has no name")?,
+            name => name.to_string(),
+        };
+
+        let mut entry = CredentialEntry::new(service, login.username.unwrap_or_default(), secret);
+        // A link crab couldn't open is dropped rather than failing the whole item.
+        entry.url = url.and_then(|url| link::normalize_url(&url).ok());
+        if let Some((secret, params)) = login.totp.as_deref().and_then(parse_totp) {
+            entry.totp_secret = Some(secret);
+            entry.totp_params = params;
+        }
+        if let Some(notes) = self.notes.filter(|notes| !notes.trim().is_empty()) {
+            entry.fields.insert("notes".to_string(), notes);
+        }
+        for field in self.fields {
+            if let (Some(name), Some(value)) = (field.name, field.value) {
+                if !name.trim().is_empty() {
+                    entry.fields.entry(name.trim().to_string()).or_insert(value);
+                }
+            }
+        }
+        // Folders are the closest thing Bitwarden has to tags.
+        entry.tags = folder
+            .map(|folder| vec![folder.to_string()])
+            .unwrap_or_default();
+        if let Some(created_at) = parse_timestamp(self.creation_date.as_deref()) {
+            entry.created_at = created_at;
+        }
+        entry.updated_at =
+            parse_timestamp(self.revision_date.as_deref()).unwrap_or(entry.created_at);
+        Ok(entry)
+    }
+}
+
+pub struct Bitwarden;
+
+impl Importer for Bitwarden {
+    fn parse(&self, reader: &mut dyn Read) -> CredentialResult<ParsedImport> {
+        let export: Export = serde_json::from_reader(reader)?;
+        if export.encrypted {
+            return Err(CredentialError::invalid_input(
+                "This Bitwarden export is encrypted; export it as unencrypted JSON instead",
+            ));
+        }
+
+        let folders: HashMap<&str, &str> = export
+            .folders
+            .iter()
+            .map(|folder| (folder.id.as_str(), folder.name.as_str()))
+            .collect();
+        let mut parsed = ParsedImport::default();
+        for (i, item) in export.items.into_iter().enumerate() {
+            let name = item.name.clone();
+            let folder = item
+                .folder_id
+                .as_deref()
+                .and_then(|id| folders.get(id).copied());
+            match item.into_entry(folder) {
+                Ok(entry) => parsed.entries.push(entry),
+                Err(reason) => parsed
+                    .errors
+                    .push(format!("item {} ('{name}'): {reason}", i + 1)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"{
+        "encrypted": false,
+        "folders": [],
+        "items": [
+            {
+                "type": 1,
+                "name": "GitHub",
+                "creationDate": "2024-01-01T00:00:00.000Z",
+                "revisionDate": "2024-02-01T00:00:00.000Z",
+                "login": {
+                    "username": "alice",
+                    "password": "hunter2",
+                    "uris": [{ "match": null, "uri": "https://github.com" }],
+                    "totp": "JBSWY3DPEHPK3PXP"
+                }
+            },
+            { "type": 2, "name": "Secure note", "notes": "hello" },
+            {
+                "type": 1,
+                "name": "Empty",
+                "login": { "username": "bob", "password": null, "uris": null, "totp": null }
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn maps_login_items_onto_entries() {
+        let parsed = Bitwarden
+            .parse(&mut EXPORT.as_bytes())
+            .expect("parse should succeed");
+
+        assert_eq!(parsed.entries.len(), 1);
+        let entry = &parsed.entries[0];
+        assert_eq!(entry.service, "GitHub");
+        assert_eq!(entry.account, "alice");
+        assert_eq!(entry.secret, "hunter2");
+        assert_eq!(entry.url.as_deref(), Some("https://github.com"));
+        assert_eq!(entry.totp_secret.as_deref(), Some("JBSWY3DPEHPK3PXP"));
+        assert_eq!(entry.created_at, 1704067200);
+        assert_eq!(entry.updated_at, 1706745600);
+    }
+
+    #[test]
+    fn reports_items_without_a_usable_login() {
+        let parsed = Bitwarden
+            .parse(&mut EXPORT.as_bytes())
+            .expect("parse should succeed");
+
+        assert_eq!(parsed.errors.len(), 2);
+        assert!(parsed.errors[0].contains("Secure note"));
+        assert!(parsed.errors[1].contains("no password"));
+    }
+
+    #[test]
+    fn maps_notes_fields_folders_and_otpauth_totp() {
+        let export = r#"{
+            "encrypted": false,
+            "folders": [{ "id": "f1", "name": "Work" }],
+            "items": [{
+                "type": 1,
+                "name": "Bank",
+                "folderId": "f1",
+                "notes": "branch 042",
+                "fields": [
+                    { "name": "region", "value": "eu", "type": 0 },
+                    { "name": "pin", "value": "1234", "type": 1 },
+                    { "name": "Username", "value": null, "linkedId": 100, "type": 3 }
+                ],
+                "login": {
+                    "username": "alice",
+                    "password": "hunter2",
+                    "totp": "otpauth://totp/Bank:alice?secret=JBSWY3DP&algorithm=SHA256&digits=8"
+                }
+            }]
+        }"#;
+
+        let parsed = Bitwarden
+            .parse(&mut export.as_bytes())
+            .expect("parse should succeed");
+
+        let entry = &parsed.entries[0];
+        assert_eq!(entry.tags, vec!["Work".to_string()]);
+        assert_eq!(
+            entry.fields.get("notes").map(String::as_str),
+            Some("branch 042")
+        );
+        assert_eq!(entry.fields.get("region").map(String::as_str), Some("eu"));
+        assert_eq!(entry.fields.get("pin").map(String::as_str), Some("1234"));
+        assert!(!entry.fields.contains_key("Username"));
+        assert_eq!(entry.totp_secret.as_deref(), Some("JBSWY3DP"));
+        assert_eq!(entry.totp_params.algorithm, totp::TotpAlgorithm::Sha256);
+        assert_eq!(entry.totp_params.digits, 8);
+    }
+
+    #[test]
+    fn rejects_encrypted_exports() {
+        let export = r#"{ "encrypted": true, "items": [] }"#;
+
+        let result = Bitwarden.parse(&mut export.as_bytes());
+
+        assert!(matches!(result, Err(CredentialError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,99 @@
+// crab's own export layout: a JSON array or CSV with service/account/secret columns.
+use super::{Importer, ParsedImport};
+use crate::error::CredentialResult;
+use crate::model::CredentialEntry;
+use serde::Deserialize;
+use std::io::Read;
+
+#[derive(Debug, Deserialize)]
+struct ImportRecord {
+    service: String,
+    account: String,
+    secret: String,
+    created_at: Option<u64>,
+    updated_at: Option<u64>,
+}
+
+impl ImportRecord {
+    fn into_entry(self) -> CredentialEntry {
+        let mut entry = CredentialEntry::new(self.service, self.account, self.secret);
+        if let Some(created_at) = self.created_at {
+            entry.created_at = created_at;
+        }
+        entry.updated_at = self.updated_at.unwrap_or(entry.created_at);
+        entry
+    }
+}
+
+pub struct CrabJson;
+
+impl Importer for CrabJson {
+    // Malformed items are collected instead of aborting, so one bad row doesn't
+    // block the rest of the import.
+    fn parse(&self, reader: &mut dyn Read) -> CredentialResult<ParsedImport> {
+        let items: Vec<serde_json::Value> = serde_json::from_reader(reader)?;
+        let mut parsed = ParsedImport::default();
+
+        for (i, item) in items.into_iter().enumerate() {
+            match serde_json::from_value::<ImportRecord>(item) {
+                Ok(record) => parsed.entries.push(record.into_entry()),
+                Err(e) => parsed.errors.push(format!("item {}: {e}", i + 1)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+pub struct CrabCsv;
+
+impl Importer for CrabCsv {
+    fn parse(&self, reader: &mut dyn Read) -> CredentialResult<ParsedImport> {
+        let mut csv_reader = csv::Reader::from_reader(reader);
+        let mut parsed = ParsedImport::default();
+
+        for (i, record) in csv_reader.deserialize::<ImportRecord>().enumerate() {
+            // Row 1 is the header line.
+            match record {
+                Ok(record) => parsed.entries.push(record.into_entry()),
+                Err(e) => parsed.errors.push(format!("row {}: {e}", i + 2)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_csv_collects_malformed_rows() {
+        let csv = "service,account,secret\ngithub,alice,s1\nbroken,only-two\naws,bob,s2\n";
+
+        let parsed = CrabCsv
+            .parse(&mut csv.as_bytes())
+            .expect("parse should succeed");
+
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.entries[1].service, "aws");
+        assert_eq!(parsed.errors.len(), 1);
+        assert!(parsed.errors[0].starts_with("row 3"));
+    }
+
+    #[test]
+    fn parse_json_keeps_timestamps_and_reports_bad_items() {
+        let json = r#"[
+            {"service": "github", "account": "alice", "secret": "s1", "created_at": 10, "updated_at": 20},
+            {"service": "missing-secret", "account": "bob"}
+        ]"#;
+
+        let parsed = CrabJson
+            .parse(&mut json.as_bytes())
+            .expect("parse should succeed");
+
+        assert_eq!(parsed.entries.len(), 1);
+        assert_eq!(parsed.entries[0].created_at, 10);
+        assert_eq!(parsed.entries[0].updated_at, 20);
+        assert_eq!(parsed.errors.len(), 1);
+    }
+}
@@ -0,0 +1,221 @@
+// `.env` files: one KEY=VALUE per line, with optional `export ` prefixes, `#`
+// comments, and single- or double-quoted values that may span lines.
+use super::{Importer, ParsedImport};
+use crate::error::CredentialResult;
+use crate::model::CredentialEntry;
+use std::io::Read;
+
+// Entries get an empty account; the import command fills in `--account`.
+pub struct DotEnv;
+
+impl Importer for DotEnv {
+    fn parse(&self, reader: &mut dyn Read) -> CredentialResult<ParsedImport> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let mut parsed = ParsedImport::default();
+
+        let mut lines = text.lines().enumerate();
+        while let Some((i, line)) = lines.next() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+
+            let Some((key, value)) = line.split_once('=') else {
+                parsed
+                    .errors
+                    .push(format!("line {}: expected KEY=VALUE", i + 1));
+                continue;
+            };
+            let key = key.trim();
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                parsed
+                    .errors
+                    .push(format!("line {}: '{key}' is not a variable name", i + 1));
+                continue;
+            }
+
+            // A quoted value keeps consuming lines until its closing quote.
+            let mut value = value.trim_start().to_string();
+            if let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') {
+                while closing_quote(&value, quote).is_none() {
+                    match lines.next() {
+                        Some((_, next)) => {
+                            value.push('\n');
+                            value.push_str(next);
+                        }
+                        None => break,
+                    }
+                }
+            }
+
+            match parse_value(&value) {
+                Ok(secret) => parsed.entries.push(CredentialEntry::new(
+                    key.to_string(),
+                    String::new(),
+                    secret,
+                )),
+                Err(e) => parsed.errors.push(format!("line {}: {e}", i + 1)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+// Byte offset of the quote that closes `value` (which starts with `quote`).
+// Backslash escapes only apply inside double quotes.
+fn closing_quote(value: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (offset, c) in value.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && quote == '"' {
+            escaped = true;
+        } else if c == quote {
+            return Some(offset);
+        }
+    }
+    None
+}
+
+fn parse_value(value: &str) -> Result<String, String> {
+    let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+        // Unquoted: an inline comment starts at whitespace followed by '#'.
+        let end = value
+            .char_indices()
+            .find(|(offset, c)| *c == '#' && value[..*offset].ends_with(char::is_whitespace))
+            .map_or(value.len(), |(offset, _)| offset);
+        return Ok(value[..end].trim_end().to_string());
+    };
+
+    let close = closing_quote(value, quote).ok_or("unterminated quoted value")?;
+    let rest = value[close + 1..].trim_start();
+    if !(rest.is_empty() || rest.starts_with('#')) {
+        return Err(format!("unexpected text after the closing quote: '{rest}'"));
+    }
+
+    let inner = &value[1..close];
+    if quote == '\'' {
+        return Ok(inner.to_string());
+    }
+    let mut unescaped = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    Ok(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::CredentialDatabase;
+    use crate::storage::export::export_env;
+
+    fn parse(text: &str) -> ParsedImport {
+        DotEnv
+            .parse(&mut text.as_bytes())
+            .expect("parse should succeed")
+    }
+
+    fn pairs(parsed: &ParsedImport) -> Vec<(&str, &str)> {
+        parsed
+            .entries
+            .iter()
+            .map(|entry| (entry.service.as_str(), entry.secret.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_plain_quoted_and_exported_lines() {
+        let parsed = parse(
+            "# database settings\n\
+             \n\
+             DB_URL=postgres://localhost/app\n\
+             export API_KEY = abc123 # rotated monthly\n\
+             GREETING=\"hello \\\"world\\\"\\nbye\"\n\
+             LITERAL='no \\n escapes here'\n\
+             HASH=a#b\n\
+             EMPTY=\n",
+        );
+
+        assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+        assert_eq!(
+            pairs(&parsed),
+            vec![
+                ("DB_URL", "postgres://localhost/app"),
+                ("API_KEY", "abc123"),
+                ("GREETING", "hello \"world\"\nbye"),
+                ("LITERAL", "no \\n escapes here"),
+                ("HASH", "a#b"),
+                ("EMPTY", ""),
+            ]
+        );
+        assert!(parsed.entries.iter().all(|entry| entry.account.is_empty()));
+    }
+
+    #[test]
+    fn quoted_values_may_span_lines() {
+        let parsed = parse("KEY=\"-----BEGIN KEY-----\nabc\n-----END KEY-----\"\nNEXT=1\n");
+
+        assert_eq!(
+            pairs(&parsed),
+            vec![
+                ("KEY", "-----BEGIN KEY-----\nabc\n-----END KEY-----"),
+                ("NEXT", "1")
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_lines_are_reported_with_line_numbers() {
+        let parsed = parse("GOOD=1\nnot a pair\nBAD KEY=2\nTAIL=\"x\" y\nOPEN=\"never closed\n");
+
+        assert_eq!(pairs(&parsed), vec![("GOOD", "1")]);
+        assert_eq!(parsed.errors.len(), 4);
+        assert!(parsed.errors[0].starts_with("line 2"));
+        assert!(parsed.errors[1].starts_with("line 3"));
+        assert!(parsed.errors[2].contains("after the closing quote"));
+        assert!(parsed.errors[3].starts_with("line 5: unterminated"));
+    }
+
+    #[test]
+    fn export_env_round_trips() {
+        let mut database = CredentialDatabase::new();
+        for (service, secret) in [
+            ("PLAIN", "abc123"),
+            ("SPACES", "two words"),
+            ("TRICKY", "quote \" back\\slash $HOME\nnew line\ttab"),
+            ("EMPTY", ""),
+        ] {
+            database.add_entry(CredentialEntry::new(
+                service.to_string(),
+                "env".to_string(),
+                secret.to_string(),
+            ));
+        }
+        let mut output = Vec::new();
+        export_env(&database, &mut output).expect("export should succeed");
+
+        let parsed = parse(&String::from_utf8(output).expect("utf8"));
+
+        assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+        let original: Vec<(&str, &str)> = database
+            .entries
+            .iter()
+            .map(|entry| (entry.service.as_str(), entry.secret.as_str()))
+            .collect();
+        assert_eq!(pairs(&parsed), original);
+    }
+}
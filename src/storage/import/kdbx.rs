@@ -0,0 +1,345 @@
+// KeePass / KeePassXC databases (.kdbx). Unlike the other formats the file is
+// encrypted, so the importer carries the password and key file that open it.
+use super::{Importer, ParsedImport};
+use crate::error::{CredentialError, CredentialResult};
+use crate::model::CredentialEntry;
+use crate::util::{link, totp};
+use keepass::db::{Entry, Group, NodeRef};
+use keepass::error::{DatabaseKeyError, DatabaseOpenError};
+use keepass::{Database, DatabaseKey};
+use std::io::Read;
+use zeroize::Zeroizing;
+
+// Fields with a meaning of their own; everything else is a custom field.
+const STANDARD_FIELDS: &[&str] = &["Title", "UserName", "Password", "URL", "Notes", "otp"];
+// KeePass 2's built-in TOTP settings, as opposed to KeePassXC's otpauth:// "otp" field.
+const KEEPASS_TOTP_PREFIX: &str = "TimeOtp-";
+
+#[derive(Default)]
+pub struct KdbxKey {
+    pub password: Option<Zeroizing<String>>,
+    pub keyfile: Option<Zeroizing<Vec<u8>>>,
+}
+
+impl KdbxKey {
+    fn database_key(&self) -> CredentialResult<DatabaseKey> {
+        let mut key = DatabaseKey::new();
+        if let Some(password) = &self.password {
+            key = key.with_password(password);
+        }
+        if let Some(keyfile) = &self.keyfile {
+            key = key.with_keyfile(&mut keyfile.as_slice())?;
+        }
+        if key.is_empty() {
+            return Err(CredentialError::invalid_input(
+                "A KeePass database needs a password or a key file to open",
+            ));
+        }
+        Ok(key)
+    }
+}
+
+pub struct Kdbx {
+    pub key: KdbxKey,
+}
+
+fn open_error(error: DatabaseOpenError) -> CredentialError {
+    match error {
+        DatabaseOpenError::Key(DatabaseKeyError::IncorrectKey) => {
+            CredentialError::invalid_input("Wrong password or key file for this KeePass database")
+        }
+        other => CredentialError::invalid_input(&format!("Cannot open KeePass database: {other}")),
+    }
+}
+
+fn timestamp(time: Option<&chrono::NaiveDateTime>) -> Option<u64> {
+    u64::try_from(time?.and_utc().timestamp()).ok()
+}
+
+fn totp_params(entry: &Entry) -> totp::TotpParams {
+    let mut params = totp::TotpParams::default();
+    if let Some(algorithm) = entry.get("TimeOtp-Algorithm") {
+        params.algorithm = match algorithm {
+            "HMAC-SHA-256" => totp::TotpAlgorithm::Sha256,
+            "HMAC-SHA-512" => totp::TotpAlgorithm::Sha512,
+            _ => totp::TotpAlgorithm::Sha1,
+        };
+    }
+    if let Some(digits) = entry.get("TimeOtp-Length").and_then(|v| v.parse().ok()) {
+        params.digits = digits;
+    }
+    if let Some(period) = entry.get("TimeOtp-Period").and_then(|v| v.parse().ok()) {
+        params.period = period;
+    }
+    params
+}
+
+// KeePassXC keeps an otpauth:// URI (or, in old files, a bare secret) in
+// "otp"; KeePass 2 spreads its settings over TimeOtp-* fields.
+fn entry_totp(entry: &Entry) -> Option<(String, totp::TotpParams)> {
+    if let Some(otp) = entry.get("otp") {
+        if otp.trim_start().starts_with("otpauth://") {
+            return totp::parse_otpauth_uri(otp)
+                .ok()
+                .map(|otp| (otp.secret, otp.params));
+        }
+        return totp::validate_secret(otp)
+            .ok()
+            .map(|_| (totp::normalize_secret(otp), totp::TotpParams::default()));
+    }
+    let secret = entry.get("TimeOtp-Secret-Base32")?;
+    let params = totp_params(entry);
+    (totp::validate_secret(secret).is_ok() && params.validate().is_ok())
+        .then(|| (totp::normalize_secret(secret), params))
+}
+
+fn into_entry(entry: &Entry, group_path: &str) -> Result<CredentialEntry, String> {
+    let secret = entry
+        .get_password()
+        .filter(|password| !password.is_empty())
+        .ok_or("no password")?;
+    let url = entry.get_url().map(str::trim).filter(|url| !url.is_empty());
+    let service = match entry.get_title().map(str::trim).unwrap_or("") {
+        "" => url.ok_or("entry has no title")?,
+        title => title,
+    };
+
+    let mut credential = CredentialEntry::new(
+        service.to_string(),
+        entry.get_username().unwrap_or_default().to_string(),
+        secret.to_string(),
+    );
+    // A link crab couldn't open is dropped rather than failing the whole entry.
+    credential.url = url.and_then(|url| link::normalize_url(url).ok());
+    if let Some((secret, params)) = entry_totp(entry) {
+        credential.totp_secret = Some(secret);
+        credential.totp_params = params;
+    }
+    if let Some(notes) = entry.get("Notes").filter(|notes| !notes.trim().is_empty()) {
+        credential
+            .fields
+            .insert("notes".to_string(), notes.to_string());
+    }
+    for name in entry.fields.keys() {
+        if STANDARD_FIELDS.contains(&name.as_str()) || name.starts_with(KEEPASS_TOTP_PREFIX) {
+            continue;
+        }
+        if let Some(value) = entry.get(name).filter(|value| !value.is_empty()) {
+            credential
+                .fields
+                .entry(name.clone())
+                .or_insert_with(|| value.to_string());
+        }
+    }
+
+    let mut tags = Vec::new();
+    if !group_path.is_empty() {
+        tags.push(group_path.to_string());
+    }
+    tags.extend(entry.tags.iter().cloned());
+    credential.tags = crate::model::parse_tags(&tags.join(","));
+
+    if let Some(created_at) = timestamp(entry.times.get_creation()) {
+        credential.created_at = created_at;
+    }
+    credential.updated_at =
+        timestamp(entry.times.get_last_modification()).unwrap_or(credential.created_at);
+    Ok(credential)
+}
+
+// Walks the tree depth-first, naming each entry's group by its path below the
+// root ("Internet/Social"). The recycle bin is left behind.
+fn collect(group: &Group, path: &str, recycle_bin: Option<&[u8; 16]>, parsed: &mut ParsedImport) {
+    for node in &group.children {
+        match node.as_ref() {
+            NodeRef::Group(child) => {
+                if Some(child.uuid.as_bytes()) == recycle_bin {
+                    continue;
+                }
+                let child_path = if path.is_empty() {
+                    child.name.clone()
+                } else {
+                    format!("{path}/{}", child.name)
+                };
+                collect(child, &child_path, recycle_bin, parsed);
+            }
+            NodeRef::Entry(entry) => match into_entry(entry, path) {
+                Ok(credential) => parsed.entries.push(credential),
+                Err(reason) => {
+                    let title = entry.get_title().unwrap_or_default();
+                    let location = if path.is_empty() { "/" } else { path };
+                    parsed
+                        .errors
+                        .push(format!("'{title}' in {location}: {reason}"));
+                }
+            },
+        }
+    }
+}
+
+impl Importer for Kdbx {
+    fn parse(&self, reader: &mut dyn Read) -> CredentialResult<ParsedImport> {
+        let database = Database::open(reader, self.key.database_key()?).map_err(open_error)?;
+        let recycle_bin = database
+            .meta
+            .recyclebin_uuid
+            .filter(|_| database.meta.recyclebin_enabled.unwrap_or(true))
+            .map(|uuid| *uuid.as_bytes());
+
+        let mut parsed = ParsedImport::default();
+        collect(&database.root, "", recycle_bin.as_ref(), &mut parsed);
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keepass::config::{DatabaseConfig, KdfConfig};
+    use keepass::db::Value;
+
+    fn kp_entry(fields: &[(&str, &str)]) -> Entry {
+        let mut entry = Entry::new();
+        for (name, value) in fields {
+            entry
+                .fields
+                .insert(name.to_string(), Value::Unprotected(value.to_string()));
+        }
+        entry
+    }
+
+    // A few AES rounds instead of the default Argon2 keep the tests fast.
+    fn sample_database() -> Database {
+        let mut database = Database::new(DatabaseConfig {
+            kdf_config: KdfConfig::Aes { rounds: 10 },
+            ..DatabaseConfig::default()
+        });
+
+        let mut github = kp_entry(&[
+            ("Title", "GitHub"),
+            ("UserName", "alice"),
+            ("Password", "hunter2"),
+            ("URL", "github.com"),
+            ("Notes", "work account"),
+            ("Recovery PIN", "1234"),
+            (
+                "otp",
+                "otpauth://totp/GitHub:alice?secret=JBSWY3DP&algorithm=SHA256&digits=8",
+            ),
+        ]);
+        github.tags = vec!["dev".to_string()];
+        let mut social = Group::new("Social");
+        social.add_child(github);
+        let mut internet = Group::new("Internet");
+        internet.add_child(social);
+        internet.add_child(kp_entry(&[
+            ("Title", "Bank"),
+            ("UserName", "bob"),
+            ("Password", "s3cret"),
+            ("TimeOtp-Secret-Base32", "JBSWY3DP"),
+            ("TimeOtp-Length", "7"),
+        ]));
+        database.root.add_child(internet);
+        database
+            .root
+            .add_child(kp_entry(&[("Title", "Empty"), ("UserName", "carol")]));
+
+        let mut recycle_bin = Group::new("Recycle Bin");
+        recycle_bin.add_child(kp_entry(&[("Title", "Old"), ("Password", "gone")]));
+        database.meta.recyclebin_uuid = Some(recycle_bin.uuid);
+        database.root.add_child(recycle_bin);
+        database
+    }
+
+    fn saved(database: &Database, key: DatabaseKey) -> Vec<u8> {
+        let mut data = Vec::new();
+        database.save(&mut data, key).expect("save kdbx");
+        data
+    }
+
+    fn password_key(password: &str) -> KdbxKey {
+        KdbxKey {
+            password: Some(Zeroizing::new(password.to_string())),
+            keyfile: None,
+        }
+    }
+
+    #[test]
+    fn maps_groups_fields_and_totp_onto_entries() {
+        let data = saved(&sample_database(), DatabaseKey::new().with_password("pw"));
+
+        let parsed = Kdbx {
+            key: password_key("pw"),
+        }
+        .parse(&mut data.as_slice())
+        .expect("parse should succeed");
+
+        let services: Vec<&str> = parsed.entries.iter().map(|e| e.service.as_str()).collect();
+        assert_eq!(services, vec!["GitHub", "Bank"]);
+
+        let github = &parsed.entries[0];
+        assert_eq!(github.account, "alice");
+        assert_eq!(github.secret, "hunter2");
+        assert_eq!(github.url.as_deref(), Some("https://github.com"));
+        assert_eq!(github.tags, vec!["Internet/Social", "dev"]);
+        assert_eq!(
+            github.fields.get("notes").map(String::as_str),
+            Some("work account")
+        );
+        assert_eq!(
+            github.fields.get("Recovery PIN").map(String::as_str),
+            Some("1234")
+        );
+        assert!(!github.fields.contains_key("otp"));
+        assert_eq!(github.totp_secret.as_deref(), Some("JBSWY3DP"));
+        assert_eq!(github.totp_params.algorithm, totp::TotpAlgorithm::Sha256);
+        assert_eq!(github.totp_params.digits, 8);
+
+        let bank = &parsed.entries[1];
+        assert_eq!(bank.tags, vec!["Internet"]);
+        assert_eq!(bank.totp_secret.as_deref(), Some("JBSWY3DP"));
+        assert_eq!(bank.totp_params.digits, 7);
+        assert!(bank.fields.is_empty());
+
+        assert_eq!(parsed.errors, vec!["'Empty' in /: no password".to_string()]);
+    }
+
+    #[test]
+    fn opens_with_a_key_file() {
+        let keyfile = b"any file can be a key file".to_vec();
+        let key = DatabaseKey::new()
+            .with_keyfile(&mut keyfile.as_slice())
+            .expect("key file");
+        let data = saved(&sample_database(), key);
+
+        let parsed = Kdbx {
+            key: KdbxKey {
+                password: None,
+                keyfile: Some(Zeroizing::new(keyfile)),
+            },
+        }
+        .parse(&mut data.as_slice())
+        .expect("parse should succeed");
+
+        assert_eq!(parsed.entries.len(), 2);
+    }
+
+    #[test]
+    fn wrong_or_missing_key_is_an_error() {
+        let data = saved(&sample_database(), DatabaseKey::new().with_password("pw"));
+
+        let wrong = Kdbx {
+            key: password_key("nope"),
+        }
+        .parse(&mut data.as_slice());
+        let missing = Kdbx {
+            key: KdbxKey::default(),
+        }
+        .parse(&mut data.as_slice());
+
+        assert!(
+            matches!(wrong, Err(CredentialError::InvalidInput(ref m)) if m.contains("Wrong password"))
+        );
+        assert!(matches!(missing, Err(CredentialError::InvalidInput(_))));
+    }
+}
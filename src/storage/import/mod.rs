@@ -0,0 +1,317 @@
+use crate::error::{CredentialError, CredentialResult};
+use crate::model::{CredentialDatabase, CredentialEntry};
+use clap::ValueEnum;
+use std::io::Read;
+use std::path::Path;
+
+mod bitwarden;
+mod crab;
+mod dotenv;
+mod kdbx;
+mod onepassword;
+
+use bitwarden::Bitwarden;
+use crab::{CrabCsv, CrabJson};
+use dotenv::DotEnv;
+use kdbx::Kdbx;
+pub use kdbx::KdbxKey;
+use onepassword::{OnePasswordCsv, OnePif};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    Json,
+    Csv,
+    Bitwarden,
+    #[value(name = "1password-csv")]
+    OnePasswordCsv,
+    #[value(name = "1pif")]
+    OnePif,
+    Env,
+    Kdbx,
+}
+
+impl ImportFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        // `.env` and `.env.local` have no extension as far as `Path` is concerned.
+        let file_name = path.file_name()?.to_str()?;
+        if file_name == ".env" || file_name.starts_with(".env.") {
+            return Some(ImportFormat::Env);
+        }
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "json" => Some(ImportFormat::Json),
+            "csv" => Some(ImportFormat::Csv),
+            "1pif" => Some(ImportFormat::OnePif),
+            "env" => Some(ImportFormat::Env),
+            "kdbx" => Some(ImportFormat::Kdbx),
+            _ => None,
+        }
+    }
+
+    // Refines the extension guess by peeking at the content, since Bitwarden and
+    // 1Password exports share extensions with crab's own format.
+    pub fn detect(path: &Path, data: &[u8]) -> Option<Self> {
+        match Self::from_path(path)? {
+            ImportFormat::Json => {
+                let value: serde_json::Value = serde_json::from_slice(data).ok()?;
+                if value.get("items").is_some_and(serde_json::Value::is_array) {
+                    Some(ImportFormat::Bitwarden)
+                } else {
+                    Some(ImportFormat::Json)
+                }
+            }
+            ImportFormat::Csv => {
+                let mut reader = csv::Reader::from_reader(data);
+                let headers = reader.headers().ok()?;
+                let has = |name: &str| {
+                    headers
+                        .iter()
+                        .any(|header| header.trim().eq_ignore_ascii_case(name))
+                };
+                if !has("service") && has("password") {
+                    Some(ImportFormat::OnePasswordCsv)
+                } else {
+                    Some(ImportFormat::Csv)
+                }
+            }
+            other => Some(other),
+        }
+    }
+
+    fn importer(self) -> Box<dyn Importer> {
+        match self {
+            ImportFormat::Json => Box::new(CrabJson),
+            ImportFormat::Csv => Box::new(CrabCsv),
+            ImportFormat::Bitwarden => Box::new(Bitwarden),
+            ImportFormat::OnePasswordCsv => Box::new(OnePasswordCsv),
+            ImportFormat::OnePif => Box::new(OnePif),
+            ImportFormat::Env => Box::new(DotEnv),
+            // parse_kdbx supplies the key; without one the file won't open.
+            ImportFormat::Kdbx => Box::new(Kdbx {
+                key: KdbxKey::default(),
+            }),
+        }
+    }
+}
+
+// One implementation per export format. Records that cannot be mapped onto a
+// CredentialEntry go into `errors` rather than failing the whole import.
+pub trait Importer {
+    fn parse(&self, reader: &mut dyn Read) -> CredentialResult<ParsedImport>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MergeStrategy {
+    Skip,
+    Overwrite,
+    #[value(alias = "rename")]
+    RenameOnConflict,
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedImport {
+    pub entries: Vec<CredentialEntry>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub skipped: usize,
+    pub overwritten: usize,
+    pub renamed: usize,
+}
+
+pub fn parse_import(format: ImportFormat, mut reader: impl Read) -> CredentialResult<ParsedImport> {
+    format.importer().parse(&mut reader)
+}
+
+pub fn parse_kdbx(mut reader: impl Read, key: KdbxKey) -> CredentialResult<ParsedImport> {
+    Kdbx { key }.parse(&mut reader)
+}
+
+pub fn apply_import(
+    database: &mut CredentialDatabase,
+    entries: Vec<CredentialEntry>,
+    strategy: MergeStrategy,
+) -> ImportSummary {
+    let mut summary = ImportSummary::default();
+    for entry in entries {
+        apply_import_entry(database, entry, strategy, &mut summary);
+    }
+    summary
+}
+
+// Only the same service and account is a conflict; a second account for a
+// service is simply added alongside the first.
+pub fn apply_import_entry(
+    database: &mut CredentialDatabase,
+    mut entry: CredentialEntry,
+    strategy: MergeStrategy,
+    summary: &mut ImportSummary,
+) {
+    if database
+        .find_entry(&entry.service, Some(&entry.account))
+        .is_some()
+    {
+        match strategy {
+            MergeStrategy::Skip => {
+                summary.skipped += 1;
+                return;
+            }
+            MergeStrategy::Overwrite => {
+                database.remove_entry(&entry.service, Some(&entry.account));
+                summary.overwritten += 1;
+            }
+            MergeStrategy::RenameOnConflict => {
+                entry.service = available_service_name(database, &entry.service);
+                summary.renamed += 1;
+            }
+        }
+    } else {
+        summary.imported += 1;
+    }
+    database.add_entry(entry);
+}
+
+fn available_service_name(database: &CredentialDatabase, service: &str) -> String {
+    (2..)
+        .map(|n| format!("{service}-{n}"))
+        .find(|candidate| database.find_entry(candidate, None).is_none())
+        .expect("an unused suffix always exists")
+}
+
+pub fn unknown_format_error(path: &Path) -> CredentialError {
+    CredentialError::invalid_input(&format!(
+        "Cannot infer the import format of '{}'; pass --format",
+        path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_entry(service: &str, secret: &str) -> CredentialEntry {
+        CredentialEntry::new(
+            service.to_string(),
+            "account".to_string(),
+            secret.to_string(),
+        )
+    }
+
+    #[test]
+    fn format_is_inferred_from_extension() {
+        assert_eq!(
+            ImportFormat::from_path(Path::new("vault.CSV")),
+            Some(ImportFormat::Csv)
+        );
+        assert_eq!(ImportFormat::from_path(Path::new("vault.txt")), None);
+        for name in [".env", "app/.env.local", "secrets.env"] {
+            assert_eq!(
+                ImportFormat::from_path(Path::new(name)),
+                Some(ImportFormat::Env),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn detect_recognises_other_managers_by_content() {
+        let bitwarden = br#"{"encrypted": false, "items": []}"#;
+        let crab_json = br#"[{"service": "github", "account": "a", "secret": "s"}]"#;
+        let onepassword = b"Title,Website,Username,Password\n";
+        let crab_csv = b"service,account,secret\n";
+
+        let detect = |name: &str, data: &[u8]| ImportFormat::detect(Path::new(name), data);
+
+        assert_eq!(detect("a.json", bitwarden), Some(ImportFormat::Bitwarden));
+        assert_eq!(detect("a.json", crab_json), Some(ImportFormat::Json));
+        assert_eq!(
+            detect("a.csv", onepassword),
+            Some(ImportFormat::OnePasswordCsv)
+        );
+        assert_eq!(detect("a.csv", crab_csv), Some(ImportFormat::Csv));
+        assert_eq!(detect("a.1pif", b""), Some(ImportFormat::OnePif));
+    }
+
+    #[test]
+    fn apply_import_skips_conflicts() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(sample_entry("github", "old"));
+
+        let summary = apply_import(
+            &mut database,
+            vec![sample_entry("github", "new"), sample_entry("aws", "s")],
+            MergeStrategy::Skip,
+        );
+
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.skipped, 1);
+        assert_eq!(
+            database.find_entry("github", None).expect("entry").secret,
+            "old"
+        );
+    }
+
+    #[test]
+    fn apply_import_adds_second_account_for_same_service() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(sample_entry("github", "old"));
+        let mut work = sample_entry("github", "work-secret");
+        work.account = "work".to_string();
+
+        let summary = apply_import(&mut database, vec![work], MergeStrategy::Skip);
+
+        assert_eq!(summary.imported, 1);
+        assert_eq!(database.entries_for("github").len(), 2);
+        assert_eq!(
+            database
+                .find_entry("github", Some("work"))
+                .expect("entry")
+                .secret,
+            "work-secret"
+        );
+    }
+
+    #[test]
+    fn apply_import_overwrites_conflicts() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(sample_entry("github", "old"));
+
+        let summary = apply_import(
+            &mut database,
+            vec![sample_entry("github", "new")],
+            MergeStrategy::Overwrite,
+        );
+
+        assert_eq!(summary.overwritten, 1);
+        assert_eq!(database.len(), 1);
+        assert_eq!(
+            database.find_entry("github", None).expect("entry").secret,
+            "new"
+        );
+    }
+
+    #[test]
+    fn apply_import_renames_conflicts() {
+        let mut database = CredentialDatabase::new();
+        database.add_entry(sample_entry("github", "old"));
+        database.add_entry(sample_entry("github-2", "older"));
+
+        let summary = apply_import(
+            &mut database,
+            vec![sample_entry("github", "new")],
+            MergeStrategy::RenameOnConflict,
+        );
+
+        assert_eq!(summary.renamed, 1);
+        assert_eq!(
+            database.find_entry("github-3", None).expect("entry").secret,
+            "new"
+        );
+        assert_eq!(
+            database.find_entry("github", None).expect("entry").secret,
+            "old"
+        );
+    }
+}
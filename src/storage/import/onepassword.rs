@@ -0,0 +1,232 @@
+// 1Password exports: the CSV written by 1Password 7/8 and the older .1pif format.
+use super::{Importer, ParsedImport};
+use crate::error::{CredentialError, CredentialResult};
+use crate::model::CredentialEntry;
+use crate::util::link::normalize_url;
+use serde::Deserialize;
+use std::io::Read;
+
+// Lines of this form separate the JSON records in a .1pif file.
+const PIF_SEPARATOR: &str = "***5642bee8-a5ff-11dc-8314-0800200c9a66***";
+
+fn column(headers: &csv::StringRecord, names: &[&str]) -> Option<usize> {
+    headers
+        .iter()
+        .position(|header| names.contains(&header.trim().to_lowercase().as_str()))
+}
+
+fn entry_from_parts(
+    title: &str,
+    username: &str,
+    password: &str,
+    url: &str,
+) -> Result<CredentialEntry, String> {
+    if password.is_empty() {
+        return Err("no password".to_string());
+    }
+    let url = Some(url.trim()).filter(|url| !url.is_empty());
+    let service = match title.trim() {
+        "" => url.ok_or("no title")?,
+        title => title,
+    };
+
+    let mut entry = CredentialEntry::new(
+        service.to_string(),
+        username.to_string(),
+        password.to_string(),
+    );
+    // A link crab couldn't open is dropped rather than failing the whole row.
+    entry.url = url.and_then(|url| normalize_url(url).ok());
+    Ok(entry)
+}
+
+pub struct OnePasswordCsv;
+
+impl Importer for OnePasswordCsv {
+    fn parse(&self, reader: &mut dyn Read) -> CredentialResult<ParsedImport> {
+        let mut csv_reader = csv::Reader::from_reader(reader);
+        let headers = csv_reader
+            .headers()
+            .map_err(|e| CredentialError::invalid_input(&format!("Unreadable CSV header: {e}")))?
+            .clone();
+        let title = column(&headers, &["title", "name"]);
+        let username = column(&headers, &["username"]);
+        let url = column(&headers, &["url", "website", "location"]);
+        let password = column(&headers, &["password"]).ok_or_else(|| {
+            CredentialError::invalid_input("1Password CSV export has no Password column")
+        })?;
+
+        let mut parsed = ParsedImport::default();
+        for (i, record) in csv_reader.records().enumerate() {
+            // Row 1 is the header line.
+            let row = i + 2;
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    parsed.errors.push(format!("row {row}: {e}"));
+                    continue;
+                }
+            };
+            let field = |index: Option<usize>| index.and_then(|i| record.get(i)).unwrap_or("");
+
+            match entry_from_parts(
+                field(title),
+                field(username),
+                field(Some(password)),
+                field(url),
+            ) {
+                Ok(entry) => parsed.entries.push(entry),
+                Err(reason) => parsed.errors.push(format!("row {row}: {reason}")),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PifRecord {
+    #[serde(default)]
+    type_name: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    location: String,
+    #[serde(default)]
+    trashed: bool,
+    created_at: Option<u64>,
+    updated_at: Option<u64>,
+    #[serde(default)]
+    secure_contents: PifContents,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PifContents {
+    #[serde(default)]
+    fields: Vec<PifField>,
+    password: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PifField {
+    designation: Option<String>,
+    #[serde(default)]
+    value: String,
+}
+
+impl PifRecord {
+    fn designated(&self, designation: &str) -> Option<&str> {
+        self.secure_contents
+            .fields
+            .iter()
+            .find(|field| field.designation.as_deref() == Some(designation))
+            .map(|field| field.value.as_str())
+    }
+
+    fn into_entry(self) -> Result<CredentialEntry, String> {
+        if self.trashed {
+            return Err("in trash".to_string());
+        }
+        let password = match self.type_name.as_str() {
+            "webforms.WebForm" => self.designated("password").unwrap_or(""),
+            "passwords.Password" => self.secure_contents.password.as_deref().unwrap_or(""),
+            other => return Err(format!("not a login ({other})")),
+        };
+
+        let mut entry = entry_from_parts(
+            &self.title,
+            self.designated("username").unwrap_or(""),
+            password,
+            &self.location,
+        )?;
+        if let Some(created_at) = self.created_at {
+            entry.created_at = created_at;
+        }
+        entry.updated_at = self.updated_at.unwrap_or(entry.created_at);
+        Ok(entry)
+    }
+}
+
+pub struct OnePif;
+
+impl Importer for OnePif {
+    fn parse(&self, reader: &mut dyn Read) -> CredentialResult<ParsedImport> {
+        let mut data = String::new();
+        reader.read_to_string(&mut data)?;
+
+        let mut parsed = ParsedImport::default();
+        let records = data
+            .split(PIF_SEPARATOR)
+            .map(str::trim)
+            .filter(|record| !record.is_empty());
+        for (i, record) in records.enumerate() {
+            let result = serde_json::from_str::<PifRecord>(record)
+                .map_err(|e| e.to_string())
+                .and_then(|record| {
+                    let title = record.title.clone();
+                    record
+                        .into_entry()
+                        .map_err(|reason| format!("'{title}': {reason}"))
+                });
+            match result {
+                Ok(entry) => parsed.entries.push(entry),
+                Err(reason) => parsed.errors.push(format!("record {}: {reason}", i + 1)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_maps_columns_by_header_name() {
+        let csv = "Title,Website,Username,Password,Notes\n\
+                   GitHub,https://github.com,alice,hunter2,\n\
+                   Wifi,,,,router notes\n";
+
+        let parsed = OnePasswordCsv
+            .parse(&mut csv.as_bytes())
+            .expect("parse should succeed");
+
+        assert_eq!(parsed.entries.len(), 1);
+        let entry = &parsed.entries[0];
+        assert_eq!(entry.service, "GitHub");
+        assert_eq!(entry.account, "alice");
+        assert_eq!(entry.secret, "hunter2");
+        assert_eq!(entry.url.as_deref(), Some("https://github.com"));
+        assert_eq!(parsed.errors, vec!["row 3: no password".to_string()]);
+    }
+
+    #[test]
+    fn csv_without_password_column_is_rejected() {
+        let result = OnePasswordCsv.parse(&mut "Title,Notes\nx,y\n".as_bytes());
+
+        assert!(matches!(result, Err(CredentialError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn pif_reads_web_forms_and_reports_other_records() {
+        let pif = format!(
+            "{}\n{PIF_SEPARATOR}\n{}\n{PIF_SEPARATOR}\n",
+            r#"{"typeName":"webforms.WebForm","title":"GitHub","location":"https://github.com","createdAt":10,"updatedAt":20,"secureContents":{"fields":[{"designation":"username","value":"alice"},{"designation":"password","value":"hunter2"}]}}"#,
+            r#"{"typeName":"securenotes.SecureNote","title":"Notes","secureContents":{}}"#,
+        );
+
+        let parsed = OnePif
+            .parse(&mut pif.as_bytes())
+            .expect("parse should succeed");
+
+        assert_eq!(parsed.entries.len(), 1);
+        let entry = &parsed.entries[0];
+        assert_eq!(entry.service, "GitHub");
+        assert_eq!(entry.account, "alice");
+        assert_eq!(entry.secret, "hunter2");
+        assert_eq!(entry.created_at, 10);
+        assert_eq!(entry.updated_at, 20);
+        assert_eq!(parsed.errors.len(), 1);
+        assert!(parsed.errors[0].contains("not a login"));
+    }
+}
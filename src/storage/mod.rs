@@ -1,6 +1,12 @@
+pub mod doctor;
+pub mod export;
 pub mod file;
+pub mod import;
 
 pub use file::{
-    backup_database, database_exists, delete_database, get_database_info, load_database,
-    save_database,
+    backup_database, database_exists, delete_database, get_database_info, init_database,
+    load_database, save_database,
 };
+
+pub use export::{export_database, export_entry, EntryFormat, ExportFormat};
+pub use import::{ImportFormat, MergeStrategy};
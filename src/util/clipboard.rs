@@ -0,0 +1,148 @@
+use crate::error::{CredentialError, CredentialResult};
+use arboard::Clipboard;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+pub const DEFAULT_CLEAR_TIMEOUT_SECS: u64 = 30;
+
+// Hidden subcommand the background helper runs as.
+pub const HELPER_COMMAND: &str = "__clipboard";
+
+pub trait ClipboardBackend {
+    fn get_text(&mut self) -> CredentialResult<String>;
+    fn set_text(&mut self, text: &str) -> CredentialResult<()>;
+}
+
+pub struct SystemClipboard {
+    clipboard: Clipboard,
+}
+
+impl SystemClipboard {
+    pub fn new() -> CredentialResult<Self> {
+        let clipboard =
+            Clipboard::new().map_err(|e| CredentialError::clipboard_unavailable(&e.to_string()))?;
+        Ok(Self { clipboard })
+    }
+}
+
+impl ClipboardBackend for SystemClipboard {
+    fn get_text(&mut self) -> CredentialResult<String> {
+        self.clipboard
+            .get_text()
+            .map_err(|e| CredentialError::clipboard_unavailable(&e.to_string()))
+    }
+
+    fn set_text(&mut self, text: &str) -> CredentialResult<()> {
+        self.clipboard
+            .set_text(text)
+            .map_err(|e| CredentialError::clipboard_unavailable(&e.to_string()))
+    }
+}
+
+// Only wipe the clipboard if it still holds what we put there, so anything the
+// user copied in the meantime survives.
+pub fn clear_if_unchanged(
+    backend: &mut impl ClipboardBackend,
+    expected: &str,
+) -> CredentialResult<bool> {
+    match backend.get_text() {
+        Ok(current) if current == expected => {
+            backend.set_text("")?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+// Blocks the calling thread for `timeout`. The process has to stay alive anyway
+// on platforms where the clipboard contents are served by their owner (X11),
+// which is why the clearing normally happens in a spawn_clearer helper.
+pub fn clear_after(
+    backend: &mut impl ClipboardBackend,
+    expected: &str,
+    timeout: Duration,
+) -> CredentialResult<bool> {
+    thread::sleep(timeout);
+    clear_if_unchanged(backend, expected)
+}
+
+// Hands the text to a background copy of crab that keeps serving it and clears
+// it after `timeout`, so the command that copied can exit straight away. The
+// text goes over a pipe, never through argv or the environment.
+pub fn spawn_clearer(text: &str, timeout: Duration) -> CredentialResult<()> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .args([HELPER_COMMAND, "--timeout", &timeout.as_secs().to_string()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // Its own process group, so a Ctrl+C in the terminal doesn't reach it.
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let mut child = command.spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    stdin.write_all(text.as_bytes())?;
+    Ok(())
+}
+
+// The helper's side of spawn_clearer: takes over the clipboard with the text
+// read from stdin, then clears it unless the user has copied something else.
+pub fn run_clearer(timeout: Duration) -> CredentialResult<()> {
+    let mut text = Zeroizing::new(String::new());
+    std::io::stdin().read_to_string(&mut text)?;
+    let mut clipboard = SystemClipboard::new()?;
+    clipboard.set_text(&text)?;
+    clear_after(&mut clipboard, &text, timeout)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockClipboard {
+        contents: String,
+    }
+
+    impl ClipboardBackend for MockClipboard {
+        fn get_text(&mut self) -> CredentialResult<String> {
+            Ok(self.contents.clone())
+        }
+
+        fn set_text(&mut self, text: &str) -> CredentialResult<()> {
+            self.contents = text.to_string();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn clear_after_timeout_wipes_our_secret() {
+        let mut clipboard = MockClipboard::default();
+        clipboard.set_text("secret").expect("set");
+
+        let cleared = clear_after(&mut clipboard, "secret", Duration::from_millis(10))
+            .expect("clear should succeed");
+
+        assert!(cleared);
+        assert_eq!(clipboard.contents, "");
+    }
+
+    #[test]
+    fn clear_keeps_contents_copied_by_user() {
+        let mut clipboard = MockClipboard::default();
+        clipboard.set_text("secret").expect("set");
+        clipboard.set_text("something else").expect("set");
+
+        let cleared = clear_if_unchanged(&mut clipboard, "secret").expect("clear should succeed");
+
+        assert!(!cleared);
+        assert_eq!(clipboard.contents, "something else");
+    }
+}
@@ -0,0 +1,63 @@
+use crate::error::{CredentialError, CredentialResult};
+
+// Returns the URL as it should be stored: trimmed, with `https://` added when
+// no scheme was given ("example.com"). Anything `url` can't parse, or that has
+// no host to open, is rejected. The input is otherwise kept as typed, since
+// `Url`'s own serialisation adds a trailing slash people didn't write.
+pub fn normalize_url(input: &str) -> CredentialResult<String> {
+    let input = input.trim();
+    if input.is_empty() || input.contains(char::is_whitespace) {
+        return Err(CredentialError::invalid_input(&format!(
+            "'{input}' is not a valid URL"
+        )));
+    }
+
+    let candidate = if input.contains("://") {
+        input.to_string()
+    } else {
+        format!("https://{input}")
+    };
+    let parsed = url::Url::parse(&candidate).map_err(|e| {
+        CredentialError::invalid_input(&format!("'{input}' is not a valid URL: {e}"))
+    })?;
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err(CredentialError::invalid_input(&format!(
+            "'{input}' has no host to open"
+        )));
+    }
+    Ok(candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_host_gets_https() {
+        assert_eq!(
+            normalize_url("example.com").expect("should normalize"),
+            "https://example.com"
+        );
+        assert_eq!(
+            normalize_url("  example.com/login ").expect("should normalize"),
+            "https://example.com/login"
+        );
+    }
+
+    #[test]
+    fn full_url_passes_through_unchanged() {
+        for url in ["https://example.com", "http://localhost:8080/admin?x=1"] {
+            assert_eq!(normalize_url(url).expect("should pass"), url);
+        }
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        for input in ["", "not a url", "???", "https://", "file:///etc/passwd"] {
+            assert!(
+                matches!(normalize_url(input), Err(CredentialError::InvalidInput(_))),
+                "{input:?} should be rejected"
+            );
+        }
+    }
+}
@@ -1,3 +1,19 @@
+pub mod clipboard;
+pub mod link;
+pub mod passphrase;
+pub mod password;
+pub mod policy;
+pub mod progress;
+pub mod pwned;
+pub mod qr;
+pub mod screen;
+pub mod strength;
+pub mod style;
+pub mod table;
 pub mod time;
+pub mod totp;
 
-pub use time::format_timestamp_local;
+pub use clipboard::{ClipboardBackend, SystemClipboard};
+pub use password::{generate_password, PasswordOptions};
+pub use strength::estimate_strength;
+pub use time::{format_timestamp_local, TimeFormat};
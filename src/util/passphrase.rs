@@ -0,0 +1,103 @@
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use std::collections::HashSet;
+
+pub const DEFAULT_SEPARATOR: &str = "-";
+
+// Accepts the EFF diceware format ("11111<TAB>abacus") as well as a plain list
+// with one word per line. Duplicates are rejected because they would make the
+// entropy estimate overstate the real strength.
+pub fn parse_wordlist(text: &str) -> Result<Vec<String>, String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut seen = HashSet::new();
+    for (index, line) in text.lines().enumerate() {
+        let mut fields = line.split_whitespace();
+        let word = match (fields.next(), fields.next()) {
+            (None, _) => continue,
+            (Some(roll), Some(word)) if roll.bytes().all(|b| (b'1'..=b'6').contains(&b)) => word,
+            (Some(word), None) => word,
+            (Some(_), Some(_)) => {
+                return Err(format!(
+                    "line {}: expected one word, or a dice roll and a word",
+                    index + 1
+                ))
+            }
+        };
+        if fields.next().is_some() {
+            return Err(format!("line {}: expected a single word", index + 1));
+        }
+        if !seen.insert(word) {
+            return Err(format!(
+                "line {}: '{word}' appears more than once",
+                index + 1
+            ));
+        }
+        words.push(word.to_string());
+    }
+    if words.len() < 2 {
+        return Err("a word list needs at least two distinct words".to_string());
+    }
+    Ok(words)
+}
+
+pub fn generate_passphrase(words: &[String], count: usize, separator: &str) -> String {
+    let mut rng = OsRng;
+    (0..count)
+        .map(|_| {
+            words
+                .choose(&mut rng)
+                .expect("word list is not empty")
+                .as_str()
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+// Each word is an independent, uniform pick from the list.
+pub fn entropy_bits(list_len: usize, count: usize) -> f64 {
+    count as f64 * (list_len as f64).log2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_wordlist_accepts_eff_and_plain_formats() {
+        let eff = parse_wordlist("11111\tabacus\n11112\tabdomen\n\n").expect("eff list");
+        let plain = parse_wordlist("abacus\n  abdomen  \n").expect("plain list");
+
+        assert_eq!(eff, vec!["abacus", "abdomen"]);
+        assert_eq!(plain, eff);
+    }
+
+    #[test]
+    fn parse_wordlist_rejects_duplicates_and_malformed_lines() {
+        let duplicate = parse_wordlist("apple\npear\napple\n").unwrap_err();
+        assert!(duplicate.contains("line 3"), "{duplicate}");
+
+        let extra = parse_wordlist("11111 apple pie\n").unwrap_err();
+        assert!(extra.contains("line 1"), "{extra}");
+
+        assert!(parse_wordlist("green apple\n").is_err());
+        assert!(parse_wordlist("lonely\n").is_err());
+    }
+
+    #[test]
+    fn passphrase_has_the_requested_words() {
+        let words = parse_wordlist("alpha\nbravo\ncharlie\n").expect("list");
+
+        let passphrase = generate_passphrase(&words, 6, ".");
+
+        let parts: Vec<&str> = passphrase.split('.').collect();
+        assert_eq!(parts.len(), 6);
+        assert!(parts.iter().all(|part| words.iter().any(|w| w == part)));
+    }
+
+    #[test]
+    fn entropy_grows_with_list_size_and_word_count() {
+        assert_eq!(entropy_bits(1024, 1), 10.0);
+        assert_eq!(entropy_bits(1024, 6), 60.0);
+        assert!((entropy_bits(7776, 6) - 77.55).abs() < 0.01);
+    }
+}
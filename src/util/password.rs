@@ -0,0 +1,161 @@
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+pub const DEFAULT_PASSWORD_LENGTH: usize = 20;
+
+const LOWERCASE: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &[u8] = b"0123456789";
+const SYMBOLS: &[u8] = b"!@#$%^&*()-_=+[]{};:,.<>?";
+// Characters that are easy to misread in some fonts.
+const AMBIGUOUS: &[u8] = b"Il1O0";
+
+#[derive(Debug, Clone, Copy)]
+pub struct PasswordOptions {
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    pub exclude_ambiguous: bool,
+}
+
+impl Default for PasswordOptions {
+    fn default() -> Self {
+        Self {
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+            exclude_ambiguous: false,
+        }
+    }
+}
+
+impl PasswordOptions {
+    fn classes(&self) -> Vec<Vec<u8>> {
+        [
+            (self.lowercase, LOWERCASE),
+            (self.uppercase, UPPERCASE),
+            (self.digits, DIGITS),
+            (self.symbols, SYMBOLS),
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, class)| {
+            class
+                .iter()
+                .copied()
+                .filter(|c| !(self.exclude_ambiguous && AMBIGUOUS.contains(c)))
+                .collect()
+        })
+        .collect()
+    }
+
+    pub fn has_classes(&self) -> bool {
+        self.lowercase || self.uppercase || self.digits || self.symbols
+    }
+}
+
+// Panics if every character class is disabled; check has_classes first.
+pub fn generate_password(len: usize, opts: PasswordOptions) -> String {
+    let mut rng = OsRng;
+    let classes = opts.classes();
+    assert!(!classes.is_empty(), "no character classes enabled");
+    let alphabet: Vec<u8> = classes.concat();
+
+    // Seed one character from each class so short passwords still cover them all,
+    // then fill the rest from the full alphabet and shuffle.
+    let mut password: Vec<u8> = classes
+        .iter()
+        .take(len)
+        .map(|class| class[rng.gen_range(0..class.len())])
+        .collect();
+    while password.len() < len {
+        password.push(alphabet[rng.gen_range(0..alphabet.len())]);
+    }
+    password.shuffle(&mut rng);
+
+    String::from_utf8(password).expect("alphabet is ASCII")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contains_any(password: &str, class: &[u8]) -> bool {
+        password.bytes().any(|b| class.contains(&b))
+    }
+
+    #[test]
+    fn generates_requested_length() {
+        for len in [4, 20, 64] {
+            assert_eq!(
+                generate_password(len, PasswordOptions::default()).len(),
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn covers_every_character_class() {
+        let password = generate_password(8, PasswordOptions::default());
+
+        assert!(contains_any(&password, LOWERCASE));
+        assert!(contains_any(&password, UPPERCASE));
+        assert!(contains_any(&password, DIGITS));
+        assert!(contains_any(&password, SYMBOLS));
+    }
+
+    #[test]
+    fn excludes_symbols_when_disabled() {
+        let password = generate_password(
+            64,
+            PasswordOptions {
+                symbols: false,
+                ..PasswordOptions::default()
+            },
+        );
+
+        assert!(!contains_any(&password, SYMBOLS));
+        assert!(password.bytes().all(|b| b.is_ascii_alphanumeric()));
+    }
+
+    #[test]
+    fn uses_only_the_enabled_classes() {
+        let opts = PasswordOptions {
+            lowercase: false,
+            uppercase: false,
+            symbols: false,
+            ..PasswordOptions::default()
+        };
+
+        let password = generate_password(32, opts);
+
+        assert!(password.bytes().all(|b| b.is_ascii_digit()));
+        assert!(!PasswordOptions {
+            digits: false,
+            ..opts
+        }
+        .has_classes());
+    }
+
+    #[test]
+    fn excludes_ambiguous_characters() {
+        let opts = PasswordOptions {
+            exclude_ambiguous: true,
+            ..PasswordOptions::default()
+        };
+
+        for _ in 0..20 {
+            assert!(!contains_any(&generate_password(64, opts), AMBIGUOUS));
+        }
+    }
+
+    #[test]
+    fn generated_passwords_differ() {
+        let opts = PasswordOptions::default();
+
+        assert_ne!(generate_password(20, opts), generate_password(20, opts));
+    }
+}
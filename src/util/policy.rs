@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+// Minimum requirements for stored secrets, set in the `[policy]` table of the
+// config. Everything is off by default. Unknown keys are an error rather than
+// a warning, since a misspelled rule would otherwise silently not apply.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    pub min_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+}
+
+impl Policy {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+// Returns every rule the secret breaks, not just the first, so the user can
+// fix them all at once. Length counts characters, not bytes.
+pub fn check_policy(secret: &str, policy: &Policy) -> Result<(), Vec<String>> {
+    let mut violations = Vec::new();
+
+    let length = secret.chars().count();
+    if length < policy.min_length {
+        violations.push(format!(
+            "must be at least {} characters (has {length})",
+            policy.min_length
+        ));
+    }
+    if policy.require_uppercase && !secret.chars().any(char::is_uppercase) {
+        violations.push("must contain an uppercase letter".to_string());
+    }
+    if policy.require_lowercase && !secret.chars().any(char::is_lowercase) {
+        violations.push("must contain a lowercase letter".to_string());
+    }
+    if policy.require_digit && !secret.chars().any(|c| c.is_ascii_digit()) {
+        violations.push("must contain a digit".to_string());
+    }
+    if policy.require_symbol
+        && !secret
+            .chars()
+            .any(|c| !c.is_alphanumeric() && !c.is_whitespace())
+    {
+        violations.push("must contain a symbol".to_string());
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strict() -> Policy {
+        Policy {
+            min_length: 12,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_symbol: true,
+        }
+    }
+
+    #[test]
+    fn default_policy_accepts_anything() {
+        assert!(Policy::default().is_empty());
+        for secret in ["", "a", "password", "ĉiuĵaŭde"] {
+            assert_eq!(check_policy(secret, &Policy::default()), Ok(()));
+        }
+    }
+
+    #[test]
+    fn compliant_secret_passes_strict_policy() {
+        assert!(!strict().is_empty());
+        assert_eq!(check_policy("Correct-Horse-42", &strict()), Ok(()));
+    }
+
+    #[test]
+    fn reports_every_violation_at_once() {
+        let violations = check_policy("", &strict()).expect_err("empty secret should fail");
+
+        assert_eq!(violations.len(), 5);
+        assert!(violations[0].contains("at least 12 characters (has 0)"));
+    }
+
+    #[test]
+    fn each_rule_is_checked_on_its_own() {
+        let cases = [
+            (
+                Policy {
+                    min_length: 8,
+                    ..Policy::default()
+                },
+                "short",
+                "at least 8",
+            ),
+            (
+                Policy {
+                    require_uppercase: true,
+                    ..Policy::default()
+                },
+                "lowercase only",
+                "uppercase",
+            ),
+            (
+                Policy {
+                    require_lowercase: true,
+                    ..Policy::default()
+                },
+                "UPPERCASE ONLY",
+                "lowercase",
+            ),
+            (
+                Policy {
+                    require_digit: true,
+                    ..Policy::default()
+                },
+                "no-digits-here",
+                "digit",
+            ),
+            (
+                Policy {
+                    require_symbol: true,
+                    ..Policy::default()
+                },
+                "letters and spaces 123",
+                "symbol",
+            ),
+        ];
+
+        for (policy, secret, expected) in cases {
+            let violations = check_policy(secret, &policy).expect_err(secret);
+            assert_eq!(violations.len(), 1, "{secret:?}: {violations:?}");
+            assert!(violations[0].contains(expected), "{violations:?}");
+        }
+    }
+
+    #[test]
+    fn length_counts_characters_not_bytes() {
+        let policy = Policy {
+            min_length: 4,
+            ..Policy::default()
+        };
+
+        assert_eq!(check_policy("ĉĝĥĵ", &policy), Ok(()));
+        assert!(check_policy("ĉĝĥ", &policy).is_err());
+    }
+
+    #[test]
+    fn non_ascii_letters_and_punctuation_count() {
+        let policy = Policy {
+            require_uppercase: true,
+            require_lowercase: true,
+            require_symbol: true,
+            ..Policy::default()
+        };
+
+        assert_eq!(check_policy("Ñandú€", &policy), Ok(()));
+    }
+}
@@ -0,0 +1,76 @@
+// Feedback and Ctrl+C handling for commands that walk the whole vault. Once a
+// bulk command installs the handler, SIGINT only raises a flag; the command
+// checks it between entries and bails out before anything is saved, so the
+// vault on disk is never half-updated.
+use crate::error::{CredentialError, CredentialResult};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+static INTERRUPTED: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+// Installed only once prompts are done: dialoguer's own Ctrl+C handling
+// still applies to anything asked before this.
+pub fn catch_interrupts() {
+    INTERRUPTED.get_or_init(|| {
+        let flag = Arc::new(AtomicBool::new(false));
+        let handler_flag = Arc::clone(&flag);
+        // Without a handler Ctrl+C simply kills the process, which is still
+        // safe because nothing has been written yet.
+        let _ = ctrlc::set_handler(move || handler_flag.store(true, Ordering::SeqCst));
+        flag
+    });
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED
+        .get()
+        .is_some_and(|flag| flag.load(Ordering::SeqCst))
+}
+
+pub fn check_interrupted() -> CredentialResult<()> {
+    if interrupted() {
+        Err(CredentialError::Interrupted)
+    } else {
+        Ok(())
+    }
+}
+
+// Drawn on stderr so it never mixes with --json or piped output, and hidden
+// entirely when stderr is not a terminal.
+pub fn progress_bar(len: usize, message: &'static str) -> ProgressBar {
+    let target = if std::io::stderr().is_terminal() {
+        ProgressDrawTarget::stderr()
+    } else {
+        ProgressDrawTarget::hidden()
+    };
+    let style = ProgressStyle::with_template("{msg} [{bar:30}] {pos}/{len}")
+        .expect("progress template is valid")
+        .progress_chars("=> ");
+    ProgressBar::with_draw_target(Some(len as u64), target)
+        .with_style(style)
+        .with_message(message)
+}
+
+// Advances the bar and reports whether the user has asked to stop.
+pub fn tick(bar: &ProgressBar) -> CredentialResult<()> {
+    bar.inc(1);
+    check_interrupted()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nothing_is_interrupted_before_a_signal() {
+        catch_interrupts();
+        assert!(!interrupted());
+        assert!(check_interrupted().is_ok());
+
+        let bar = progress_bar(2, "Checking");
+        assert!(tick(&bar).is_ok());
+        assert_eq!(bar.position(), 1);
+    }
+}
@@ -0,0 +1,196 @@
+// Breach lookups against Have I Been Pwned's k-anonymity range API: only the
+// first five hex characters of a secret's SHA-1 ever leave the machine.
+use crate::error::{CredentialError, CredentialResult};
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub const OFFLINE_ENV: &str = "CRAB_OFFLINE";
+
+const RANGE_API: &str = "https://api.pwnedpasswords.com/range/";
+const PREFIX_LEN: usize = 5;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub trait RangeClient {
+    // Returns the raw "SUFFIX:COUNT" lines for hashes starting with `prefix`.
+    fn fetch_range(&self, prefix: &str) -> CredentialResult<String>;
+}
+
+pub struct HibpClient {
+    agent: ureq::Agent,
+}
+
+impl HibpClient {
+    pub fn new() -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("crab/", env!("CARGO_PKG_VERSION")))
+            .build();
+        Self { agent }
+    }
+}
+
+impl Default for HibpClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RangeClient for HibpClient {
+    fn fetch_range(&self, prefix: &str) -> CredentialResult<String> {
+        // Padding hides the real size of the response from on-path observers.
+        self.agent
+            .get(&format!("{RANGE_API}{prefix}"))
+            .set("Add-Padding", "true")
+            .call()
+            .map_err(|e| network_error(&e.to_string()))?
+            .into_string()
+            .map_err(|e| network_error(&e.to_string()))
+    }
+}
+
+fn network_error(reason: &str) -> CredentialError {
+    CredentialError::IoError(std::io::Error::other(format!(
+        "Breach lookup failed: {reason}"
+    )))
+}
+
+pub fn network_disabled(offline: bool) -> bool {
+    offline || std::env::var_os(OFFLINE_ENV).is_some_and(|value| !value.is_empty())
+}
+
+// Builds the client only when the network is allowed, so an offline run never
+// gets as far as a request.
+pub fn online_client<C: RangeClient>(
+    offline: bool,
+    build: impl FnOnce() -> C,
+) -> CredentialResult<C> {
+    if network_disabled(offline) {
+        return Err(CredentialError::invalid_input(&format!(
+            "Network access is disabled (--offline, offline = true in the config, or {OFFLINE_ENV} is set)"
+        )));
+    }
+    Ok(build())
+}
+
+pub fn sha1_hex(secret: &str) -> String {
+    Sha1::digest(secret.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect()
+}
+
+fn count_in_range(range: &str, suffix: &str) -> u64 {
+    range
+        .lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .find(|(candidate, _)| candidate.eq_ignore_ascii_case(suffix))
+        .and_then(|(_, count)| count.parse().ok())
+        .unwrap_or(0)
+}
+
+// Looks up each secret, fetching every hash prefix once. Returns how often each
+// secret has been seen in breaches, in input order (0 = not found). `after_each`
+// runs once per secret and stops the lookups early if it fails.
+pub fn breach_counts(
+    client: &impl RangeClient,
+    secrets: &[&str],
+    mut after_each: impl FnMut() -> CredentialResult<()>,
+) -> CredentialResult<Vec<u64>> {
+    let mut ranges: HashMap<String, String> = HashMap::new();
+    let mut counts = Vec::with_capacity(secrets.len());
+
+    for secret in secrets {
+        let hash = sha1_hex(secret);
+        let (prefix, suffix) = hash.split_at(PREFIX_LEN);
+        if !ranges.contains_key(prefix) {
+            ranges.insert(prefix.to_string(), client.fetch_range(prefix)?);
+        }
+        counts.push(count_in_range(&ranges[prefix], suffix));
+        after_each()?;
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    // SHA-1("password") = 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+    struct StubClient {
+        requested: RefCell<Vec<String>>,
+    }
+
+    impl RangeClient for StubClient {
+        fn fetch_range(&self, prefix: &str) -> CredentialResult<String> {
+            self.requested.borrow_mut().push(prefix.to_string());
+            Ok("1E4C9B93F3F0682250B6CF8331B7EE68FD8:3861493\r\n\
+                0000000000000000000000000000000000A:0\r\n"
+                .to_string())
+        }
+    }
+
+    #[test]
+    fn sha1_hex_is_uppercase() {
+        assert_eq!(
+            sha1_hex("password"),
+            "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8"
+        );
+    }
+
+    #[test]
+    fn only_prefixes_are_sent_and_each_once() {
+        let client = StubClient {
+            requested: RefCell::new(Vec::new()),
+        };
+
+        let counts = breach_counts(&client, &["password", "password", "correct horse"], || {
+            Ok(())
+        })
+        .expect("lookup should succeed");
+
+        assert_eq!(counts[0], 3861493);
+        assert_eq!(counts[1], 3861493);
+        assert_eq!(counts[2], 0);
+        let requested = client.requested.borrow();
+        assert_eq!(requested.len(), 2);
+        assert!(requested.iter().all(|prefix| prefix.len() == PREFIX_LEN));
+        assert_eq!(requested[0], "5BAA6");
+    }
+
+    #[test]
+    fn failing_callback_stops_further_lookups() {
+        let client = StubClient {
+            requested: RefCell::new(Vec::new()),
+        };
+
+        let result = breach_counts(&client, &["password", "correct horse"], || {
+            Err(CredentialError::Interrupted)
+        });
+
+        assert!(matches!(result, Err(CredentialError::Interrupted)));
+        assert_eq!(client.requested.borrow().len(), 1);
+    }
+
+    #[test]
+    fn offline_never_builds_a_client() {
+        let built = std::cell::Cell::new(false);
+
+        let result = online_client(true, || {
+            built.set(true);
+            StubClient {
+                requested: RefCell::new(Vec::new()),
+            }
+        });
+
+        assert!(matches!(result, Err(CredentialError::InvalidInput(_))));
+        assert!(!built.get());
+    }
+
+    #[test]
+    fn padding_lines_do_not_match() {
+        assert_eq!(count_in_range("ABC:0\nDEF:12\n", "abc"), 0);
+        assert_eq!(count_in_range("ABC:0\nDEF:12\n", "def"), 12);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::error::{CredentialError, CredentialResult};
+use image::GrayImage;
+use qrcode::render::unicode::Dense1x2;
+use qrcode::QrCode;
+use std::path::Path;
+
+// Two modules per character cell using half blocks. Colours are inverted so the
+// code scans on the usual light-on-dark terminal.
+pub fn render_qr(data: &str) -> CredentialResult<String> {
+    let code = QrCode::new(data.as_bytes())
+        .map_err(|e| CredentialError::invalid_input(&format!("Cannot encode as QR code: {e}")))?;
+    Ok(code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build())
+}
+
+// The network-join format phone cameras understand. WPA also covers WPA2 and
+// WPA3 personal networks; the special characters are backslash-escaped.
+pub fn wifi_payload(ssid: &str, password: &str) -> String {
+    fn escape(value: &str) -> String {
+        let mut escaped = String::with_capacity(value.len());
+        for c in value.chars() {
+            if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+    format!("WIFI:T:WPA;S:{};P:{};;", escape(ssid), escape(password))
+}
+
+// Reads the text of the first QR code found in a PNG or JPEG, such as a
+// screenshot of a service's 2FA enrolment page.
+pub fn decode_qr_image(path: &Path) -> CredentialResult<String> {
+    let image = image::open(path).map_err(|e| {
+        CredentialError::invalid_input(&format!("Cannot read image {}: {e}", path.display()))
+    })?;
+    decode_qr(image.to_luma8()).ok_or_else(|| {
+        CredentialError::invalid_input(&format!("No readable QR code in {}", path.display()))
+    })
+}
+
+fn decode_qr(image: GrayImage) -> Option<String> {
+    let mut prepared = rqrr::PreparedImage::prepare(image);
+    prepared
+        .detect_grids()
+        .into_iter()
+        .find_map(|grid| grid.decode().ok().map(|(_, content)| content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qrcode::Color;
+
+    // Draws a code the way a screenshot would show it: dark on light, with
+    // several pixels per module and a quiet zone.
+    fn qr_image(data: &str) -> GrayImage {
+        const SCALE: u32 = 4;
+        const MARGIN: u32 = 4;
+        let code = QrCode::new(data.as_bytes()).expect("should encode");
+        let width = code.width() as u32;
+        let colors = code.to_colors();
+        let size = (width + 2 * MARGIN) * SCALE;
+        GrayImage::from_fn(size, size, |x, y| {
+            let (mx, my) = (x / SCALE, y / SCALE);
+            let inside =
+                (MARGIN..MARGIN + width).contains(&mx) && (MARGIN..MARGIN + width).contains(&my);
+            let dark =
+                inside && colors[((my - MARGIN) * width + (mx - MARGIN)) as usize] == Color::Dark;
+            image::Luma([if dark { 0 } else { 255 }])
+        })
+    }
+
+    #[test]
+    fn renders_square_block_art() {
+        let art = render_qr("otpauth://totp/x:y?secret=JBSWY3DP").expect("should encode");
+
+        let lines: Vec<&str> = art.lines().collect();
+        let width = lines[0].chars().count();
+        assert!(lines.iter().all(|line| line.chars().count() == width));
+        // Each text row holds two module rows.
+        assert_eq!(lines.len(), width.div_ceil(2));
+    }
+
+    #[test]
+    fn oversized_data_is_an_error() {
+        let data = "x".repeat(8000);
+
+        assert!(matches!(
+            render_qr(&data),
+            Err(CredentialError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn wifi_payload_escapes_special_characters() {
+        assert_eq!(
+            wifi_payload("Home Net", "hunter2"),
+            "WIFI:T:WPA;S:Home Net;P:hunter2;;"
+        );
+        assert_eq!(
+            wifi_payload("cafe;guest", r#"a:b,c\d"e"#),
+            r#"WIFI:T:WPA;S:cafe\;guest;P:a\:b\,c\\d\"e;;"#
+        );
+    }
+
+    #[test]
+    fn decodes_a_saved_qr_image() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("code.png");
+        let uri = "otpauth://totp/Acme:alice?secret=JBSWY3DP&issuer=Acme";
+        qr_image(uri).save(&path).expect("save png");
+
+        assert_eq!(decode_qr_image(&path).expect("should decode"), uri);
+    }
+
+    #[test]
+    fn images_without_a_code_are_errors() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let blank = dir.path().join("blank.png");
+        GrayImage::from_pixel(64, 64, image::Luma([255]))
+            .save(&blank)
+            .expect("save png");
+
+        assert!(matches!(
+            decode_qr_image(&blank),
+            Err(CredentialError::InvalidInput(_))
+        ));
+        assert!(decode_qr_image(&dir.path().join("missing.png")).is_err());
+    }
+}
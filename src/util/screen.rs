@@ -0,0 +1,38 @@
+use std::io::{IsTerminal, Write};
+use std::thread;
+use std::time::Duration;
+
+// Erase the visible screen, then the scrollback (an xterm extension most
+// terminals honour), and home the cursor.
+const CLEAR_SCREEN_AND_SCROLLBACK: &str = "\x1b[2J\x1b[3J\x1b[H";
+
+pub fn should_scrub(timeout: u64, is_tty: bool) -> bool {
+    timeout > 0 && is_tty
+}
+
+// Blocks for `timeout`, then clears the terminal so a revealed secret doesn't
+// stay on screen. Returns false without waiting when stdout is not a terminal,
+// since escape codes would only corrupt piped output.
+pub fn scrub_after(timeout: u64) -> std::io::Result<bool> {
+    let mut stdout = std::io::stdout();
+    if !should_scrub(timeout, stdout.is_terminal()) {
+        return Ok(false);
+    }
+
+    thread::sleep(Duration::from_secs(timeout));
+    stdout.write_all(CLEAR_SCREEN_AND_SCROLLBACK.as_bytes())?;
+    stdout.flush()?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrubs_only_interactive_output_with_a_timeout() {
+        assert!(should_scrub(10, true));
+        assert!(!should_scrub(10, false));
+        assert!(!should_scrub(0, true));
+    }
+}
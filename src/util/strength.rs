@@ -0,0 +1,116 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strength {
+    VeryWeak,
+    Weak,
+    Fair,
+    Strong,
+    VeryStrong,
+}
+
+impl Strength {
+    pub fn indicator(&self) -> &'static str {
+        match self {
+            Strength::VeryWeak | Strength::Weak => "⚠️",
+            Strength::Fair => "🟡",
+            Strength::Strong | Strength::VeryStrong => "✅",
+        }
+    }
+}
+
+impl fmt::Display for Strength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Strength::VeryWeak => "Very Weak",
+            Strength::Weak => "Weak",
+            Strength::Fair => "Fair",
+            Strength::Strong => "Strong",
+            Strength::VeryStrong => "Very Strong",
+        };
+        write!(f, "{label}")
+    }
+}
+
+// Secrets scoring below this are worth a second thought before saving.
+pub const MIN_ACCEPTABLE_STRENGTH: Strength = Strength::Fair;
+
+// A rough estimate from length and character-class diversity; it does not
+// know about dictionary words, so it errs on the generous side.
+pub fn estimate_strength(secret: &str) -> Strength {
+    let length = secret.chars().count();
+
+    let mut distinct: Vec<char> = secret.chars().collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    if distinct.len() <= 2 {
+        return Strength::VeryWeak;
+    }
+
+    let classes = [
+        secret.chars().any(|c| c.is_lowercase()),
+        secret.chars().any(|c| c.is_uppercase()),
+        secret.chars().any(|c| c.is_ascii_digit()),
+        secret.chars().any(|c| !c.is_alphanumeric()),
+    ]
+    .iter()
+    .filter(|present| **present)
+    .count();
+
+    let length_score = match length {
+        0..=7 => 0,
+        8..=11 => 1,
+        12..=15 => 2,
+        _ => 3,
+    };
+    let class_score = match classes {
+        0 | 1 => 0,
+        2 | 3 => 1,
+        _ => 2,
+    };
+
+    match length_score + class_score {
+        0 => Strength::VeryWeak,
+        1 => Strength::Weak,
+        2 => Strength::Fair,
+        3 => Strength::Strong,
+        _ => Strength::VeryStrong,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_example_passwords() {
+        let cases = [
+            ("", Strength::VeryWeak),
+            ("abc", Strength::VeryWeak),
+            ("aaaaaaaaaaaaaaaaaaaa", Strength::VeryWeak),
+            ("hunter2", Strength::Weak),
+            ("password", Strength::Weak),
+            ("Password1", Strength::Fair),
+            ("correcthorsebattery", Strength::Strong),
+            ("Tr0ub4dor&3xyz!", Strength::VeryStrong),
+            ("q8#Lm2!vZr9@Wk4$", Strength::VeryStrong),
+        ];
+
+        for (secret, expected) in cases {
+            assert_eq!(estimate_strength(secret), expected, "secret: {secret:?}");
+        }
+    }
+
+    #[test]
+    fn strength_levels_are_ordered() {
+        assert!(Strength::VeryWeak < Strength::Weak);
+        assert!(Strength::Fair < Strength::VeryStrong);
+        assert!(estimate_strength("password") < MIN_ACCEPTABLE_STRENGTH);
+    }
+
+    #[test]
+    fn labels_are_human_readable() {
+        assert_eq!(Strength::VeryWeak.to_string(), "Very Weak");
+        assert_eq!(Strength::VeryStrong.to_string(), "Very Strong");
+    }
+}
@@ -0,0 +1,140 @@
+// Decorated output (emoji prefixes, ANSI colour) is dropped when NO_COLOR is
+// set, --no-color is passed, or stdout is not a terminal. Only the decoration
+// at the start of a line is removed, so user data is printed unchanged.
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub const NO_COLOR_ENV: &str = "NO_COLOR";
+
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+// Per https://no-color.org, NO_COLOR counts only when set to a non-empty value.
+pub fn plain_requested(no_color_flag: bool, no_color_env: Option<OsString>, is_tty: bool) -> bool {
+    no_color_flag || no_color_env.is_some_and(|value| !value.is_empty()) || !is_tty
+}
+
+pub fn init(no_color_flag: bool) {
+    let plain = plain_requested(
+        no_color_flag,
+        std::env::var_os(NO_COLOR_ENV),
+        std::io::stdout().is_terminal(),
+    );
+    PLAIN.store(plain, Ordering::Relaxed);
+}
+
+pub fn is_plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+fn is_decoration(c: char) -> bool {
+    matches!(c,
+        '\u{2100}'..='\u{2BFF}'      // letterlike symbols, arrows, dingbats
+        | '\u{1F000}'..='\u{1FAFF}'  // emoji blocks
+        | '\u{FE0F}'                 // emoji presentation selector
+        | '\u{200D}'                 // zero-width joiner
+    )
+}
+
+fn strip_ansi(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' && chars.peek() == Some(&'[') {
+            chars.next();
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+            continue;
+        }
+        output.push(c);
+    }
+    output
+}
+
+pub fn render_plain(line: &str) -> String {
+    let line = strip_ansi(line);
+    let body = line.trim_start();
+    let indent = &line[..line.len() - body.len()];
+
+    let rest = body.trim_start_matches(is_decoration);
+    if rest.len() == body.len() {
+        return line;
+    }
+    format!("{indent}{}", rest.trim_start())
+}
+
+pub fn render(line: &str) -> Cow<'_, str> {
+    if is_plain() {
+        Cow::Owned(render_plain(line))
+    } else {
+        Cow::Borrowed(line)
+    }
+}
+
+// Inline indicators (e.g. after a strength label) that vanish in plain mode.
+pub fn symbol(symbol: &'static str) -> &'static str {
+    if is_plain() {
+        ""
+    } else {
+        symbol
+    }
+}
+
+macro_rules! outln {
+    ($($arg:tt)*) => {
+        println!("{}", $crate::util::style::render(&format!($($arg)*)))
+    };
+}
+
+macro_rules! errln {
+    ($($arg:tt)*) => {
+        eprintln!("{}", $crate::util::style::render(&format!($($arg)*)))
+    };
+}
+
+pub(crate) use {errln, outln};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_color_env_flag_or_pipe_selects_plain_output() {
+        assert!(plain_requested(false, Some(OsString::from("1")), true));
+        assert!(plain_requested(true, None, true));
+        assert!(plain_requested(false, None, false));
+        assert!(!plain_requested(false, Some(OsString::new()), true));
+        assert!(!plain_requested(false, None, true));
+    }
+
+    #[test]
+    fn plain_rendering_drops_emoji_and_escape_sequences() {
+        let cases = [
+            ("✅ Credential added", "Credential added"),
+            ("⚠️  Weak passwords:", "Weak passwords:"),
+            ("🗑️  Database deleted", "Database deleted"),
+            ("  💡 Try 'crab list'", "  Try 'crab list'"),
+            ("\u{1b}[31m❌ Error\u{1b}[0m", "Error"),
+            ("ℹ️  No active session.", "No active session."),
+            ("  1. github", "  1. github"),
+        ];
+
+        for (input, expected) in cases {
+            let rendered = render_plain(input);
+            assert_eq!(rendered, expected);
+            assert!(!rendered.contains('\u{1b}'));
+        }
+    }
+
+    #[test]
+    fn plain_rendering_keeps_user_data_after_the_prefix() {
+        assert_eq!(
+            render_plain("✅ Credential for '🔥 fire' added"),
+            "Credential for '🔥 fire' added"
+        );
+    }
+}
@@ -0,0 +1,61 @@
+// Renders rows as left-aligned columns separated by two spaces, with a rule
+// under the header. Widths are measured in characters, not bytes.
+pub fn render_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let render_row = |cells: Vec<&str>| -> String {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        line.join("  ").trim_end().to_string()
+    };
+
+    let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+    let mut lines = vec![
+        render_row(headers.to_vec()),
+        render_row(rule.iter().map(String::as_str).collect()),
+    ];
+    for row in rows {
+        lines.push(render_row(row.iter().map(String::as_str).collect()));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligns_columns_to_widest_cell() {
+        let rows = vec![
+            vec!["github".to_string(), "alice".to_string()],
+            vec!["aws".to_string(), "root-account".to_string()],
+        ];
+
+        let table = render_table(&["SERVICE", "ACCOUNT"], &rows);
+
+        assert_eq!(
+            table,
+            "SERVICE  ACCOUNT\n\
+             -------  ------------\n\
+             github   alice\n\
+             aws      root-account"
+        );
+    }
+
+    #[test]
+    fn measures_width_in_characters() {
+        let rows = vec![vec!["café".to_string(), "x".to_string()]];
+
+        let table = render_table(&["S", "A"], &rows);
+
+        assert_eq!(table.lines().nth(2), Some("café  x"));
+    }
+}
@@ -1,25 +1,182 @@
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveDate, SecondsFormat};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-pub fn format_timestamp_local(timestamp: u64) -> String {
-    match DateTime::from_timestamp(timestamp as i64, 0) {
-        Some(utc_datetime) => {
-            let local_datetime: DateTime<Local> = utc_datetime.with_timezone(&Local);
-            local_datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeFormat {
+    #[default]
+    Default,
+    Rfc3339,
+    Relative,
+}
+
+pub fn format_timestamp_local(timestamp: u64, format: TimeFormat) -> String {
+    let Some(utc_datetime) = DateTime::from_timestamp(timestamp as i64, 0) else {
+        return format!("Invalid timestamp: {timestamp}");
+    };
+    let local_datetime: DateTime<Local> = utc_datetime.with_timezone(&Local);
+
+    match format {
+        TimeFormat::Default => local_datetime.format("%Y-%m-%d %H:%M:%S").to_string(),
+        TimeFormat::Rfc3339 => local_datetime.to_rfc3339_opts(SecondsFormat::Secs, false),
+        TimeFormat::Relative => {
+            let now = now_unix();
+            format_relative(timestamp, now)
         }
-        None => format!("Invalid timestamp: {timestamp}"),
     }
 }
 
+// Seconds since the Unix epoch. A clock set before 1970 reads as 0 instead
+// of panicking, so timestamps taken then just look very old.
+pub fn now_unix() -> u64 {
+    unix_secs(SystemTime::now())
+}
+
+pub fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn format_relative(timestamp: u64, now: u64) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+    const MONTH: u64 = 30 * DAY;
+    const YEAR: u64 = 365 * DAY;
+
+    // Timestamps slightly in the future (clock skew) read as "just now" too.
+    let delta = now.saturating_sub(timestamp);
+    let (count, unit) = match delta {
+        0..MINUTE => return "just now".to_string(),
+        MINUTE..HOUR => (delta / MINUTE, "minute"),
+        HOUR..DAY => (delta / HOUR, "hour"),
+        DAY..MONTH => (delta / DAY, "day"),
+        MONTH..YEAR => (delta / MONTH, "month"),
+        _ => (delta / YEAR, "year"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{count} {unit}{plural} ago")
+}
+
+// Parses spans like "90s", "30m", "24h", "7d" or "2w".
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let input = input.trim();
+    let split = input.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = input.split_at(split);
+    let amount: u64 = amount.parse().ok()?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(amount.checked_mul(unit_secs)?))
+}
+
+// Accepts a local calendar date (YYYY-MM-DD, taken as local midnight) or a full
+// RFC 3339 timestamp, returning Unix seconds.
+pub fn parse_date(input: &str) -> Option<u64> {
+    let input = input.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(input) {
+        return u64::try_from(datetime.timestamp()).ok();
+    }
+
+    let midnight = NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        .ok()?
+        .and_hms_opt(0, 0, 0)?
+        .and_local_timezone(Local)
+        .earliest()?;
+    u64::try_from(midnight.timestamp()).ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn unix_secs_saturates_before_the_epoch() {
+        assert_eq!(unix_secs(UNIX_EPOCH + Duration::from_secs(5)), 5);
+        assert_eq!(unix_secs(UNIX_EPOCH - Duration::from_secs(5)), 0);
+        assert!(now_unix() > 0);
+    }
+
     #[test]
     fn test_format_timestamp_local() {
         let timestamp = 1704067200;
-        let formatted = format_timestamp_local(timestamp);
+        let formatted = format_timestamp_local(timestamp, TimeFormat::Default);
+
+        assert!(formatted.contains("2024") || formatted.contains("2023"));
+        assert_eq!(formatted.len(), "2024-01-01 00:00:00".len());
+    }
+
+    #[test]
+    fn rfc3339_format_parses_back_to_same_instant() {
+        let timestamp = 1704067200;
+        let formatted = format_timestamp_local(timestamp, TimeFormat::Rfc3339);
+
+        let parsed = DateTime::parse_from_rfc3339(&formatted).expect("valid RFC 3339");
+        assert_eq!(parsed.timestamp(), timestamp as i64);
+    }
+
+    #[test]
+    fn relative_format_describes_delta_from_now() {
+        let now = 1_000_000_000;
+        let cases = [
+            (now, "just now"),
+            (now + 30, "just now"),
+            (now - 59, "just now"),
+            (now - 60, "1 minute ago"),
+            (now - 2 * 3600, "2 hours ago"),
+            (now - 3 * 86400, "3 days ago"),
+            (now - 45 * 86400, "1 month ago"),
+            (now - 800 * 86400, "2 years ago"),
+        ];
+
+        for (timestamp, expected) in cases {
+            assert_eq!(format_relative(timestamp, now), expected);
+        }
+    }
+
+    #[test]
+    fn parse_duration_accepts_units() {
+        assert_eq!(parse_duration("90s"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30m"), Some(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_duration("24h"), Some(Duration::from_secs(24 * 3600)));
+        assert_eq!(parse_duration("7d"), Some(Duration::from_secs(7 * 86400)));
+        assert_eq!(
+            parse_duration(" 2w "),
+            Some(Duration::from_secs(14 * 86400))
+        );
+    }
+
+    #[test]
+    fn parse_duration_rejects_malformed_input() {
+        for input in ["", "7", "d", "7x", "-1d", "1.5h", "7dd"] {
+            assert_eq!(parse_duration(input), None, "input: {input:?}");
+        }
+    }
 
-        assert!(formatted.contains("2024"));
-        assert!(formatted.len() > 10);
+    #[test]
+    fn parse_date_accepts_dates_and_rfc3339() {
+        assert_eq!(parse_date("2024-01-01T00:00:00Z"), Some(1704067200));
+
+        let midnight = parse_date("2024-01-01").expect("date should parse");
+        assert_eq!(
+            format_timestamp_local(midnight, TimeFormat::Default),
+            "2024-01-01 00:00:00"
+        );
+
+        assert_eq!(parse_date("yesterday"), None);
+        assert_eq!(parse_date("2024-13-01"), None);
+    }
+
+    #[test]
+    fn invalid_timestamp_is_reported() {
+        assert!(format_timestamp_local(i64::MAX as u64, TimeFormat::Default).starts_with("Invalid"));
     }
 }
@@ -0,0 +1,420 @@
+use crate::util::time::now_unix;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const DEFAULT_DIGITS: usize = 6;
+const DEFAULT_PERIOD_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TotpAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl fmt::Display for TotpAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TotpAlgorithm::Sha1 => "SHA1",
+            TotpAlgorithm::Sha256 => "SHA256",
+            TotpAlgorithm::Sha512 => "SHA512",
+        })
+    }
+}
+
+impl From<TotpAlgorithm> for Algorithm {
+    fn from(algorithm: TotpAlgorithm) -> Self {
+        match algorithm {
+            TotpAlgorithm::Sha1 => Algorithm::SHA1,
+            TotpAlgorithm::Sha256 => Algorithm::SHA256,
+            TotpAlgorithm::Sha512 => Algorithm::SHA512,
+        }
+    }
+}
+
+// How codes are derived from an entry's secret. Most services use the
+// defaults (SHA1, 6 digits, 30 seconds); the rest say so when enrolling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TotpParams {
+    pub algorithm: TotpAlgorithm,
+    pub digits: usize,
+    pub period: u64,
+}
+
+impl Default for TotpParams {
+    fn default() -> Self {
+        Self {
+            algorithm: TotpAlgorithm::default(),
+            digits: DEFAULT_DIGITS,
+            period: DEFAULT_PERIOD_SECS,
+        }
+    }
+}
+
+impl TotpParams {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(6..=8).contains(&self.digits) {
+            return Err(format!(
+                "TOTP codes must have 6 to 8 digits, not {}",
+                self.digits
+            ));
+        }
+        if self.period == 0 {
+            return Err("The TOTP period must be at least one second".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Authenticator apps show secrets in spaced, lowercase groups; store them canonically.
+pub fn normalize_secret(secret: &str) -> String {
+    secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=')
+        .collect::<String>()
+        .to_uppercase()
+}
+
+pub fn validate_secret(secret: &str) -> Result<(), String> {
+    let secret = normalize_secret(secret);
+    match Secret::Encoded(secret).to_bytes() {
+        Ok(bytes) if !bytes.is_empty() => Ok(()),
+        _ => Err("Not a valid base32 TOTP secret".to_string()),
+    }
+}
+
+fn build(secret: &str, params: TotpParams) -> Result<TOTP, String> {
+    params.validate()?;
+    let bytes = Secret::Encoded(normalize_secret(secret))
+        .to_bytes()
+        .map_err(|_| "Not a valid base32 TOTP secret".to_string())?;
+    // Many services still hand out 80-bit secrets, below the RFC's recommended minimum.
+    Ok(TOTP::new_unchecked(
+        params.algorithm.into(),
+        params.digits,
+        1,
+        params.period,
+        bytes,
+    ))
+}
+
+// Returns the code valid at `unix_time` and the seconds until it expires.
+pub fn code_at(secret: &str, params: TotpParams, unix_time: u64) -> Result<(String, u64), String> {
+    let totp = build(secret, params)?;
+    Ok((
+        totp.generate(unix_time),
+        params.period - unix_time % params.period,
+    ))
+}
+
+pub fn current_code(secret: &str, params: TotpParams) -> Result<(String, u64), String> {
+    let now = now_unix();
+    code_at(secret, params, now)
+}
+
+// RFC 3986 percent-encoding: everything but unreserved characters.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+// Key URI understood by authenticator apps:
+// otpauth://totp/Issuer:account?secret=...&issuer=Issuer&...
+pub fn provisioning_uri(secret: &str, params: TotpParams, issuer: &str, account: &str) -> String {
+    let issuer = percent_encode(issuer);
+    format!(
+        "otpauth://totp/{issuer}:{}?secret={}&issuer={issuer}&algorithm={}&digits={}&period={}",
+        percent_encode(account),
+        normalize_secret(secret),
+        params.algorithm,
+        params.digits,
+        params.period,
+    )
+}
+
+// A key URI taken from an authenticator app, split into what an entry stores.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtpAuth {
+    pub secret: String,
+    pub params: TotpParams,
+    pub issuer: Option<String>,
+    pub account: Option<String>,
+}
+
+fn percent_decode(value: &str) -> Result<String, String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| format!("Bad percent-encoding in '{value}'"))?;
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| format!("'{value}' is not valid UTF-8"))
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+// Reads otpauth://totp/Issuer:account?secret=...; the issuer parameter wins
+// over the label prefix, and anything left out keeps its standard default.
+pub fn parse_otpauth_uri(uri: &str) -> Result<OtpAuth, String> {
+    let url = url::Url::parse(uri.trim()).map_err(|e| format!("Not a valid otpauth URI: {e}"))?;
+    match (url.scheme(), url.host_str()) {
+        ("otpauth", Some("totp")) => {}
+        ("otpauth", Some("hotp")) => {
+            return Err("Counter-based (HOTP) codes are not supported".to_string())
+        }
+        ("otpauth-migration", _) => {
+            return Err(
+                "Bulk export links are not supported; export or show each account's QR code individually"
+                    .to_string(),
+            )
+        }
+        _ => return Err("Expected a URI starting with otpauth://totp/".to_string()),
+    }
+
+    let label = percent_decode(url.path().trim_start_matches('/'))?;
+    let (mut issuer, account) = match label.split_once(':') {
+        Some((issuer, account)) => (non_empty(issuer), non_empty(account)),
+        None => (None, non_empty(&label)),
+    };
+
+    let mut secret = None;
+    let mut params = TotpParams::default();
+    for (key, value) in url.query_pairs() {
+        match key.to_ascii_lowercase().as_str() {
+            "secret" => secret = non_empty(&value),
+            "issuer" => issuer = non_empty(&value).or(issuer),
+            "algorithm" => {
+                params.algorithm = TotpAlgorithm::from_str(&value, true)
+                    .map_err(|_| format!("Unsupported TOTP algorithm '{value}'"))?
+            }
+            "digits" => {
+                params.digits = value
+                    .parse()
+                    .map_err(|_| format!("Invalid digits '{value}'"))?
+            }
+            "period" => {
+                params.period = value
+                    .parse()
+                    .map_err(|_| format!("Invalid period '{value}'"))?
+            }
+            _ => {}
+        }
+    }
+
+    let secret = secret.ok_or("The URI has no secret parameter")?;
+    validate_secret(&secret)?;
+    params.validate()?;
+    Ok(OtpAuth {
+        secret: normalize_secret(&secret),
+        params,
+        issuer,
+        account,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 test key "12345678901234567890" in base32.
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+    // The 32- and 64-byte keys RFC 6238 uses for its SHA256 and SHA512 vectors.
+    const RFC_SECRET_SHA256: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZA";
+    const RFC_SECRET_SHA512: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNA";
+
+    fn params(algorithm: TotpAlgorithm, digits: usize) -> TotpParams {
+        TotpParams {
+            algorithm,
+            digits,
+            ..TotpParams::default()
+        }
+    }
+
+    #[test]
+    fn matches_rfc_6238_vectors() {
+        assert_eq!(
+            code_at(RFC_SECRET, TotpParams::default(), 59).unwrap(),
+            ("287082".to_string(), 1)
+        );
+        assert_eq!(
+            code_at(RFC_SECRET, TotpParams::default(), 1111111109)
+                .unwrap()
+                .0,
+            "081804".to_string()
+        );
+        assert_eq!(
+            code_at(RFC_SECRET, TotpParams::default(), 1234567890).unwrap(),
+            ("005924".to_string(), 30)
+        );
+    }
+
+    #[test]
+    fn matches_rfc_6238_vectors_for_other_algorithms() {
+        let sha1 = params(TotpAlgorithm::Sha1, 8);
+        let sha256 = params(TotpAlgorithm::Sha256, 8);
+        let sha512 = params(TotpAlgorithm::Sha512, 8);
+
+        assert_eq!(code_at(RFC_SECRET, sha1, 59).unwrap().0, "94287082");
+        assert_eq!(
+            code_at(RFC_SECRET_SHA256, sha256, 59).unwrap().0,
+            "46119246"
+        );
+        assert_eq!(
+            code_at(RFC_SECRET_SHA512, sha512, 59).unwrap().0,
+            "90693936"
+        );
+        assert_eq!(
+            code_at(RFC_SECRET_SHA256, sha256, 1111111109).unwrap().0,
+            "68084774"
+        );
+        assert_eq!(
+            code_at(RFC_SECRET_SHA512, sha512, 1111111109).unwrap().0,
+            "25091201"
+        );
+    }
+
+    #[test]
+    fn custom_period_changes_the_remaining_time() {
+        let sixty = TotpParams {
+            period: 60,
+            ..TotpParams::default()
+        };
+
+        assert_eq!(code_at(RFC_SECRET, sixty, 59).unwrap().1, 1);
+        assert_eq!(code_at(RFC_SECRET, sixty, 61).unwrap().1, 59);
+        assert_eq!(
+            code_at(RFC_SECRET, sixty, 59).unwrap().0,
+            code_at(RFC_SECRET, sixty, 0).unwrap().0
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_params() {
+        assert!(params(TotpAlgorithm::Sha1, 5).validate().is_err());
+        assert!(params(TotpAlgorithm::Sha1, 9).validate().is_err());
+        let zero_period = TotpParams {
+            period: 0,
+            ..TotpParams::default()
+        };
+        assert!(code_at(RFC_SECRET, zero_period, 59).is_err());
+    }
+
+    #[test]
+    fn normalizes_spaced_lowercase_secrets() {
+        assert_eq!(normalize_secret("gezd gnbv gy3t qojq"), "GEZDGNBVGY3TQOJQ");
+        assert_eq!(
+            code_at(
+                "gezd gnbv gy3t qojq gezd gnbv gy3t qojq",
+                TotpParams::default(),
+                59
+            )
+            .unwrap(),
+            code_at(RFC_SECRET, TotpParams::default(), 59).unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_base32() {
+        assert!(validate_secret("not base32!").is_err());
+        assert!(validate_secret("").is_err());
+        assert!(validate_secret("JBSWY3DPEHPK3PXP").is_ok());
+    }
+
+    #[test]
+    fn provisioning_uri_encodes_labels() {
+        let uri = provisioning_uri(
+            "jbsw y3dp",
+            TotpParams::default(),
+            "Acme Co",
+            "alice@example.com",
+        );
+
+        assert_eq!(
+            uri,
+            "otpauth://totp/Acme%20Co:alice%40example.com?secret=JBSWY3DP&issuer=Acme%20Co&algorithm=SHA1&digits=6&period=30"
+        );
+        assert!(
+            provisioning_uri("JBSWY3DP", params(TotpAlgorithm::Sha256, 8), "a", "b")
+                .ends_with("&algorithm=SHA256&digits=8&period=30")
+        );
+    }
+
+    #[test]
+    fn parses_otpauth_uri_with_all_parameters() {
+        let otp = parse_otpauth_uri(
+            "otpauth://totp/Acme%20Co:alice%40example.com?secret=jbswy3dp&issuer=Acme%20Co&algorithm=sha256&digits=8&period=60&image=x",
+        )
+        .expect("should parse");
+
+        assert_eq!(otp.secret, "JBSWY3DP");
+        assert_eq!(otp.issuer.as_deref(), Some("Acme Co"));
+        assert_eq!(otp.account.as_deref(), Some("alice@example.com"));
+        assert_eq!(
+            otp.params,
+            TotpParams {
+                algorithm: TotpAlgorithm::Sha256,
+                digits: 8,
+                period: 60,
+            }
+        );
+    }
+
+    #[test]
+    fn otpauth_uri_defaults_and_round_trip() {
+        let bare = parse_otpauth_uri("otpauth://totp/alice?secret=JBSWY3DP").expect("bare");
+        assert_eq!(bare.issuer, None);
+        assert_eq!(bare.account.as_deref(), Some("alice"));
+        assert!(bare.params.is_default());
+
+        let custom = params(TotpAlgorithm::Sha512, 7);
+        let uri = provisioning_uri("JBSWY3DP", custom, "Acme Co", "bob+2fa@example.com");
+        let parsed = parse_otpauth_uri(&uri).expect("round trip");
+        assert_eq!(parsed.params, custom);
+        assert_eq!(parsed.account.as_deref(), Some("bob+2fa@example.com"));
+    }
+
+    #[test]
+    fn rejects_unusable_otpauth_uris() {
+        for uri in [
+            "https://example.com/?secret=JBSWY3DP",
+            "otpauth://hotp/a?secret=JBSWY3DP&counter=1",
+            "otpauth-migration://offline?data=abc",
+            "otpauth://totp/a",
+            "otpauth://totp/a?secret=not-base32!",
+            "otpauth://totp/a?secret=JBSWY3DP&digits=10",
+            "otpauth://totp/a?secret=JBSWY3DP&algorithm=MD5",
+        ] {
+            assert!(parse_otpauth_uri(uri).is_err(), "{uri}");
+        }
+    }
+}